@@ -0,0 +1,337 @@
+use super::*;
+use std::collections::VecDeque;
+use std::fmt;
+
+/* What the cpu sees memory through. memory::Mem is the usual 4K of chip8
+ * memory, the others here either give more room or sit in front of
+ * another bus to watch what the program does with it. Only the byte
+ * accessors have to be written, the rest is built on them. Every address
+ * from 0 up to size() can be read and written, unless the bus says
+ * otherwise, anything past that is an error */
+pub trait Bus {
+    /* Byte @ ADDRESS */
+    fn read8(&mut self, address : usize) -> Result<u8, BusError>;
+
+    /* Write DATA @ ADDRESS */
+    fn write8(&mut self, address : usize, data : u8) -> Result<(), BusError>;
+
+    /* Write DATA @ ADDRESS for the interpreter, like when it loads the font.
+     * Only differs from write8 on buses that keep the program out of places */
+    fn poke(&mut self, address : usize, data : u8) -> Result<(), BusError> {
+        return self.write8(address, data);
+    }
+
+    /* Byte @ ADDRESS for the interpreter, like when the hex view draws it.
+     * Unlike read8, buses watching the program don't see it */
+    fn peek(&self, address : usize) -> Result<u8, BusError>;
+
+    /* Number of addressable bytes, from 0 */
+    fn size(&self) -> usize;
+
+    /* Back to how it was made, fonts loaded and no program */
+    fn reset(&mut self);
+
+    /* 16 bits @ ADDRESS, big endian like instructions are */
+    fn read16(&mut self, address : usize) -> Result<[u8; 2], BusError> {
+        return Ok([self.read8(address)?, self.read8(address + 1)?]);
+    }
+
+    /* Write 16 bits @ ADDRESS, big endian */
+    fn write16(&mut self, address : usize, data : [u8; 2]) -> Result<(), BusError> {
+        self.write8(address, data[0])?;
+        return self.write8(address + 1, data[1]);
+    }
+
+    /* Accesses the bus kept track of, oldest first. Only a Logger keeps any */
+    fn log(&self) -> Vec<Access> {
+        return vec![];
+    }
+
+    /* Write a game file to memory. Anything that doesn't fit gets cut off,
+     * the rom loader should have caught it */
+    fn store_game(&mut self, contents : Vec<u8>) {
+        let room : usize = self.size().saturating_sub(memory::_PROG_START);
+        for (i, byte) in contents.into_iter().take(room).enumerate() {
+            if self.write8(memory::_PROG_START + i, byte).is_err() {
+                break;
+            }
+        }
+    }
+}
+
+/* Accesses that didn't happen */
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum BusError {
+    OutOfRange(usize), /* Address past the end of memory */
+    ReadOnly(usize), /* Address that can't be written */
+}
+
+impl fmt::Display for BusError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BusError::OutOfRange(address) => write!(f, "address {:#05x} is past the end of memory", address),
+            BusError::ReadOnly(address) => write!(f, "address {:#05x} can't be written", address),
+        }
+    }
+}
+
+/* Checks ADDRESS is below SIZE, for buses backed by a plain array */
+pub fn check_range(address : usize, size : usize) -> Result<usize, BusError> {
+    if address >= size {
+        return Err(BusError::OutOfRange(address));
+    }
+    return Ok(address);
+}
+
+/* Size of the usual chip8 address space */
+pub const CHIP8_SIZE: usize = 0x1000;
+
+/* Size of the XO-CHIP address space */
+pub const XO_SIZE: usize = 0x10000;
+
+/* Names of the memory sizes, for the command line and files */
+pub const MEMORY_NAMES: [&str; 2] = ["4k", "64k"];
+
+/* Most accesses a Logger holds on to */
+pub const LOG_SIZE: usize = 4096;
+
+/* 64K of memory like XO-CHIP has, laid out like memory::Mem below 4K */
+#[derive(Clone)]
+pub struct XoMem {
+    pub data : Vec<u8>,
+}
+
+/* A bus that can be cloned out of a box, so the frontends can pick the bus
+ * when they start and still take save states */
+pub trait AnyBus : Bus {
+    fn clone_box(&self) -> Box<dyn AnyBus>;
+}
+
+/* The bus main runs the cpu on, put together from the options */
+pub type Boxed = Box<dyn AnyBus>;
+
+/* One read or write, as seen by a Logger */
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+pub struct Access {
+    pub address : usize,
+    pub data : u8, /* Byte read, or the one being written */
+    pub write : bool,
+}
+
+/* Passes everything through to INNER, and keeps the last LOG_SIZE
+ * accesses so they can be looked at afterwards */
+#[derive(Clone)]
+pub struct Logger<B : Bus> {
+    pub inner : B,
+    pub log : VecDeque<Access>, /* Oldest first */
+}
+
+/* Passes everything through to INNER, except writes into the interpreter
+ * area (below 0x200), which a real VIP would have crashed on. Those are
+ * refused with BusError::ReadOnly, so the cpu stops right there */
+#[derive(Clone)]
+pub struct Guard<B : Bus> {
+    pub inner : B,
+}
+
+pub fn make_xo_memory() -> XoMem {
+    let mut m = XoMem { data: vec![0x0; XO_SIZE] };
+    m.reset();
+    return m;
+}
+
+pub fn make_logger<B : Bus>(inner : B) -> Logger<B> {
+    let l = Logger { inner: inner, log: VecDeque::new() };
+    return l;
+}
+
+pub fn make_guard<B : Bus>(inner : B) -> Guard<B> {
+    let g = Guard { inner: inner };
+    return g;
+}
+
+/* SIZE bytes of memory, CHIP8_SIZE or XO_SIZE. Behind a Guard if PROTECT,
+ * and a Logger if LOG */
+pub fn make_bus(size : usize, protect : bool, log : bool) -> Boxed {
+    let mut b : Boxed = match size {
+        XO_SIZE => Box::new(make_xo_memory()),
+        _ => Box::new(memory::make_memory()),
+    };
+    if protect {
+        b = Box::new(make_guard(b));
+    }
+    if log {
+        b = Box::new(make_logger(b));
+    }
+    return b;
+}
+
+/* Memory size called NAME, None if there isn't one */
+pub fn parse_memory(name : &str) -> Option<usize> {
+    match name {
+        "4k" => return Some(CHIP8_SIZE),
+        "64k" => return Some(XO_SIZE),
+        _ => return None,
+    }
+}
+
+/* Name of the memory SIZE, what parse_memory takes */
+pub fn memory_name(size : usize) -> &'static str {
+    if size == XO_SIZE {
+        return "64k";
+    }
+    return "4k";
+}
+
+impl<B : Bus + Clone + 'static> AnyBus for B {
+    fn clone_box(&self) -> Box<dyn AnyBus> {
+        return Box::new(self.clone());
+    }
+}
+
+impl Clone for Boxed {
+    fn clone(&self) -> Boxed {
+        return self.as_ref().clone_box();
+    }
+}
+
+impl Bus for Boxed {
+    fn read8(&mut self, address : usize) -> Result<u8, BusError> {
+        return self.as_mut().read8(address);
+    }
+
+    fn write8(&mut self, address : usize, data : u8) -> Result<(), BusError> {
+        return self.as_mut().write8(address, data);
+    }
+
+    fn poke(&mut self, address : usize, data : u8) -> Result<(), BusError> {
+        return self.as_mut().poke(address, data);
+    }
+
+    fn peek(&self, address : usize) -> Result<u8, BusError> {
+        return self.as_ref().peek(address);
+    }
+
+    fn size(&self) -> usize {
+        return self.as_ref().size();
+    }
+
+    fn reset(&mut self) {
+        self.as_mut().reset();
+    }
+
+    fn log(&self) -> Vec<Access> {
+        return self.as_ref().log();
+    }
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        let what : &str = if self.write { "write" } else { "read" };
+        return write!(f, "{} {:#05x} = {:#04x}", what, self.address, self.data);
+    }
+}
+
+impl Bus for XoMem {
+    fn read8(&mut self, address : usize) -> Result<u8, BusError> {
+        return self.peek(address);
+    }
+
+    fn peek(&self, address : usize) -> Result<u8, BusError> {
+        return Ok(self.data[check_range(address, self.data.len())?]);
+    }
+
+    fn write8(&mut self, address : usize, data : u8) -> Result<(), BusError> {
+        let address : usize = check_range(address, self.data.len())?;
+        self.data[address] = data;
+        return Ok(());
+    }
+
+    fn size(&self) -> usize {
+        return self.data.len();
+    }
+
+    fn reset(&mut self) {
+        let low : memory::Mem = memory::make_memory();
+        self.data.fill(0);
+        self.data[..low.data.len()].copy_from_slice(&low.data);
+    }
+}
+
+impl<B : Bus> Logger<B> {
+    fn record(&mut self, access : Access) {
+        if self.log.len() == LOG_SIZE {
+            self.log.pop_front();
+        }
+        self.log.push_back(access);
+    }
+}
+
+impl<B : Bus> Bus for Logger<B> {
+    fn read8(&mut self, address : usize) -> Result<u8, BusError> {
+        let data : u8 = self.inner.read8(address)?;
+        self.record(Access { address: address, data: data, write: false });
+        return Ok(data);
+    }
+
+    fn write8(&mut self, address : usize, data : u8) -> Result<(), BusError> {
+        self.inner.write8(address, data)?;
+        self.record(Access { address: address, data: data, write: true });
+        return Ok(());
+    }
+
+    fn peek(&self, address : usize) -> Result<u8, BusError> {
+        return self.inner.peek(address);
+    }
+
+    fn size(&self) -> usize {
+        return self.inner.size();
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.log.clear();
+    }
+
+    fn log(&self) -> Vec<Access> {
+        return self.log.iter().copied().collect();
+    }
+}
+
+impl<B : Bus> Bus for Guard<B> {
+    fn read8(&mut self, address : usize) -> Result<u8, BusError> {
+        return self.inner.read8(address);
+    }
+
+    fn write8(&mut self, address : usize, data : u8) -> Result<(), BusError> {
+        if address < memory::_PROG_START {
+            return Err(BusError::ReadOnly(address));
+        }
+        return self.inner.write8(address, data);
+    }
+
+    fn poke(&mut self, address : usize, data : u8) -> Result<(), BusError> {
+        return self.inner.poke(address, data);
+    }
+
+    fn peek(&self, address : usize) -> Result<u8, BusError> {
+        return self.inner.peek(address);
+    }
+
+    fn size(&self) -> usize {
+        return self.inner.size();
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    fn log(&self) -> Vec<Access> {
+        return self.inner.log();
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,95 @@
+use super::*;
+
+#[test]
+fn test_bus_mem() {
+    let mut m : memory::Mem = memory::make_memory();
+    assert_eq!(m.size(), 4096);
+    m.store_game(vec![0xab; 8192]);
+    assert_eq!(m.read8(0xfff), Ok(0xab));
+    m.reset();
+    assert_eq!(m.data, memory::make_memory().data);
+}
+
+#[test]
+fn test_bus_xo_memory() {
+    let mut m : XoMem = make_xo_memory();
+    assert_eq!(m.size(), XO_SIZE);
+    assert_eq!(m.data[..4096], memory::make_memory().data[..]);
+
+    /* Programs can be much bigger */
+    m.store_game(vec![0xab; 0x8000]);
+    assert_eq!(m.read8(0x81ff), Ok(0xab));
+    assert_eq!(m.read8(0x8200), Ok(0x00));
+    assert_eq!(m.write8(0xffff, 0x01), Ok(()));
+    assert_eq!(m.write8(0x10000, 0x01), Err(BusError::OutOfRange(0x10000)));
+    m.reset();
+    assert_eq!(m.read8(0xffff), Ok(0x00));
+    assert_eq!(m.read8(memory::_PROG_START), Ok(0x00));
+}
+
+#[test]
+fn test_bus_logger() {
+    let mut l : Logger<memory::Mem> = make_logger(memory::make_memory());
+    l.write8(0x300, 0x42).unwrap();
+    assert_eq!(l.read8(0x300), Ok(0x42));
+    assert!(l.read8(0x1000).is_err()); /* Didn't happen, so isn't logged */
+    assert_eq!(l.peek(0x300), Ok(0x42)); /* Not the program's, so isn't logged either */
+    assert_eq!(l.log, vec![Access { address: 0x300, data: 0x42, write: true },
+                           Access { address: 0x300, data: 0x42, write: false }]);
+    assert_eq!(l.log[0].to_string(), "write 0x300 = 0x42");
+
+    /* Only the latest are kept */
+    for i in 0..LOG_SIZE {
+        l.read8(i).unwrap();
+    }
+    assert_eq!(l.log.len(), LOG_SIZE);
+    assert!(l.log.iter().all(|a| !a.write));
+    l.reset();
+    assert!(l.log.is_empty());
+
+    /* --debug printing the instruction doesn't fetch it a second time */
+    let mut c : cpu::CPU<Logger<memory::Mem>> = cpu::make_cpu(make_logger(memory::make_memory()));
+    c.mem.store_game(vec![0x60, 0x07]); /* V0 = 7 */
+    c.mem.log.clear();
+    c.debug = true;
+    c.exec(0xf0);
+    assert_eq!(c.mem.log, vec![Access { address: 0x200, data: 0x60, write: false },
+                               Access { address: 0x201, data: 0x07, write: false }]);
+}
+
+#[test]
+fn test_bus_guard() {
+    let mut g : Guard<memory::Mem> = make_guard(memory::make_memory());
+    assert_eq!(g.write8(memory::_FONT_START, 0xff), Err(BusError::ReadOnly(memory::_FONT_START)));
+    assert_eq!(g.read8(memory::_FONT_START), Ok(memory::FONTS[0]));
+    assert_eq!(g.write8(memory::_PROG_START, 0xff), Ok(()));
+
+    /* A program poking the interpreter area gets stopped right there */
+    let mut c : cpu::CPU<Guard<memory::Mem>> = cpu::make_cpu(make_guard(memory::make_memory()));
+    c.mem.store_game(vec![0x60, 0x07, 0xA1, 0x00, 0xF0, 0x55, 0x12, 0x06]); /* V0 = 7, I = 0x100, store V0 */
+    assert!(!c.run_frame(4, 0xf0));
+    assert_eq!(c.pc, 0x206);
+    assert_eq!(c.fault, Some(cpu::Fault::Bus(BusError::ReadOnly(0x100))));
+}
+
+#[test]
+fn test_bus_boxed() {
+    let mut b : Boxed = make_bus(parse_memory("64k").unwrap(), true, true);
+    assert_eq!(b.size(), XO_SIZE);
+    assert_eq!(b.write8(0x100, 0x01), Err(BusError::ReadOnly(0x100)));
+    assert_eq!(b.write8(0x8000, 0x01), Ok(()));
+    assert_eq!(b.peek(0x8000), Ok(0x01));
+    assert_eq!(b.log(), vec![Access { address: 0x8000, data: 0x01, write: true }]);
+
+    /* Clones don't share memory */
+    let mut copy : Boxed = b.clone();
+    copy.write8(0x8000, 0x02).unwrap();
+    assert_eq!(b.read8(0x8000), Ok(0x01));
+
+    /* Plain memory keeps no log */
+    let mut plain : Boxed = make_bus(CHIP8_SIZE, false, false);
+    assert_eq!(plain.size(), CHIP8_SIZE);
+    assert_eq!(plain.write8(0x100, 0x01), Ok(()));
+    assert!(plain.log().is_empty());
+    assert_eq!(parse_memory("1k"), None);
+}
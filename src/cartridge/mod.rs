@@ -0,0 +1,267 @@
+use super::*;
+use std::borrow::Cow;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/* Octo cartridges. Octo shares programs as gifs with the program hidden in
+ * the pixels: the low 2 bits of every pixel's palette index, 4 pixels to a
+ * byte, high bits first, running on from one frame to the next. The palette
+ * has every colour 4 times over, so the data doesn't show. The bytes are a
+ * 4 byte big endian length, then that much json:
+ *
+ *   {"program": "<octo source>", "options": {"tickrate": 20, ...}}
+ *
+ * The program is octo source, not a rom. There's no octo assembler here, so
+ * only programs that are nothing but byte literals (what Octo exports a rom
+ * as, and what we write out) can be loaded. Of the options only the ones
+ * we have a use for are read, the rest are ignored */
+
+/* Size of each frame of the gif we write, same as Octo's */
+pub const WIDTH: u16 = 128;
+pub const HEIGHT: u16 = 64;
+
+/* Room Octo gives a plain chip8 program */
+pub const OCTO_MAX_SIZE: usize = 3584;
+
+/* What's inside a cartridge */
+#[derive(Deserialize, Serialize)]
+pub struct Payload {
+    pub program : String, /* Octo source */
+    #[serde(default)]
+    pub options : Options,
+}
+
+/* Octo's runtime options, None where the cartridge didn't say */
+#[derive(Debug)]
+#[derive(Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Options {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tickrate : Option<u16>, /* Instructions per frame */
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_color : Option<String>, /* Lit pixels, as #rrggbb */
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background_color : Option<String>, /* Background, as #rrggbb */
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub v_blank_quirks : Option<bool>, /* Same as our display_wait */
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clip_quirks : Option<bool>, /* Sprites get clipped, so wrap_sprites is the opposite */
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_size : Option<usize>, /* Biggest the program can be */
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub font_style : Option<String>, /* Our font names, except Octo calls chip48 octo */
+}
+
+/* Things that can go wrong reading a cartridge */
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum CartridgeError {
+    Gif(String), /* Not a gif we can read */
+    Truncated, /* Ran out of pixels before the payload ended */
+    Json(String), /* Payload isn't the json we expected */
+    NeedsAssembler(usize, String), /* Line, token. Source that needs a real octo assembler */
+}
+
+impl fmt::Display for CartridgeError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CartridgeError::Gif(err) => write!(f, "couldn't read cartridge gif: {}", err),
+            CartridgeError::Truncated => write!(f, "cartridge ends part way through its program"),
+            CartridgeError::Json(err) => write!(f, "bad cartridge payload: {}", err),
+            CartridgeError::NeedsAssembler(line, token) =>
+                write!(f, "line {} of the cartridge's program ({:?}) needs the octo assembler, \
+                           only byte literals can be loaded", line, token),
+        }
+    }
+}
+
+impl Options {
+    /* What the options would set, as if they came from the config file */
+    pub fn settings(&self) -> config::Settings {
+        let mut out : config::Settings = config::make_settings();
+        out.speed = self.tickrate.filter(|t| *t > 0);
+        if self.v_blank_quirks.is_some() || self.clip_quirks.is_some() {
+            let mut quirks : cpu::Quirks = cpu::make_quirks();
+            quirks.display_wait = self.v_blank_quirks.unwrap_or(false);
+            quirks.wrap_sprites = !self.clip_quirks.unwrap_or(false);
+            out.quirks = Some(quirks);
+        }
+        out.font = match self.font_style.as_deref() {
+            Some("octo") => Some(font::Style::Chip48),
+            Some(name) => font::parse_style(name),
+            None => None,
+        };
+        if let (Some(on), Some(off)) = (&self.fill_color, &self.background_color) {
+            out.palette = display::parse_palette(&format!("{},{}", on, off));
+        }
+        return out;
+    }
+}
+
+/* Options for a cartridge out of SETTINGS, what Octo should run the program with */
+pub fn make_options(settings : &config::Settings) -> Options {
+    let hex = |c : [u8; 3]| format!("#{:02X}{:02X}{:02X}", c[0], c[1], c[2]);
+    let quirks : cpu::Quirks = settings.quirks.unwrap_or(cpu::make_quirks());
+    let palette : display::Palette = settings.palette.unwrap_or(display::make_palette());
+    let o = Options {
+        tickrate: Some(settings.speed.unwrap_or(scheduler::DEFAULT_IPF)),
+        fill_color: Some(hex(palette.on)),
+        background_color: Some(hex(palette.off)),
+        v_blank_quirks: Some(quirks.display_wait),
+        clip_quirks: Some(!quirks.wrap_sprites),
+        max_size: Some(OCTO_MAX_SIZE),
+        font_style: match settings.font.unwrap_or(font::Style::Chip48) {
+            font::Style::Chip48 => Some(String::from("octo")),
+            style => Some(style.name().to_string()),
+        },
+    };
+    return o;
+}
+
+/* Pull the hidden bytes out of the cartridge gif in DATA */
+pub fn extract(data : &[u8]) -> Result<Vec<u8>, CartridgeError> {
+    let gif_err = |err : gif::DecodingError| CartridgeError::Gif(err.to_string());
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::Indexed);
+    let mut decoder = options.read_info(data).map_err(gif_err)?;
+
+    /* 2 bits out of every pixel, until we know how long it is and have that much */
+    let mut bytes : Vec<u8> = vec![];
+    let mut byte : u8 = 0;
+    let mut bits : u8 = 0;
+    let mut len : Option<usize> = None;
+    while let Some(frame) = decoder.read_next_frame().map_err(gif_err)? {
+        for index in frame.buffer.iter() {
+            byte = (byte << 2) | (index & 0x3);
+            bits += 2;
+            if bits < 8 {
+                continue;
+            }
+            bytes.push(byte);
+            byte = 0;
+            bits = 0;
+
+            if bytes.len() == 4 {
+                len = Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize);
+            }
+            if let Some(n) = len {
+                if bytes.len() == 4 + n {
+                    return Ok(bytes.split_off(4));
+                }
+            }
+        }
+    }
+    return Err(CartridgeError::Truncated);
+}
+
+/* Read the payload out of the cartridge gif in DATA */
+pub fn decode(data : &[u8]) -> Result<Payload, CartridgeError> {
+    let json : Vec<u8> = extract(data)?;
+    return serde_json::from_slice(&json).map_err(|err| CartridgeError::Json(err.to_string()));
+}
+
+/* Octo source for the rom in DATA, as byte literals like Octo exports it */
+pub fn disassemble(data : &[u8]) -> String {
+    let mut out : String = String::from(": main\n");
+    for line in data.chunks(16) {
+        let bytes : Vec<String> = line.iter().map(|b| format!("0x{:02X}", b)).collect();
+        out.push_str(&bytes.join(" "));
+        out.push('\n');
+    }
+    return out;
+}
+
+/* Make a cartridge gif holding the rom in DATA, to be run with OPTIONS. The
+ * gif shows a frame in the fill colour, the program goes in underneath */
+pub fn encode(data : &[u8], options : Options) -> Result<Vec<u8>, CartridgeError> {
+    let gif_err = |err : gif::EncodingError| CartridgeError::Gif(err.to_string());
+    let payload = Payload { program: disassemble(data), options: options };
+    let json : String = serde_json::to_string(&payload).map_err(|err| CartridgeError::Json(err.to_string()))?;
+
+    let mut bytes : Vec<u8> = (json.len() as u32).to_be_bytes().to_vec();
+    bytes.extend_from_slice(json.as_bytes());
+
+    /* Colours 0-3 are the background, 4-7 the fill colour */
+    let colours : String = format!("{},{}", payload.options.fill_color.as_deref().unwrap_or(""),
+                                   payload.options.background_color.as_deref().unwrap_or(""));
+    let colours : display::Palette = display::parse_palette(&colours).unwrap_or(display::make_palette());
+    let mut palette : Vec<u8> = vec![];
+    for group in [colours.off, colours.on, colours.off, colours.off] {
+        for _ in 0..4 {
+            palette.extend_from_slice(&group);
+        }
+    }
+
+    /* Every frame holds WIDTH * HEIGHT / 4 bytes */
+    let (w, h) : (usize, usize) = (WIDTH as usize, HEIGHT as usize);
+    let mut out : Vec<u8> = vec![];
+    {
+        let mut encoder = gif::Encoder::new(&mut out, WIDTH, HEIGHT, &palette).map_err(gif_err)?;
+        for chunk in bytes.chunks(w * h / 4) {
+            let mut pixels : Vec<u8> = vec![0; w * h];
+            for (i, pixel) in pixels.iter_mut().enumerate() {
+                let (x, y) : (usize, usize) = (i % w, i / w);
+                if x == 0 || y == 0 || x == w - 1 || y == h - 1 {
+                    *pixel = 4;
+                }
+                if let Some(b) = chunk.get(i / 4) {
+                    *pixel |= (b >> (6 - 2 * (i % 4))) & 0x3;
+                }
+            }
+            let frame = gif::Frame {
+                width: WIDTH,
+                height: HEIGHT,
+                delay: 10,
+                buffer: Cow::Owned(pixels),
+                ..gif::Frame::default()
+            };
+            encoder.write_frame(&frame).map_err(gif_err)?;
+        }
+    }
+    return Ok(out);
+}
+
+/* Parse an octo number literal: 12, -3, 0xff, 0b1010 */
+fn parse_byte(token : &str) -> Option<u8> {
+    let (negative, digits) : (bool, &str) = match token.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, token),
+    };
+    let n : i32 = if let Some(hex) = digits.strip_prefix("0x") {
+        i32::from_str_radix(hex, 16).ok()?
+    } else if let Some(bin) = digits.strip_prefix("0b") {
+        i32::from_str_radix(bin, 2).ok()?
+    } else {
+        digits.parse().ok()?
+    };
+    let n : i32 = if negative { -n } else { n };
+    if !(-128..=255).contains(&n) {
+        return None;
+    }
+    return Some(n as u8);
+}
+
+/* Turn octo SOURCE made of byte literals into a rom. Label definitions
+ * (": main") and comments are fine, anything else needs the real assembler */
+pub fn assemble(source : &str) -> Result<Vec<u8>, CartridgeError> {
+    let mut rom : Vec<u8> = vec![];
+    for (i, line) in source.lines().enumerate() {
+        let code : &str = line.split('#').next().unwrap_or("");
+        let mut tokens = code.split_whitespace();
+        while let Some(token) = tokens.next() {
+            if token == ":" {
+                tokens.next();
+                continue;
+            }
+            match parse_byte(token) {
+                Some(b) => rom.push(b),
+                None => return Err(CartridgeError::NeedsAssembler(i + 1, token.to_string())),
+            }
+        }
+    }
+    return Ok(rom);
+}
+
+#[cfg(test)]
+mod test;
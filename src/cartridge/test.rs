@@ -0,0 +1,108 @@
+use super::*;
+use std::borrow::Cow;
+
+/* Hide BYTES in a WIDTH x HEIGHT gif the way octo does, one frame per screenful */
+fn make_gif(bytes : &[u8], width : u16, height : u16) -> Vec<u8> {
+    let mut pixels : Vec<u8> = vec![];
+    for b in bytes.iter() {
+        for shift in [6, 4, 2, 0] {
+            pixels.push(4 + ((b >> shift) & 0x3)); /* Not palette 0, only the low bits count */
+        }
+    }
+    let size : usize = width as usize * height as usize;
+    pixels.resize(pixels.len().div_ceil(size).max(1) * size, 0);
+
+    let palette : Vec<u8> = (0..16).flat_map(|i| [i * 16, i * 16, i * 16]).collect();
+    let mut out : Vec<u8> = vec![];
+    {
+        let mut encoder = gif::Encoder::new(&mut out, width, height, &palette).unwrap();
+        for chunk in pixels.chunks(size) {
+            let frame = gif::Frame {
+                width: width,
+                height: height,
+                buffer: Cow::Borrowed(chunk),
+                ..gif::Frame::default()
+            };
+            encoder.write_frame(&frame).unwrap();
+        }
+    }
+    return out;
+}
+
+/* Length prefixed, like the payload is stored */
+fn with_len(json : &str) -> Vec<u8> {
+    let mut out : Vec<u8> = (json.len() as u32).to_be_bytes().to_vec();
+    out.extend_from_slice(json.as_bytes());
+    return out;
+}
+
+#[test]
+fn test_cartridge_extract() {
+    let json : &str = r#"{"program": "0x12 0x00", "options": {"tickrate": 20}}"#;
+
+    /* Spread over several small frames */
+    let gif : Vec<u8> = make_gif(&with_len(json), 8, 4);
+    assert_eq!(extract(&gif).unwrap(), json.as_bytes());
+    assert_eq!(decode(&gif).unwrap().program, "0x12 0x00");
+
+    /* Says it's longer than it is */
+    let mut short : Vec<u8> = with_len(json);
+    short[3] += 100;
+    assert_eq!(extract(&make_gif(&short, 8, 4)), Err(CartridgeError::Truncated));
+
+    assert!(matches!(decode(&make_gif(&with_len("nope"), 8, 4)), Err(CartridgeError::Json(_))));
+    assert!(matches!(extract(b"GIF89a"), Err(CartridgeError::Gif(_))));
+}
+
+#[test]
+fn test_cartridge_assemble() {
+    let source : &str = ": main\n\
+                         0x00 0xE0 # clear\n\
+                         \n\
+                         0b00010010 2 -1 255\n";
+    assert_eq!(assemble(source).unwrap(), vec![0x00, 0xE0, 0x12, 0x02, 0xFF, 0xFF]);
+    assert!(assemble("").unwrap().is_empty());
+
+    assert_eq!(assemble(": main\n  clear\n"), Err(CartridgeError::NeedsAssembler(2, String::from("clear"))));
+    assert_eq!(assemble("256"), Err(CartridgeError::NeedsAssembler(1, String::from("256"))));
+    assert_eq!(assemble("0x12 v0"), Err(CartridgeError::NeedsAssembler(1, String::from("v0"))));
+}
+
+#[test]
+fn test_cartridge_round_trip() {
+    /* Big enough to need a few frames */
+    let rom : Vec<u8> = (0..3000).map(|i| (i * 7) as u8).collect();
+    let mut settings : config::Settings = config::make_settings();
+    settings.speed = Some(30);
+    settings.quirks = Some(cpu::Quirks { display_wait: true, wrap_sprites: true, ..cpu::make_quirks() });
+    settings.palette = display::parse_palette("ffb000,202020");
+    settings.font = Some(font::Style::Vip);
+
+    let gif : Vec<u8> = encode(&rom, make_options(&settings)).unwrap();
+    assert!(gif.starts_with(b"GIF89a"));
+    let payload : Payload = decode(&gif).unwrap();
+    assert!(payload.program.starts_with(": main\n0x00 0x07 0x0E "));
+    assert_eq!(assemble(&payload.program).unwrap(), rom);
+    assert_eq!(payload.options.max_size, Some(OCTO_MAX_SIZE));
+    assert_eq!(payload.options.settings(), settings);
+}
+
+#[test]
+fn test_cartridge_options() {
+    let options : Options = serde_json::from_str(r##"{
+        "tickrate": 15, "fillColor": "#FFCC00", "backgroundColor": "#996600",
+        "clipQuirks": true, "shiftQuirks": false, "fontStyle": "octo"
+    }"##).unwrap();
+    let s : config::Settings = options.settings();
+    assert_eq!(s.speed, Some(15));
+    assert_eq!(s.font, Some(font::Style::Chip48));
+    assert_eq!(s.quirks, Some(cpu::make_quirks()));
+    assert_eq!(s.palette.unwrap().on, [0xff, 0xcc, 0x00]);
+    assert_eq!(s.palette.unwrap().off, [0x99, 0x66, 0x00]);
+
+    /* Octo wraps sprites unless told to clip them */
+    let options : Options = serde_json::from_str(r#"{"vBlankQuirks": true}"#).unwrap();
+    assert_eq!(options.settings().quirks, Some(cpu::Quirks { display_wait: true, wrap_sprites: true, ..cpu::make_quirks() }));
+
+    assert_eq!(Options::default().settings(), config::make_settings());
+}
@@ -0,0 +1,282 @@
+use super::*;
+use cpu::CPU;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/* Cheats. A search narrows memory down to the bytes that changed the way
+ * a variable would (lives going down when one is lost, the score going
+ * up), and cheats then hold bytes where they are or keep writing bytes of
+ * our own, every frame. Cheats are kept by the rom's sha1 in a toml file,
+ * next to the config file unless --cheats says otherwise:
+ *
+ *   [[b232ef880bd6060fb45fa6effed7edf0ae95670e]]   # sha1 of the rom
+ *   name = "Lives"
+ *   address = 0x2f0
+ *   value = [0x09]      # written every frame
+ *   enabled = true
+ *
+ *   [[b232ef880bd6060fb45fa6effed7edf0ae95670e]]
+ *   name = "Timer"
+ *   address = 0x2f4
+ *   size = 2            # held at what the 2 bytes were when turned on
+ *
+ * A freeze holds nothing until it gets turned on while the game runs, with Z
+ * in the hex view or F4, so it doesn't hold memory from before the program
+ * set it up. Freezes that are holding get saved with what they hold as their
+ * value.
+ */
+
+/* File under the config directory */
+pub const CHEATS_FILE: &str = "cheats.toml";
+
+/* What a cheat does to its bytes */
+#[derive(Debug)]
+#[derive(Clone, PartialEq)]
+pub enum Kind {
+    Freeze(usize), /* Hold this many bytes at what they were when turned on */
+    Patch(Vec<u8>), /* Write these bytes */
+}
+
+/* One cheat */
+#[derive(Debug)]
+#[derive(Clone, PartialEq)]
+pub struct Cheat {
+    pub name : String, /* What it's for */
+    pub address : usize, /* First byte it touches */
+    pub kind : Kind,
+    pub enabled : bool, /* Being applied */
+    held : Option<Vec<u8>>, /* What a freeze is holding, taken when it was turned on */
+}
+
+/* Cheats for every rom in a file, by lowercase sha1 */
+pub type CheatFile = BTreeMap<String, Vec<Cheat>>;
+
+/* Ways bytes can have changed since the last look */
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Compare {
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+}
+
+/* A search in progress */
+#[derive(Debug)]
+pub struct Search {
+    pub candidates : Vec<usize>, /* Addresses that matched every comparison so far */
+    snapshot : Vec<u8>, /* Memory at the last look */
+}
+
+/* Things that can go wrong loading a cheat file */
+#[derive(Debug)]
+pub enum CheatError {
+    Io(io::Error), /* Couldn't read or write the file */
+    Parse(toml::de::Error), /* Not valid toml, or unknown fields */
+    BadValue(String, String), /* Where, what was wrong */
+}
+
+impl fmt::Display for CheatError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheatError::Io(err) => write!(f, "couldn't read cheats: {}", err),
+            CheatError::Parse(err) => write!(f, "bad cheat file: {}", err),
+            CheatError::BadValue(key, what) => write!(f, "bad value for {}: {}", key, what),
+        }
+    }
+}
+
+impl fmt::Display for Cheat {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        let state : &str = match (self.enabled, &self.kind, &self.held) {
+            (false, _, _) => "off",
+            (true, Kind::Freeze(_), None) => "waiting to be turned on",
+            (true, _, _) => "on",
+        };
+        match &self.kind {
+            Kind::Freeze(size) => write!(f, "{} ({}): freeze {} byte(s) @ {:#05x}", self.name, state, size, self.address),
+            Kind::Patch(bytes) => {
+                let hex : Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+                write!(f, "{} ({}): write {} @ {:#05x}", self.name, state, hex.join(" "), self.address)
+            },
+        }
+    }
+}
+
+/* Shape of one cheat in the file */
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CheatEntry {
+    name : Option<String>,
+    address : usize,
+    value : Option<Vec<u8>>,
+    size : Option<usize>,
+    enabled : Option<bool>,
+}
+
+/* A cheat holding SIZE bytes @ ADDRESS where they are */
+pub fn make_freeze(name : &str, address : usize, size : usize) -> Cheat {
+    let c = Cheat { name: name.to_string(), address: address, kind: Kind::Freeze(size), enabled: true, held: None };
+    return c;
+}
+
+/* A cheat writing BYTES @ ADDRESS */
+pub fn make_patch(name : &str, address : usize, bytes : Vec<u8>) -> Cheat {
+    let c = Cheat { name: name.to_string(), address: address, kind: Kind::Patch(bytes), enabled: true, held: None };
+    return c;
+}
+
+/* Where the cheat file lives when --cheats isn't given, next to the config file */
+pub fn default_path() -> Option<PathBuf> {
+    return Some(config::default_path()?.with_file_name(CHEATS_FILE));
+}
+
+/* Check ENTRY, the INDEX'th cheat for ROM */
+fn cheat(entry : CheatEntry, rom : &str, index : usize) -> Result<Cheat, CheatError> {
+    let bad = |what : &str| CheatError::BadValue(format!("{} cheat {}", rom, index + 1), what.to_string());
+    let name : String = entry.name.unwrap_or(format!("{:#05x}", entry.address));
+    let mut c : Cheat = match (entry.value, entry.size) {
+        (Some(_), Some(_)) => return Err(bad("has both a value and a size")),
+        (Some(value), None) if value.is_empty() => return Err(bad("value is empty")),
+        (Some(value), None) => make_patch(&name, entry.address, value),
+        (None, Some(0)) => return Err(bad("size is 0")),
+        (None, size) => make_freeze(&name, entry.address, size.unwrap_or(1)),
+    };
+    c.enabled = entry.enabled.unwrap_or(true);
+    return Ok(c);
+}
+
+/* Parse the cheat file in TEXT */
+pub fn parse(text : &str) -> Result<CheatFile, CheatError> {
+    let file : BTreeMap<String, Vec<CheatEntry>> = toml::from_str(text).map_err(CheatError::Parse)?;
+    let mut out : CheatFile = BTreeMap::new();
+    for (rom, entries) in file {
+        let rom : String = rom.to_lowercase();
+        for (i, entry) in entries.into_iter().enumerate() {
+            let c : Cheat = cheat(entry, &rom, i)?;
+            out.entry(rom.clone()).or_default().push(c);
+        }
+    }
+    return Ok(out);
+}
+
+/* Load the cheat file @ PATH. One that isn't there yet has no cheats in it */
+pub fn load(path : &Path) -> Result<CheatFile, CheatError> {
+    let text : String = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(err) => return Err(CheatError::Io(err)),
+    };
+    return parse(&text);
+}
+
+/* FILE as toml, what parse reads back */
+pub fn to_text(file : &CheatFile) -> String {
+    let mut out : String = String::new();
+    for (rom, cheats) in file {
+        for c in cheats {
+            out.push_str(&format!("[[{}]]\n", rom));
+            out.push_str(&format!("name = {:?}\n", c.name));
+            out.push_str(&format!("address = {:#05x}\n", c.address));
+            match (&c.kind, &c.held) {
+                (Kind::Freeze(size), None) => out.push_str(&format!("size = {}\n", size)),
+                (Kind::Freeze(_), Some(bytes)) | (Kind::Patch(bytes), _) => {
+                    let hex : Vec<String> = bytes.iter().map(|b| format!("{:#04x}", b)).collect();
+                    out.push_str(&format!("value = [{}]\n", hex.join(", ")));
+                },
+            }
+            out.push_str(&format!("enabled = {}\n\n", c.enabled));
+        }
+    }
+    return out;
+}
+
+/* Write FILE out to PATH, making the folder if it has to */
+pub fn save(path : &Path, file : &CheatFile) -> Result<(), CheatError> {
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(CheatError::Io)?;
+    }
+    return fs::write(path, to_text(file)).map_err(CheatError::Io);
+}
+
+/* Apply every enabled cheat in CHEATS to CORE's memory. Called once a
+ * frame, before the frame runs. Freezes that aren't holding anything yet
+ * are left alone. Writes go through the bus, so ones that land past the
+ * end of memory are skipped */
+pub fn apply<B : Bus>(cheats : &[Cheat], core : &mut CPU<B>) {
+    for c in cheats.iter().filter(|c| c.enabled) {
+        let bytes : &[u8] = match (&c.kind, &c.held) {
+            (Kind::Patch(bytes), _) | (Kind::Freeze(_), Some(bytes)) => bytes,
+            (Kind::Freeze(_), None) => continue,
+        };
+        for (k, b) in bytes.iter().enumerate() {
+            let _ = core.mem.write8(c.address + k, *b);
+        }
+    }
+}
+
+/* Turn on every freeze in CHEATS that's on but not holding anything yet,
+ * they hold what's in CORE's memory now */
+pub fn hold<B : Bus>(cheats : &mut [Cheat], core : &CPU<B>) {
+    for c in cheats.iter_mut().filter(|c| c.enabled && c.held.is_none()) {
+        c.hold(core);
+    }
+}
+
+/* Start a search over all of CORE's memory */
+pub fn make_search<B : Bus>(core : &CPU<B>) -> Search {
+    let size : usize = core.mem.size();
+    let s = Search {
+        candidates: (0..size).collect(),
+        snapshot: (0..size).map(|a| core.mem.peek(a).unwrap_or(0)).collect(),
+    };
+    return s;
+}
+
+impl Cheat {
+    /* Turn the cheat on or off. A freeze holds what's in CORE's memory when
+     * it's turned on, one that's on but waiting starts holding instead */
+    pub fn toggle<B : Bus>(&mut self, core : &CPU<B>) {
+        let waiting : bool = self.enabled && self.held.is_none();
+        self.enabled = !self.enabled || waiting;
+        self.held = None;
+        if self.enabled {
+            self.hold(core);
+        }
+    }
+
+    /* A freeze starts holding what's in CORE's memory now */
+    pub fn hold<B : Bus>(&mut self, core : &CPU<B>) {
+        if let Kind::Freeze(size) = self.kind {
+            let now : Vec<u8> = (self.address..self.address + size).map(|a| core.mem.peek(a).unwrap_or(0)).collect();
+            self.held = Some(now);
+        }
+    }
+}
+
+impl Search {
+    /* Keep the candidates whose byte changed the way COMPARE says since the
+     * last look, then look again. Returns how many are left */
+    pub fn narrow<B : Bus>(&mut self, core : &CPU<B>, compare : Compare) -> usize {
+        let now : Vec<u8> = (0..self.snapshot.len()).map(|a| core.mem.peek(a).unwrap_or(0)).collect();
+        let snapshot : &Vec<u8> = &self.snapshot;
+        self.candidates.retain(|a| {
+            let (before, after) : (u8, u8) = (snapshot[*a], now[*a]);
+            match compare {
+                Compare::Changed => return after != before,
+                Compare::Unchanged => return after == before,
+                Compare::Increased => return after > before,
+                Compare::Decreased => return after < before,
+            }
+        });
+        self.snapshot = now;
+        return self.candidates.len();
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,93 @@
+use super::*;
+
+const PONG: &str = "b232ef880bd6060fb45fa6effed7edf0ae95670e";
+
+#[test]
+fn test_cheat_parse() {
+    let text : String = format!("[[{}]]\nname = \"Lives\"\naddress = 0x2f0\nvalue = [9]\n\n\
+                                 [[{}]]\naddress = 0x2f4\nsize = 2\nenabled = false\n", PONG.to_uppercase(), PONG);
+    let f : CheatFile = parse(&text).unwrap();
+    let cheats : &Vec<Cheat> = &f[PONG];
+    assert_eq!(cheats.len(), 2);
+    assert_eq!(cheats[0], make_patch("Lives", 0x2f0, vec![9]));
+    assert_eq!(cheats[1].name, "0x2f4");
+    assert_eq!(cheats[1].kind, Kind::Freeze(2));
+    assert!(!cheats[1].enabled);
+
+    /* Writing it out reads back the same */
+    assert_eq!(parse(&to_text(&f)).unwrap(), f);
+
+    assert!(parse("").unwrap().is_empty());
+    assert!(matches!(parse(&format!("[[{}]]\nvalue = [1]", PONG)), Err(CheatError::Parse(_))));
+    assert!(matches!(parse(&format!("[[{}]]\naddress = 1\ncolour = 3", PONG)), Err(CheatError::Parse(_))));
+    assert!(matches!(parse(&format!("[[{}]]\naddress = 1\nvalue = [1]\nsize = 1", PONG)), Err(CheatError::BadValue(_, _))));
+    assert!(matches!(parse(&format!("[[{}]]\naddress = 1\nvalue = []", PONG)), Err(CheatError::BadValue(_, _))));
+    assert!(matches!(parse(&format!("[[{}]]\naddress = 1\nsize = 0", PONG)), Err(CheatError::BadValue(_, _))));
+}
+
+#[test]
+fn test_cheat_apply() {
+    let mut c : CPU = cpu::make_cpu(memory::make_memory());
+    c.mem.write8(0x300, 5).unwrap();
+    let mut cheats : Vec<Cheat> = vec![make_freeze("Lives", 0x300, 1), make_patch("Score", 0x310, vec![0x12, 0x34]),
+                                       make_patch("Nowhere", 0xfff, vec![1, 2])];
+
+    /* A freeze out of the file holds nothing before it's turned on */
+    apply(&cheats, &mut c);
+    c.mem.write8(0x300, 6).unwrap();
+    apply(&cheats, &mut c);
+    assert_eq!(c.mem.read8(0x300), Ok(6));
+    assert_eq!(c.mem.read16(0x310), Ok([0x12, 0x34]));
+    assert_eq!(c.mem.read8(0xfff), Ok(1));
+    assert!(format!("{}", cheats[0]).contains("waiting"));
+
+    /* Then it holds what was there when it was */
+    c.mem.write8(0x300, 5).unwrap();
+    hold(&mut cheats, &c);
+    c.mem.write8(0x300, 4).unwrap();
+    apply(&cheats, &mut c);
+    assert_eq!(c.mem.read8(0x300), Ok(5));
+
+    /* Off is off, and back on it holds the new value */
+    cheats[0].toggle(&c);
+    c.mem.write8(0x300, 3).unwrap();
+    apply(&cheats, &mut c);
+    assert_eq!(c.mem.read8(0x300), Ok(3));
+    cheats[0].toggle(&c);
+    c.mem.write8(0x300, 2).unwrap();
+    apply(&cheats, &mut c);
+    assert_eq!(c.mem.read8(0x300), Ok(3));
+
+    /* A waiting freeze starts holding on a toggle, instead of turning off */
+    let mut waiting : Cheat = make_freeze("Timer", 0x300, 1);
+    waiting.toggle(&c);
+    assert!(waiting.enabled);
+
+    /* What it holds gets saved as its value */
+    let mut f : CheatFile = BTreeMap::new();
+    f.insert(PONG.to_string(), cheats[..1].to_vec());
+    assert_eq!(parse(&to_text(&f)).unwrap()[PONG][0].kind, Kind::Patch(vec![3]));
+}
+
+#[test]
+fn test_cheat_search() {
+    let mut c : CPU = cpu::make_cpu(memory::make_memory());
+    c.mem.write8(0x300, 3).unwrap();
+    c.mem.write8(0x301, 3).unwrap();
+    let mut s : Search = make_search(&c);
+    assert_eq!(s.candidates.len(), 4096);
+
+    /* Lose a life, and the score goes up */
+    c.mem.write8(0x300, 2).unwrap();
+    c.mem.write8(0x301, 4).unwrap();
+    assert_eq!(s.narrow(&c, Compare::Changed), 2);
+    assert_eq!(s.narrow(&c, Compare::Unchanged), 2);
+
+    c.mem.write8(0x300, 1).unwrap();
+    c.mem.write8(0x301, 5).unwrap();
+    assert_eq!(s.narrow(&c, Compare::Decreased), 1);
+    assert_eq!(s.candidates, vec![0x300]);
+
+    c.mem.write8(0x300, 2).unwrap();
+    assert_eq!(s.narrow(&c, Compare::Increased), 1);
+}
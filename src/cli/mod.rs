@@ -0,0 +1,366 @@
+use super::*;
+use std::fmt;
+
+/* Command line options. Everything is optional except the rom, options that
+ * weren't given are None so a config file can fill them in */
+
+/* Printed for --help */
+pub const USAGE: &str = "\
+usage: r_chip8 [options] <rom>
+
+options:
+  --speed <ipf>           instructions per frame, 60 frames a second (default 12)
+  --quirks <list>         comma separated quirks to turn on: display_wait,
+                          wrap_sprites, wrap_memory
+  --display-wait          same as --quirks display_wait
+  --wrap-sprites          same as --quirks wrap_sprites
+  --palette <on,off>      colours as hex, e.g. ffb000,202020
+  --font <name>           chip48, vip, dream6800, eti660 or schip
+  --font-address <addr>   where the font goes in memory, e.g. 0x000 (default 0x050)
+  --stack-depth <n>       return addresses the stack holds, 12 on the VIP and 16
+                          on SCHIP (default 16)
+  --stack <mode>          what going past either end of the stack does: error,
+                          wrap, or memory for the VIP's stack at 0xEA0
+                          (default error)
+  --memory <size>         4k, or 64k like XO-CHIP (default 4k)
+  --protect               stop the program if it writes below 0x200, where the
+                          interpreter was
+  --scale <n>             window scale, 1 to 16
+  --seed <n>              seed for the random numbers, for repeatable runs
+  --keymap <name|file>    qwerty, azerty, dvorak, numpad, or a keymap file
+  --config <file>         read default options from this config file instead of
+                          $XDG_CONFIG_HOME/r_chip8/config.toml
+  --patch <file>          apply an IPS or BPS patch to the rom, give it more
+                          than once to apply several in order
+  --cheats <file>         keep cheats in this file instead of
+                          $XDG_CONFIG_HOME/r_chip8/cheats.toml
+  --mute                  don't beep
+  --headless              run without a window, needs --frames or --movie-play
+  --frames <n>            stop after n frames
+  --screenshot <file>     save a png of the screen at the end of a --headless run
+  --debug                 print every instruction as it runs, and the memory
+                          accesses before the program stopped
+  --watch                 reload the rom whenever the file changes
+  --terminal              draw in the terminal with half blocks
+  --braille               draw in the terminal with braille
+  --record <file>         record gameplay to a .gif or .y4m
+  --movie-record <file>   record the keypad into a movie
+  --movie-play <file>     play a movie back
+  --tas <script>          edit the --movie-play movie with a script and check it
+  --profile <file>        count the cycles each subroutine takes, and write a
+                          report of them and the calls between them on exit
+  --profile-folded <file> write the profile as folded stacks, for flamegraph.pl
+                          or inferno
+  --export-cartridge <gif>
+                          write the rom and its options out as an Octo cartridge
+                          and exit
+  -h, --help              print this and exit
+  -V, --version           print the version and exit
+";
+
+/* Exit code for a bad command line, or a bad file it names. Things that
+ * fail while running exit with 1 */
+pub const EXIT_USAGE: i32 = 2;
+
+/* What was asked for on the command line */
+#[derive(Debug)]
+pub struct Args {
+    pub file_path : String, /* Rom to run */
+    pub speed : Option<u16>, /* Instructions per frame */
+    pub quirks : Option<cpu::Quirks>, /* Quirks to turn on */
+    pub palette : Option<display::Palette>, /* Colours to draw with */
+    pub scale : Option<u32>, /* Window scale */
+    pub seed : Option<u64>, /* Seed for CXNN */
+    pub keymap : Option<String>, /* Keyboard layout name, or keymap file */
+    pub config : Option<String>, /* Config file */
+    pub audio : Option<bool>, /* Beep or not */
+    pub font : Option<font::Style>, /* Font to load */
+    pub font_address : Option<usize>, /* Where to load it */
+    pub stack_depth : Option<usize>, /* Return addresses the stack holds */
+    pub stack : Option<stack::Overflow>, /* What going past either end of the stack does */
+    pub memory : Option<usize>, /* Bytes of memory */
+    pub protect : bool, /* Refuse writes into the interpreter area */
+    pub headless : bool, /* Run without a window */
+    pub frames : Option<u64>, /* Stop after this many frames */
+    pub screenshot : Option<String>, /* Save the screen here after a headless run */
+    pub debug : bool, /* Trace instructions */
+    pub watch : bool, /* Reload the rom when it changes */
+    pub terminal : Option<terminal::Glyphs>, /* Run in the terminal instead of a window */
+    pub record : Option<String>, /* Record gameplay into this file from the start */
+    pub movie_record : Option<String>, /* Record the keypad into this movie file */
+    pub movie_play : Option<String>, /* Play this movie file back */
+    pub tas : Option<String>, /* Edit the movie with this script, and check how it plays out */
+    pub export_cartridge : Option<String>, /* Write the rom into this Octo cartridge instead of running it */
+    pub cheats : Option<String>, /* Cheat file */
+    pub patches : Vec<String>, /* Patches to apply to the rom, in order */
+    pub profile : Option<String>, /* Write the profiler's report here */
+    pub profile_folded : Option<String>, /* Write the profile here as folded stacks */
+}
+
+/* Reasons not to run. Help and Version aren't really errors, but stop
+ * the same way */
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum CliError {
+    Help,
+    Version,
+    UnknownOption(String),
+    MissingValue(String), /* Option that needed a value */
+    BadValue(String, String), /* Option, what was wrong */
+    NoRom,
+    ExtraArgument(String), /* More than one rom */
+    Conflict(String, String), /* Options that can't be used together */
+    Needs(String, String), /* Option, what else it needs */
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Help => write!(f, "{}", USAGE),
+            CliError::Version => write!(f, "r_chip8 {}", env!("CARGO_PKG_VERSION")),
+            CliError::UnknownOption(opt) => write!(f, "unknown option {}", opt),
+            CliError::MissingValue(opt) => write!(f, "{} needs a value", opt),
+            CliError::BadValue(opt, what) => write!(f, "bad value for {}: {}", opt, what),
+            CliError::NoRom => write!(f, "no rom given"),
+            CliError::ExtraArgument(arg) => write!(f, "only one rom can be run, got {} too", arg),
+            CliError::Conflict(a, b) => write!(f, "{} can't be used with {}", a, b),
+            CliError::Needs(opt, what) => write!(f, "{} needs {}", opt, what),
+        }
+    }
+}
+
+/* Turn on the quirks named in LIST (comma separated) in QUIRKS */
+fn parse_quirks(quirks : &mut cpu::Quirks, list : &str) -> Result<(), String> {
+    for name in list.split(',').map(|n| n.trim()).filter(|n| !n.is_empty()) {
+        if !quirks.set(name, true) {
+            return Err(format!("unknown quirk {}, known quirks are {}", name,
+                               cpu::QUIRK_NAMES.join(", ")));
+        }
+    }
+    return Ok(());
+}
+
+/* Parse a number for OPT out of VALUE, that has to be in MIN..=MAX */
+fn parse_number<T>(opt : &str, value : &str, min : T, max : T) -> Result<T, CliError>
+where T : std::str::FromStr + PartialOrd + fmt::Display {
+    let bad = |what : String| CliError::BadValue(opt.to_string(), what);
+    let n : T = value.parse().map_err(|_| bad(format!("{} isn't a number", value)))?;
+    if n < min || n > max {
+        return Err(bad(format!("{} isn't between {} and {}", value, min, max)));
+    }
+    return Ok(n);
+}
+
+/* Parse an address for OPT out of VALUE, hex with 0x in front or decimal, up to MAX */
+fn parse_address(opt : &str, value : &str, max : usize) -> Result<usize, CliError> {
+    let bad = |what : String| CliError::BadValue(opt.to_string(), what);
+    let address : usize = match value.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => value.parse(),
+    }.map_err(|_| bad(format!("{} isn't an address", value)))?;
+    if address > max {
+        return Err(bad(format!("{} is past {:#05x}", value, max)));
+    }
+    return Ok(address);
+}
+
+/* Parse ARGS, the first one being the program name */
+pub fn parse(args : &[String]) -> Result<Args, CliError> {
+    let mut out = Args {
+        file_path: String::new(),
+        speed: None,
+        quirks: None,
+        palette: None,
+        scale: None,
+        seed: None,
+        keymap: None,
+        config: None,
+        audio: None,
+        font: None,
+        font_address: None,
+        stack_depth: None,
+        stack: None,
+        memory: None,
+        protect: false,
+        headless: false,
+        frames: None,
+        debug: false,
+        watch: false,
+        terminal: None,
+        record: None,
+        movie_record: None,
+        movie_play: None,
+        tas: None,
+        export_cartridge: None,
+        cheats: None,
+        patches: vec![],
+        profile: None,
+        profile_folded: None,
+        screenshot: None,
+    };
+
+    let mut i : usize = 1;
+    while i < args.len() {
+        let opt : &str = args[i].as_str();
+
+        /* --opt=value and --opt value both work */
+        let (name, inline) : (&str, Option<&str>) = match opt.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value)),
+            _ => (opt, None),
+        };
+        let mut value = || -> Result<String, CliError> {
+            if let Some(v) = inline {
+                return Ok(v.to_string());
+            }
+            i += 1;
+            match args.get(i) {
+                Some(v) => return Ok(v.clone()),
+                None => return Err(CliError::MissingValue(name.to_string())),
+            }
+        };
+
+        match name {
+            "-h" | "--help" => return Err(CliError::Help),
+            "-V" | "--version" => return Err(CliError::Version),
+            "--speed" => out.speed = Some(parse_number(name, &value()?, scheduler::MIN_IPF, scheduler::MAX_IPF)?),
+            "--scale" => out.scale = Some(parse_number(name, &value()?, 1, 16)?),
+            "--seed" => out.seed = Some(parse_number(name, &value()?, 0, u64::MAX)?),
+            "--frames" => out.frames = Some(parse_number(name, &value()?, 0, u64::MAX)?),
+            "--quirks" => {
+                let mut quirks : cpu::Quirks = out.quirks.unwrap_or(cpu::make_quirks());
+                parse_quirks(&mut quirks, &value()?)
+                    .map_err(|what| CliError::BadValue(name.to_string(), what))?;
+                out.quirks = Some(quirks);
+            },
+            "--display-wait" | "--wrap-sprites" => {
+                let mut quirks : cpu::Quirks = out.quirks.unwrap_or(cpu::make_quirks());
+                quirks.set(&name[2..].replace('-', "_"), true);
+                out.quirks = Some(quirks);
+            },
+            "--palette" => {
+                let text : String = value()?;
+                match display::parse_palette(&text) {
+                    Some(p) => out.palette = Some(p),
+                    None => return Err(CliError::BadValue(name.to_string(),
+                                       format!("{} isn't two hex colours like ffb000,202020", text))),
+                }
+            },
+            "--font" => {
+                let text : String = value()?;
+                match font::parse_style(&text) {
+                    Some(style) => out.font = Some(style),
+                    None => return Err(CliError::BadValue(name.to_string(),
+                                       format!("unknown font {}, known fonts are {}", text,
+                                               font::STYLE_NAMES.join(", ")))),
+                }
+            },
+            "--font-address" => out.font_address = Some(parse_address(name, &value()?, font::MAX_ADDRESS)?),
+            "--stack-depth" => out.stack_depth = Some(parse_number(name, &value()?, 1, stack::MAX_DEPTH)?),
+            "--stack" => {
+                let text : String = value()?;
+                match stack::parse_overflow(&text) {
+                    Some(overflow) => out.stack = Some(overflow),
+                    None => return Err(CliError::BadValue(name.to_string(),
+                                       format!("unknown stack mode {}, known modes are {}", text,
+                                               stack::OVERFLOW_NAMES.join(", ")))),
+                }
+            },
+            "--memory" => {
+                let text : String = value()?;
+                match bus::parse_memory(&text) {
+                    Some(size) => out.memory = Some(size),
+                    None => return Err(CliError::BadValue(name.to_string(),
+                                       format!("unknown memory size {}, known sizes are {}", text,
+                                               bus::MEMORY_NAMES.join(", ")))),
+                }
+            },
+            "--keymap" => out.keymap = Some(value()?),
+            "--config" => out.config = Some(value()?),
+            "--record" => out.record = Some(value()?),
+            "--screenshot" => out.screenshot = Some(value()?),
+            "--movie-record" => out.movie_record = Some(value()?),
+            "--movie-play" => out.movie_play = Some(value()?),
+            "--tas" => out.tas = Some(value()?),
+            "--export-cartridge" => out.export_cartridge = Some(value()?),
+            "--cheats" => out.cheats = Some(value()?),
+            "--patch" => out.patches.push(value()?),
+            "--profile" => out.profile = Some(value()?),
+            "--profile-folded" => out.profile_folded = Some(value()?),
+            "--headless" => out.headless = true,
+            "--debug" => out.debug = true,
+            "--protect" => out.protect = true,
+            "--watch" => out.watch = true,
+            "--mute" => out.audio = Some(false),
+            "--terminal" => out.terminal = Some(terminal::Glyphs::HalfBlock),
+            "--braille" => out.terminal = Some(terminal::Glyphs::Braille),
+            _ if opt.starts_with('-') && opt.len() > 1 => {
+                return Err(CliError::UnknownOption(opt.to_string()));
+            },
+            _ => {
+                if !out.file_path.is_empty() {
+                    return Err(CliError::ExtraArgument(opt.to_string()));
+                }
+                out.file_path = opt.to_string();
+            },
+        }
+        i += 1;
+    }
+
+    validate(&out)?;
+    return Ok(out);
+}
+
+/* Options that don't make sense together */
+fn validate(args : &Args) -> Result<(), CliError> {
+    let needs = |opt : &str, what : &str| Err(CliError::Needs(opt.to_string(), what.to_string()));
+    let conflict = |a : &str, b : &str| Err(CliError::Conflict(a.to_string(), b.to_string()));
+
+    if args.file_path.is_empty() {
+        return Err(CliError::NoRom);
+    }
+    if args.headless && args.terminal.is_some() {
+        return conflict("--headless", "--terminal");
+    }
+    if args.headless && args.frames.is_none() && args.movie_play.is_none() {
+        return needs("--headless", "--frames or --movie-play");
+    }
+    if args.screenshot.is_some() && !args.headless {
+        return needs("--screenshot", "--headless, the window has F12");
+    }
+    if args.watch && (args.headless || args.terminal.is_some()) {
+        return conflict("--watch", "--headless or --terminal, it needs the window");
+    }
+    if args.watch && (args.movie_play.is_some() || args.movie_record.is_some()) {
+        return conflict("--watch", "movies, they are made on one version of the rom");
+    }
+    if args.record.is_some() && (args.headless || args.terminal.is_some()) {
+        return conflict("--record", "--headless or --terminal, it needs the window");
+    }
+    if args.movie_record.is_some() && args.tas.is_none() && (args.headless || args.terminal.is_some()) {
+        return conflict("--movie-record", "--headless or --terminal, it needs the window unless it's saving a --tas run");
+    }
+    if args.cheats.is_some() && (args.headless || args.terminal.is_some()) {
+        return conflict("--cheats", "--headless or --terminal, it needs the window");
+    }
+    if args.tas.is_some() && args.movie_play.is_none() {
+        return needs("--tas", "--movie-play");
+    }
+    let profiling : bool = args.profile.is_some() || args.profile_folded.is_some();
+    if profiling && args.tas.is_some() {
+        return conflict("--profile", "--tas, the script runs the movie over and over");
+    }
+    if args.export_cartridge.is_some() && (args.headless || args.terminal.is_some() || args.watch
+                                            || args.record.is_some() || args.movie_play.is_some()
+                                            || args.movie_record.is_some() || profiling) {
+        return conflict("--export-cartridge", "options for running the rom, it only writes the cartridge");
+    }
+    if args.movie_play.is_some() && (args.speed.is_some() || args.seed.is_some() || args.quirks.is_some()
+                                     || args.font.is_some() || args.font_address.is_some()
+                                     || args.stack_depth.is_some() || args.stack.is_some() || args.memory.is_some()) {
+        return conflict("--movie-play", "--speed, --seed, quirks, fonts, the stack or memory, the movie has its own");
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,93 @@
+use super::*;
+
+fn args(line : &str) -> Vec<String> {
+    let mut out : Vec<String> = vec![String::from("r_chip8")];
+    out.extend(line.split_whitespace().map(|a| a.to_string()));
+    return out;
+}
+
+#[test]
+fn test_cli_options() {
+    let a : Args = parse(&args("--speed 20 --scale=3 --seed 7 --quirks display_wait --wrap-sprites \
+                                --palette ffb000,202020 --keymap azerty --debug --mute roms/PONG")).unwrap();
+    assert_eq!(a.file_path, "roms/PONG");
+    assert_eq!(a.speed, Some(20));
+    assert_eq!(a.scale, Some(3));
+    assert_eq!(a.seed, Some(7));
+    assert_eq!(a.quirks.unwrap().enabled(), vec!["display_wait", "wrap_sprites"]);
+    assert_eq!(a.palette.unwrap().on, [0xff, 0xb0, 0x00]);
+    assert_eq!(a.keymap, Some(String::from("azerty")));
+    assert!(a.debug);
+    assert_eq!(a.audio, Some(false));
+    assert!(!a.headless);
+
+    let a : Args = parse(&args("PONG --headless --frames 600")).unwrap();
+    assert!(a.headless);
+    assert_eq!(a.frames, Some(600));
+    assert_eq!(a.speed, None);
+    assert_eq!(a.quirks, None);
+
+    let a : Args = parse(&args("PONG --font vip --font-address 0x000")).unwrap();
+    assert_eq!(a.font, Some(font::Style::Vip));
+    assert_eq!(a.font_address, Some(0x000));
+    assert_eq!(parse(&args("PONG --font-address=80")).unwrap().font_address, Some(0x050));
+    assert_eq!(parse(&args("PONG --cheats c.toml")).unwrap().cheats, Some(String::from("c.toml")));
+
+    let a : Args = parse(&args("PONG --stack-depth 12 --stack memory")).unwrap();
+    assert_eq!(a.stack_depth, Some(12));
+    assert_eq!(a.stack, Some(stack::Overflow::Memory));
+    assert!(matches!(parse(&args("PONG --stack-depth 0")), Err(CliError::BadValue(_, _))));
+    assert!(matches!(parse(&args("PONG --stack sideways")), Err(CliError::BadValue(_, _))));
+    assert_eq!(parse(&args("PONG --patch fix.ips --patch=en.bps")).unwrap().patches, vec!["fix.ips", "en.bps"]);
+    let a : Args = parse(&args("PONG --memory 64k --protect")).unwrap();
+    assert_eq!(a.memory, Some(bus::XO_SIZE));
+    assert!(a.protect);
+    assert!(matches!(parse(&args("PONG --memory 1k")), Err(CliError::BadValue(_, _))));
+    let a : Args = parse(&args("PONG --profile p.txt --profile-folded p.folded")).unwrap();
+    assert_eq!(a.profile, Some(String::from("p.txt")));
+    assert_eq!(a.profile_folded, Some(String::from("p.folded")));
+    let a : Args = parse(&args("PONG --headless --frames 60 --screenshot end.png")).unwrap();
+    assert_eq!(a.screenshot, Some(String::from("end.png")));
+    assert!(matches!(parse(&args("PONG --screenshot end.png")), Err(CliError::Needs(_, _))));
+}
+
+#[test]
+fn test_cli_help_and_version() {
+    assert_eq!(parse(&args("--help")).unwrap_err(), CliError::Help);
+    assert_eq!(parse(&args("PONG -h")).unwrap_err(), CliError::Help);
+    assert_eq!(parse(&args("-V")).unwrap_err(), CliError::Version);
+    assert!(CliError::Version.to_string().starts_with("r_chip8 "));
+}
+
+#[test]
+fn test_cli_errors() {
+    assert_eq!(parse(&args("")).unwrap_err(), CliError::NoRom);
+    assert_eq!(parse(&args("--debug")).unwrap_err(), CliError::NoRom);
+    assert_eq!(parse(&args("PONG TETRIS")).unwrap_err(), CliError::ExtraArgument(String::from("TETRIS")));
+    assert_eq!(parse(&args("PONG --fast")).unwrap_err(), CliError::UnknownOption(String::from("--fast")));
+    assert_eq!(parse(&args("PONG --speed")).unwrap_err(), CliError::MissingValue(String::from("--speed")));
+    assert!(matches!(parse(&args("PONG --speed 0")), Err(CliError::BadValue(_, _))));
+    assert!(matches!(parse(&args("PONG --speed fast")), Err(CliError::BadValue(_, _))));
+    assert!(matches!(parse(&args("PONG --scale 17")), Err(CliError::BadValue(_, _))));
+    assert!(matches!(parse(&args("PONG --quirks nope")), Err(CliError::BadValue(_, _))));
+    assert!(matches!(parse(&args("PONG --palette red")), Err(CliError::BadValue(_, _))));
+    assert!(matches!(parse(&args("PONG --font comic")), Err(CliError::BadValue(_, _))));
+    assert!(matches!(parse(&args("PONG --font-address 0x200")), Err(CliError::BadValue(_, _))));
+    assert!(matches!(parse(&args("PONG --font-address fifty")), Err(CliError::BadValue(_, _))));
+    assert!(matches!(parse(&args("PONG --movie-play a --font vip")), Err(CliError::Conflict(_, _))));
+    assert!(matches!(parse(&args("PONG --headless")), Err(CliError::Needs(_, _))));
+    assert!(matches!(parse(&args("PONG --headless --frames 1 --terminal")), Err(CliError::Conflict(_, _))));
+    assert!(matches!(parse(&args("PONG --tas a.tas")), Err(CliError::Needs(_, _))));
+    assert!(matches!(parse(&args("PONG --watch --terminal")), Err(CliError::Conflict(_, _))));
+    assert!(matches!(parse(&args("PONG --watch --movie-record a")), Err(CliError::Conflict(_, _))));
+    assert!(matches!(parse(&args("PONG --cheats c.toml --headless --frames 1")), Err(CliError::Conflict(_, _))));
+    assert!(matches!(parse(&args("PONG --record a.gif --headless --frames 1")), Err(CliError::Conflict(_, _))));
+    assert!(matches!(parse(&args("PONG --record a.gif --terminal")), Err(CliError::Conflict(_, _))));
+    assert!(matches!(parse(&args("PONG --movie-record a --headless --frames 1")), Err(CliError::Conflict(_, _))));
+    assert!(matches!(parse(&args("PONG --movie-record a --terminal")), Err(CliError::Conflict(_, _))));
+    assert!(parse(&args("PONG --movie-play a --tas a.tas --movie-record b --headless")).is_ok());
+    assert!(matches!(parse(&args("PONG --movie-play a --seed 1")), Err(CliError::Conflict(_, _))));
+    assert!(matches!(parse(&args("PONG --movie-play a --memory 64k")), Err(CliError::Conflict(_, _))));
+    assert!(matches!(parse(&args("PONG --export-cartridge a.gif --terminal")), Err(CliError::Conflict(_, _))));
+    assert!(matches!(parse(&args("PONG --movie-play a --tas a.tas --profile p.txt")), Err(CliError::Conflict(_, _))));
+}
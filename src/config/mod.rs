@@ -0,0 +1,304 @@
+use super::*;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/* Default options, so they don't have to be typed every time. Read from
+ * $XDG_CONFIG_HOME/r_chip8/config.toml (~/.config/r_chip8/config.toml), or
+ * the file given with --config. Anything given on the command line wins over
+ * the config file, and a rom's own section wins over the defaults:
+ *
+ *   speed = 15                     # instructions per frame
+ *   quirks = ["display_wait"]
+ *   palette = "ffb000,202020"      # on, off
+ *   scale = 4
+ *   keymap = "azerty"              # layout name or keymap file
+ *   audio = true                   # beep while the sound timer runs
+ *   font = "vip"                   # chip48, vip, dream6800, eti660, schip
+ *   font_address = 0x000           # where the font goes, 0x050 by default
+ *   stack_depth = 12               # return addresses the stack holds, 16 by default
+ *   stack = "memory"               # error, wrap or memory
+ *   memory = "64k"                 # 4k, or 64k like XO-CHIP
+ *
+ *   [roms.b232ef880bd6060fb45fa6effed7edf0ae95670e]    # sha1 of the rom
+ *   speed = 30
+ *   quirks = ["wrap_sprites"]
+ */
+
+/* Folder under the config directory, and the file in it */
+pub const CONFIG_DIR: &str = "r_chip8";
+pub const CONFIG_FILE: &str = "config.toml";
+
+/* Options out of one section of a config file, None where it didn't say */
+#[derive(Debug)]
+#[derive(Clone, PartialEq)]
+pub struct Settings {
+    pub speed : Option<u16>, /* Instructions per frame */
+    pub quirks : Option<cpu::Quirks>, /* Quirks to turn on */
+    pub palette : Option<display::Palette>, /* Colours to draw with */
+    pub scale : Option<u32>, /* Window scale */
+    pub keymap : Option<String>, /* Keyboard layout name, or keymap file */
+    pub audio : Option<bool>, /* Beep or not */
+    pub font : Option<font::Style>, /* Font to load */
+    pub font_address : Option<usize>, /* Where to load it */
+    pub stack_depth : Option<usize>, /* Return addresses the stack holds */
+    pub stack : Option<stack::Overflow>, /* What going past either end of the stack does */
+    pub memory : Option<usize>, /* Bytes of memory */
+}
+
+/* A whole config file */
+#[derive(Debug)]
+#[derive(Clone, PartialEq)]
+pub struct Config {
+    pub defaults : Settings, /* Top of the file, for every rom */
+    pub roms : HashMap<String, Settings>, /* Per rom sections, by lowercase sha1 */
+}
+
+/* Things that can go wrong loading a config file */
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error), /* Couldn't read the file */
+    Parse(toml::de::Error), /* Not valid toml, or unknown settings */
+    BadValue(String, String), /* Setting, what was wrong */
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "couldn't read config: {}", err),
+            ConfigError::Parse(err) => write!(f, "bad config file: {}", err),
+            ConfigError::BadValue(key, what) => write!(f, "bad value for {}: {}", key, what),
+        }
+    }
+}
+
+/* Shape of the config file */
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    speed : Option<u16>,
+    quirks : Option<Vec<String>>,
+    palette : Option<String>,
+    scale : Option<u32>,
+    keymap : Option<String>,
+    audio : Option<bool>,
+    font : Option<String>,
+    font_address : Option<usize>,
+    stack_depth : Option<usize>,
+    stack : Option<String>,
+    memory : Option<String>,
+    #[serde(default)]
+    roms : HashMap<String, SettingsFile>,
+}
+
+/* Shape of a per rom section */
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SettingsFile {
+    speed : Option<u16>,
+    quirks : Option<Vec<String>>,
+    palette : Option<String>,
+    scale : Option<u32>,
+    keymap : Option<String>,
+    audio : Option<bool>,
+    font : Option<String>,
+    font_address : Option<usize>,
+    stack_depth : Option<usize>,
+    stack : Option<String>,
+    memory : Option<String>,
+}
+
+/* Settings that don't say anything */
+pub fn make_settings() -> Settings {
+    let s = Settings { speed: None, quirks: None, palette: None, scale: None, keymap: None, audio: None,
+                       font: None, font_address: None, stack_depth: None, stack: None, memory: None };
+    return s;
+}
+
+/* Config that doesn't say anything */
+pub fn make_config() -> Config {
+    let c = Config { defaults: make_settings(), roms: HashMap::new() };
+    return c;
+}
+
+/* Where the config file lives when --config isn't given. None if there's
+ * no home to put it in */
+pub fn default_path() -> Option<PathBuf> {
+    let base : PathBuf = match env::var_os("XDG_CONFIG_HOME").map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => dir,
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    return Some(base.join(CONFIG_DIR).join(CONFIG_FILE));
+}
+
+/* Check the values in FILE, SECTION is where it came from for errors */
+fn settings(file : SettingsFile, section : &str) -> Result<Settings, ConfigError> {
+    let bad = |key : &str, what : String| ConfigError::BadValue(format!("{}{}", section, key), what);
+
+    let mut out : Settings = make_settings();
+    out.keymap = file.keymap;
+    out.audio = file.audio;
+    out.speed = match file.speed {
+        Some(speed) if !(scheduler::MIN_IPF..=scheduler::MAX_IPF).contains(&speed) =>
+            return Err(bad("speed", format!("{} isn't between {} and {}", speed, scheduler::MIN_IPF, scheduler::MAX_IPF))),
+        speed => speed,
+    };
+    out.scale = match file.scale {
+        Some(scale) if !(1..=16).contains(&scale) => return Err(bad("scale", format!("{} isn't between 1 and 16", scale))),
+        scale => scale,
+    };
+    if let Some(names) = file.quirks {
+        let mut quirks : cpu::Quirks = cpu::make_quirks();
+        for name in names.iter() {
+            if !quirks.set(name, true) {
+                return Err(bad("quirks", format!("unknown quirk {}", name)));
+            }
+        }
+        out.quirks = Some(quirks);
+    }
+    if let Some(name) = file.font {
+        match font::parse_style(&name) {
+            Some(style) => out.font = Some(style),
+            None => return Err(bad("font", format!("unknown font {}, known fonts are {}", name,
+                                                    font::STYLE_NAMES.join(", ")))),
+        }
+    }
+    out.font_address = match file.font_address {
+        Some(address) if address > font::MAX_ADDRESS =>
+            return Err(bad("font_address", format!("{:#05x} is past {:#05x}", address, font::MAX_ADDRESS))),
+        address => address,
+    };
+    out.stack_depth = match file.stack_depth {
+        Some(depth) if !(1..=stack::MAX_DEPTH).contains(&depth) =>
+            return Err(bad("stack_depth", format!("{} isn't between 1 and {}", depth, stack::MAX_DEPTH))),
+        depth => depth,
+    };
+    if let Some(name) = file.stack {
+        match stack::parse_overflow(&name) {
+            Some(overflow) => out.stack = Some(overflow),
+            None => return Err(bad("stack", format!("unknown stack mode {}, known modes are {}", name,
+                                                     stack::OVERFLOW_NAMES.join(", ")))),
+        }
+    }
+    if let Some(name) = file.memory {
+        match bus::parse_memory(&name) {
+            Some(size) => out.memory = Some(size),
+            None => return Err(bad("memory", format!("unknown memory size {}, known sizes are {}", name,
+                                                      bus::MEMORY_NAMES.join(", ")))),
+        }
+    }
+    if let Some(text) = file.palette {
+        match display::parse_palette(&text) {
+            Some(p) => out.palette = Some(p),
+            None => return Err(bad("palette", format!("{} isn't two hex colours like ffb000,202020", text))),
+        }
+    }
+    return Ok(out);
+}
+
+/* Read a config out of TEXT */
+pub fn parse(text : &str) -> Result<Config, ConfigError> {
+    let file : ConfigFile = match toml::from_str(text) {
+        Ok(file) => file,
+        Err(err) => return Err(ConfigError::Parse(err)),
+    };
+
+    let defaults = SettingsFile {
+        speed: file.speed,
+        quirks: file.quirks,
+        palette: file.palette,
+        scale: file.scale,
+        keymap: file.keymap,
+        audio: file.audio,
+        font: file.font,
+        font_address: file.font_address,
+        stack_depth: file.stack_depth,
+        stack: file.stack,
+        memory: file.memory,
+    };
+    let mut config : Config = make_config();
+    config.defaults = settings(defaults, "")?;
+    for (sha1, rom) in file.roms {
+        let section : String = format!("roms.{}.", sha1);
+        if sha1.len() != 40 || !sha1.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ConfigError::BadValue(section, String::from("roms are keyed by their sha1")));
+        }
+        config.roms.insert(sha1.to_lowercase(), settings(rom, &section)?);
+    }
+    return Ok(config);
+}
+
+/* Load the config @ PATH */
+pub fn load(path : &Path) -> Result<Config, ConfigError> {
+    match fs::read_to_string(path) {
+        Ok(text) => return parse(&text),
+        Err(err) => return Err(ConfigError::Io(err)),
+    }
+}
+
+impl Config {
+    /* Settings for the rom with ROM_SHA1, its section on top of the defaults */
+    pub fn for_rom(&self, rom_sha1 : &str) -> Settings {
+        let mut out : Settings = self.defaults.clone();
+        if let Some(rom) = self.roms.get(&rom_sha1.to_lowercase()) {
+            out.speed = rom.speed.or(out.speed);
+            out.quirks = rom.quirks.or(out.quirks);
+            out.palette = rom.palette.or(out.palette);
+            out.scale = rom.scale.or(out.scale);
+            out.keymap = rom.keymap.clone().or(out.keymap);
+            out.audio = rom.audio.or(out.audio);
+            out.font = rom.font.or(out.font);
+            out.font_address = rom.font_address.or(out.font_address);
+            out.stack_depth = rom.stack_depth.or(out.stack_depth);
+            out.stack = rom.stack.or(out.stack);
+            out.memory = rom.memory.or(out.memory);
+        }
+        return out;
+    }
+}
+
+impl Settings {
+    /* Fill in the options ARGS didn't give */
+    pub fn apply(&self, args : &mut cli::Args) {
+        if args.speed.is_none() {
+            args.speed = self.speed;
+        }
+        if args.quirks.is_none() {
+            args.quirks = self.quirks;
+        }
+        if args.palette.is_none() {
+            args.palette = self.palette;
+        }
+        if args.scale.is_none() {
+            args.scale = self.scale;
+        }
+        if args.keymap.is_none() {
+            args.keymap = self.keymap.clone();
+        }
+        if args.audio.is_none() {
+            args.audio = self.audio;
+        }
+        if args.font.is_none() {
+            args.font = self.font;
+        }
+        if args.font_address.is_none() {
+            args.font_address = self.font_address;
+        }
+        if args.stack_depth.is_none() {
+            args.stack_depth = self.stack_depth;
+        }
+        if args.stack.is_none() {
+            args.stack = self.stack;
+        }
+        if args.memory.is_none() {
+            args.memory = self.memory;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,71 @@
+use super::*;
+
+const PONG: &str = "b232ef880bd6060fb45fa6effed7edf0ae95670e";
+
+#[test]
+fn test_config_parse() {
+    let c : Config = parse("speed = 15\nquirks = [\"wrap_sprites\"]\npalette = \"ffb000,202020\"\n\
+                            scale = 4\nkeymap = \"dvorak\"\naudio = false\nfont = \"eti660\"\n\
+                            font_address = 0x000\nstack_depth = 12\nstack = \"wrap\"\n\
+                            memory = \"64k\"\n").unwrap();
+    assert_eq!(c.defaults.speed, Some(15));
+    assert_eq!(c.defaults.quirks.unwrap().enabled(), vec!["wrap_sprites"]);
+    assert_eq!(c.defaults.palette.unwrap().off, [0x20, 0x20, 0x20]);
+    assert_eq!(c.defaults.scale, Some(4));
+    assert_eq!(c.defaults.keymap, Some(String::from("dvorak")));
+    assert_eq!(c.defaults.audio, Some(false));
+    assert_eq!(c.defaults.font, Some(font::Style::Eti660));
+    assert_eq!(c.defaults.font_address, Some(0x000));
+    assert_eq!(c.defaults.stack_depth, Some(12));
+    assert_eq!(c.defaults.stack, Some(stack::Overflow::Wrap));
+    assert_eq!(c.defaults.memory, Some(bus::XO_SIZE));
+    assert!(c.roms.is_empty());
+
+    assert_eq!(parse("").unwrap(), make_config());
+    assert!(matches!(parse("speed = \"fast\""), Err(ConfigError::Parse(_))));
+    assert!(matches!(parse("volume = 3"), Err(ConfigError::Parse(_))));
+    assert!(matches!(parse("speed = 0"), Err(ConfigError::BadValue(_, _))));
+    assert!(matches!(parse("speed = 20000"), Err(ConfigError::BadValue(_, _)))); /* Same bounds as --speed */
+    assert_eq!(parse(&format!("speed = {}", scheduler::MAX_IPF)).unwrap().defaults.speed, Some(scheduler::MAX_IPF));
+    assert!(matches!(parse("scale = 40"), Err(ConfigError::BadValue(_, _))));
+    assert!(matches!(parse("quirks = [\"nope\"]"), Err(ConfigError::BadValue(_, _))));
+    assert!(matches!(parse("palette = \"red\""), Err(ConfigError::BadValue(_, _))));
+    assert!(matches!(parse("font = \"comic\""), Err(ConfigError::BadValue(_, _))));
+    assert!(matches!(parse("font_address = 0x1f0"), Err(ConfigError::BadValue(_, _))));
+    assert!(matches!(parse("stack_depth = 65"), Err(ConfigError::BadValue(_, _))));
+    assert!(matches!(parse("stack = \"sideways\""), Err(ConfigError::BadValue(_, _))));
+    assert!(matches!(parse("memory = \"1k\""), Err(ConfigError::BadValue(_, _))));
+    assert!(matches!(parse("[roms.PONG]\nspeed = 3"), Err(ConfigError::BadValue(_, _))));
+    assert!(matches!(parse(&format!("[roms.{}]\nvolume = 3", PONG)), Err(ConfigError::Parse(_))));
+}
+
+#[test]
+fn test_config_for_rom() {
+    let text : String = format!("speed = 15\nscale = 4\n\n[roms.{}]\nspeed = 30\n\
+                                 quirks = [\"display_wait\"]\n", PONG.to_uppercase());
+    let c : Config = parse(&text).unwrap();
+
+    let pong : Settings = c.for_rom(PONG);
+    assert_eq!(pong.speed, Some(30));
+    assert_eq!(pong.scale, Some(4));
+    assert_eq!(pong.quirks.unwrap().enabled(), vec!["display_wait"]);
+
+    let other : Settings = c.for_rom(&rom::sha1_hex(b"other"));
+    assert_eq!(other, c.defaults);
+}
+
+#[test]
+fn test_config_apply() {
+    let c : Config = parse("speed = 15\nscale = 4\nkeymap = \"dvorak\"\n").unwrap();
+    let line : Vec<String> = vec![String::from("r_chip8"), String::from("--speed"),
+                                  String::from("30"), String::from("PONG")];
+    let mut a : cli::Args = cli::parse(&line).unwrap();
+    c.for_rom(PONG).apply(&mut a);
+
+    /* The command line wins */
+    assert_eq!(a.speed, Some(30));
+    assert_eq!(a.scale, Some(4));
+    assert_eq!(a.keymap, Some(String::from("dvorak")));
+    assert_eq!(a.quirks, None);
+    assert_eq!(a.audio, None);
+}
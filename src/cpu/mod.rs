@@ -1,18 +1,106 @@
 use super::*;
-use crate::memory::_FONT_START;
-use rand::Rng;
-use time::{ Instant };
+use crate::bus::{Bus, BusError};
+use rand::{ Rng, SeedableRng };
+use rand::rngs::StdRng;
+use std::fmt;
 
 
-pub struct CPU {
-    pub mem : memory::Mem, /* Memory for our cpu */
+/* The cpu, generic over the memory it is wired to. Plain memory::Mem unless
+ * something needs watching or a bigger address space */
+#[derive(Clone)]
+pub struct CPU<B : Bus = memory::Mem> {
+    pub mem : B, /* Memory for our cpu */
     pub display: display::Display, /* Repr of display */
-    pub stack: Vec<u16>, /* Program stack */
+    pub stack: stack::Stack, /* Return addresses for 2NNN / 00EE */
     pub pc: u16, /* Program counter */
     pub regs: [u8; 16], /* Program variable registers */
     pub ireg: u16, /* Index registers */
     pub sound_timer : Timer, /* Sound timer */
     pub delay_timer : Timer, /* Delay timer */
+    pub quirks : Quirks, /* Which interpreter to behave like */
+    vblank : bool, /* 60hz interrupt happened, and nothing has been drawn since */
+    waited : bool, /* The last instruction is waiting, for the 60hz interrupt or a key */
+    pub seed : u64, /* Seed for CXNN, the same seed gives the same random numbers */
+    rng : StdRng, /* Random numbers for CXNN */
+    pub debug : bool, /* Print every instruction as it runs */
+    pub profiler : Option<profiler::Profiler>, /* Counts where the time goes, when profiling */
+    pub fault : Option<Fault>, /* What stopped the program */
+    pub font : font::Font, /* Font in memory, for FX29 and FX30 */
+}
+
+/* Behaviours that differ between chip8 interpreters. Everything off is
+ * how this emulator has always behaved */
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+pub struct Quirks {
+    pub display_wait : bool, /* DXYN waits for the next 60hz interrupt, like the COSMAC VIP */
+    pub wrap_sprites : bool, /* Sprites going off the edge wrap around instead of being clipped */
+    pub wrap_memory : bool, /* Reading or writing past the end of memory from I wraps back to 0, instead of stopping the program */
+}
+
+/* Things that stop a program until it is reset */
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Fault {
+    Bus(BusError), /* Bad memory access */
+    StackOverflow(usize), /* Called deeper than the stack goes */
+    StackUnderflow, /* Returned with nothing on the stack */
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Fault::Bus(err) => write!(f, "{}", err),
+            Fault::StackOverflow(depth) => write!(f, "stack overflow, called more than {} deep", depth),
+            Fault::StackUnderflow => write!(f, "stack underflow, returned with nothing on the stack"),
+        }
+    }
+}
+
+impl From<BusError> for Fault {
+    fn from(err : BusError) -> Fault {
+        return Fault::Bus(err);
+    }
+}
+
+/* Names of the quirks, for the command line and files */
+pub const QUIRK_NAMES: [&str; 3] = ["display_wait", "wrap_sprites", "wrap_memory"];
+
+pub fn make_quirks() -> Quirks {
+    let q = Quirks {
+        display_wait: false,
+        wrap_sprites: false,
+        wrap_memory: false,
+    };
+    return q;
+}
+
+impl Quirks {
+    /* Get the quirk called NAME, None if there isn't one */
+    pub fn get(&self, name : &str) -> Option<bool> {
+        match name {
+            "display_wait" => return Some(self.display_wait),
+            "wrap_sprites" => return Some(self.wrap_sprites),
+            "wrap_memory" => return Some(self.wrap_memory),
+            _ => return None,
+        }
+    }
+
+    /* Turn the quirk called NAME on or off. Returns false if there isn't one */
+    pub fn set(&mut self, name : &str, on : bool) -> bool {
+        match name {
+            "display_wait" => self.display_wait = on,
+            "wrap_sprites" => self.wrap_sprites = on,
+            "wrap_memory" => self.wrap_memory = on,
+            _ => return false,
+        }
+        return true;
+    }
+
+    /* Names of every quirk that is turned on */
+    pub fn enabled(&self) -> Vec<&'static str> {
+        return QUIRK_NAMES.iter().copied().filter(|name| self.get(name) == Some(true)).collect();
+    }
 }
 
 
@@ -30,15 +118,11 @@ pub struct Instruction {
     byte : u16, /* Instruction in 16 bit form */
 }
 
-/* Timer struct. Used for delay and sound timer. Uses start_time to get a DateTime
- * obj, then uses that to determine how much time has elapsed, and store that into 
- * CURR_SEC each second. Timers are both supposed to decrememnt at 60 ticks per 
- * second. Use BASE_VALUE for the start of the interval, and VALUE to determine
- * how many ticks have occured inthis interval */
+/* Timer struct. Used for delay and sound timer. Timers are both supposed to 
+ * decrememnt at 60 ticks per second, so they tick once per frame. The frame
+ * scheduler is what keeps frames at 60 / second */
+#[derive(Clone)]
 pub struct Timer {
-    start_time : Instant, /* Start time, used to calculate time elapsed */
-    pub curr_second : u64, /* Place holder for How much time has elapsed */
-    base_value : u8, // Base value that timer started at 
     value : u8, // Current value timer is at
 }
 
@@ -50,18 +134,20 @@ pub struct Timer {
  * or if things actually start needing some more
  * info bake cpu into WORLD alongside other fields*/
 pub struct World {
+    pub filter : filter::Filter, /* Anti-flicker filter applied when drawing */
+    pub palette : display::Palette, /* Colours to draw with */
 }
 
 /* Make a cpu struct with MEM. Should be made more like a constructor
  * for CPU class => inside of an impl statement. Would change but
  * don't feel like updating every test to do. 
  */
-pub fn make_cpu(mem: memory::Mem) -> CPU { 
+pub fn make_cpu<B : Bus>(mem: B) -> CPU<B> { 
     let dis: display::Display = display::make_display();
 
-    let mut _stack: Vec<u16> = vec![];
+    let _stack: stack::Stack = stack::make_stack(stack::SCHIP_DEPTH, stack::Overflow::Error);
 
-    let mut _core: CPU = CPU{
+    let mut _core: CPU<B> = CPU{
         mem: mem,
         display: dis,
         stack: _stack,
@@ -70,7 +156,18 @@ pub fn make_cpu(mem: memory::Mem) -> CPU {
         ireg: 0x0,
         sound_timer : Timer::new(),
         delay_timer : Timer::new(),
+        quirks : make_quirks(),
+        vblank : false,
+        waited : false,
+        seed : 0,
+        rng : StdRng::seed_from_u64(0),
+        debug : false,
+        profiler : None,
+        fault : None,
+        font : font::make_font(),
     };
+    /* Different random numbers every run, unless set_seed says otherwise */
+    _core.set_seed(rand::random());
     return _core;
 }
 
@@ -94,7 +191,7 @@ pub fn make_instruction(data : [u8; 2])-> Instruction {
 }
 
 /* CPU functionality: fetch, decode, exec */
-impl CPU {
+impl<B : Bus> CPU<B> {
     /* Decodes the instruction that is at the current program counter */
     fn decode_instruction(&mut self, instruction : Instruction, input_key : u8) {
         // Switch case for the first nibble of the instruction
@@ -176,6 +273,7 @@ impl CPU {
             }, 
             0xF => {
                 match instruction.nib4 {
+                    0 if instruction.nib3 == 3 => { self.big_font_char(instruction); } // FX30 => 
                     3 => { self.binary_coded_decimal_conv(instruction); } // FX33 => 
                     5 => {
                         match instruction.nib3 {
@@ -204,20 +302,122 @@ impl CPU {
 
     /* Fetches the next instruction from the program, updates program counter */
     fn fetch_next_instruction(&mut self)->Instruction {
-        let data : [u8; 2] = self.mem.read16(self.pc as usize);
+        let data : [u8; 2] = match self.mem.read16(self.pc as usize) {
+            Ok(data) => data,
+            Err(err) => {
+                self.fault = Some(Fault::Bus(err));
+                [0x00, 0x00]
+            },
+        };
 
         let out : Instruction = make_instruction(data);
-        self.pc += 2;
+        self.advance();
         return out;
     }
 
+    /* Move the pc on past an instruction. There's no address after the end
+     * of 64K of memory, so running off of it stops the program */
+    fn advance(&mut self) {
+        match self.pc.checked_add(2) {
+            Some(pc) => self.pc = pc,
+            None => self.fault = self.fault.or(Some(Fault::Bus(BusError::OutOfRange(0x10000)))),
+        }
+    }
+
     /* Execute one instruction */
     pub fn exec(&mut self, input_key : u8) ->bool {
+        if self.debug {
+            /* Peeked, so the fetch below is the only read of it a Logger sees */
+            let pc : usize = self.pc as usize;
+            let opcode : [u8; 2] = [self.mem.peek(pc).unwrap_or(0x00), self.mem.peek(pc + 1).unwrap_or(0x00)];
+            eprintln!("{:#05x}: {:04x}  v {:02x?}  i {:#05x}  sp {}", self.pc,
+                      u16::from_be_bytes(opcode), self.regs, self.ireg,
+                      self.stack.pointer());
+        }
+        /* A bad memory access stops the program until it is reset */
+        if self.fault.is_some() {
+            return false;
+        }
         let next : Instruction = self.fetch_next_instruction();
-        if next.nib1 == 0 && next.nib234 == 0x000 {
+        if self.fault.is_some() || (next.nib1 == 0 && next.nib234 == 0x000) {
             return false;
         }
+        let opcode : u16 = next.byte;
+        self.waited = false;
         self.decode_instruction(next, input_key);
+        if self.fault.is_some() {
+            return false;
+        }
+        if let Some(profiler) = &mut self.profiler {
+            profiler.step(opcode, self.waited);
+        }
+        return true;
+    }
+
+    /* Start the program over. Registers, timers, stack and display are
+     * cleared, memory is left alone so the rom is still there. The random
+     * numbers start over from the same seed */
+    pub fn soft_reset(&mut self) {
+        self.pc = memory::_PROG_START as u16;
+        self.regs = [0x0; 16];
+        self.ireg = 0x0;
+        self.stack.clear();
+        if let Some(profiler) = &mut self.profiler {
+            profiler.restart();
+        }
+        self.sound_timer = Timer::new();
+        self.delay_timer = Timer::new();
+        self.display = display::make_display();
+        self.vblank = false;
+        self.fault = None;
+        self.set_seed(self.seed);
+    }
+
+    /* Start over with GAME loaded into fresh memory, like it was just opened */
+    pub fn hard_reset(&mut self, game : Vec<u8>) {
+        /* Fresh memory comes with the usual font, put ours back */
+        self.mem.reset();
+        let font : font::Font = self.font;
+        self.font = font::make_font();
+        self.set_font(font);
+        self.mem.store_game(game);
+        self.soft_reset();
+    }
+
+    /* Swap the font in memory for FONT. The old one gets cleared out, in
+     * case something went looking for it */
+    pub fn set_font(&mut self, font : font::Font) {
+        for i in 0..font::FONT_SIZE {
+            let _ = self.mem.poke(self.font.address + i, 0x00);
+        }
+        for (i, byte) in font.bytes().into_iter().enumerate() {
+            let _ = self.mem.poke(font.address + i, byte);
+        }
+        self.font = font;
+    }
+
+    /* Restart the random numbers from SEED */
+    pub fn set_seed(&mut self, seed : u64) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    /* Run one 60hz frame. The interrupt at the start of the frame ticks the 
+     * timers, then IPF instructions get executed. Returns false if the
+     * program exited */
+    pub fn run_frame(&mut self, ipf : u16, input_key : u8) -> bool {
+        self.sound_timer.tick();
+        self.delay_timer.tick();
+        self.vblank = true;
+        if let Some(profiler) = &mut self.profiler {
+            profiler.frames += 1;
+        }
+
+        for _ in 0..ipf {
+            if !self.exec(input_key) {
+                return false;
+            }
+        }
         return true;
     }
 
@@ -234,7 +434,37 @@ impl CPU {
 }
 
 /* Instruction set */
-impl CPU {
+impl<B : Bus> CPU<B> {
+
+    /* Address OFFSET bytes past I. Wraps around at the end of memory with
+     * the wrap_memory quirk, otherwise it's left to the bus to say no */
+    fn i_address(&self, offset : usize) -> usize {
+        let address : usize = self.ireg as usize + offset;
+        if self.quirks.wrap_memory {
+            return address % self.mem.size();
+        }
+        return address;
+    }
+
+    /* Byte OFFSET bytes past I. A bad address stops the program and reads as 0 */
+    fn read_i(&mut self, offset : usize) -> u8 {
+        let address : usize = self.i_address(offset);
+        match self.mem.read8(address) {
+            Ok(data) => return data,
+            Err(err) => {
+                self.fault = self.fault.or(Some(Fault::Bus(err)));
+                return 0x00;
+            },
+        }
+    }
+
+    /* Write DATA OFFSET bytes past I. A bad address stops the program */
+    fn write_i(&mut self, offset : usize, data : u8) {
+        let address : usize = self.i_address(offset);
+        if let Err(err) = self.mem.write8(address, data) {
+            self.fault = self.fault.or(Some(Fault::Bus(err)));
+        }
+    }
 
     /* Clears the display to be all false values */
     fn clear_screen(&mut self) {
@@ -245,11 +475,13 @@ impl CPU {
         }
     }
 
-    /* Loads pc from stack and jumps to it */
+    /* Loads pc from stack and jumps to it. What happens when there's
+     * nothing there is up to the stack */
     fn load_pc_and_jump(&mut self) {
         /* Pop program counter */
-        if self.stack.len() > 0 {
-            self.pc = self.stack.pop().unwrap();
+        match self.stack.pop(&mut self.mem) {
+            Ok(address) => self.pc = address,
+            Err(fault) => self.fault = self.fault.or(Some(fault)),
         }
     }
 
@@ -260,7 +492,7 @@ impl CPU {
 
     /* Sets PC to an address */
     fn jump(&mut self, instruction : Instruction) {
-        if instruction.nib234 > self.mem.data.len() as u16 {
+        if instruction.nib234 as usize > self.mem.size() {
             println!("Invalid address to jump to");
         }
         self.goto(instruction.nib234);
@@ -268,8 +500,11 @@ impl CPU {
 
     /* Jump and stores pc onto the stack */
     fn jump_and_store_pc(&mut self, instruction : Instruction) {
-        /* Push the program counter onto the stack */
-        self.stack.push(self.pc as u16);
+        /* Push the program counter onto the stack, a full one stops the program */
+        if let Err(fault) = self.stack.push(&mut self.mem, self.pc) {
+            self.fault = self.fault.or(Some(fault));
+            return;
+        }
 
         /* Jump to desired address */
         self.jump(instruction);
@@ -289,7 +524,7 @@ impl CPU {
         }
 
         if skip {
-            self.pc += 2;
+            self.advance();
         }
 
     }
@@ -382,8 +617,7 @@ impl CPU {
 
     /* Gets a random number, and binary & it with value in X reg */
     fn random(&mut self, instruction : Instruction) {
-        let mut rng = rand::thread_rng();
-        let n: u8 = rng.gen();
+        let n: u8 = self.rng.gen();
 
         self.regs[instruction.nib2 as usize] = instruction.inst[1] & n;
     }
@@ -391,22 +625,50 @@ impl CPU {
 
     /* Draws the display */
     fn dxyn(&mut self, instruction : Instruction) {
-        /* Get the x and y coords of the instruction */
-        let vx : u8 = self.regs[instruction.nib2 as usize] % 64;
-        let vy : u8 = self.regs[instruction.nib3 as usize] % 32;
+        /* The VIP only draws right after the 60hz interrupt, so keep running
+         * this instruction until the next frame comes around */
+        if self.quirks.display_wait {
+            if !self.vblank {
+                self.pc -= 2;
+                self.waited = true;
+                return;
+            }
+            self.vblank = false;
+        }
+
+        /* Get the x and y coords of the instruction. Starting coords past
+         * the edge of the screen always wrap back around */
+        let height : usize = self.display.screen.len();
+        let width : usize = self.display.screen[0].len();
+        let vx : usize = self.regs[instruction.nib2 as usize] as usize % width;
+        let vy : usize = self.regs[instruction.nib3 as usize] as usize % height;
 
         self.regs[0xF] = 0; /* Set VF = 0 */
         for i in 0..instruction.nib4 as usize {
-            let pixel_sprite : u8 = self.mem.data[self.ireg as usize + i];
+            /* Rows going off the bottom get clipped, or wrap to the top */
+            let mut y : usize = vy + i;
+            if y >= height {
+                if !self.quirks.wrap_sprites { break; }
+                y %= height;
+            }
+
+            let pixel_sprite : u8 = self.read_i(i);
             for pixel_i in 0..8 {
                 let mask = 0x80 >> pixel_i;
                 let pixel = pixel_sprite & mask;
 
+                /* Same for columns going off the right side */
+                let mut x : usize = vx + pixel_i;
+                if x >= width {
+                    if !self.quirks.wrap_sprites { break; }
+                    x %= width;
+                }
+
                 if pixel != 0 {
-                    if self.display.screen[vy as usize+ i][vx as usize + pixel_i] != false {
+                    if self.display.screen[y][x] {
                         self.regs[0xF] = 1;
                     }
-                    self.display.screen[vy as usize+ i][vx as usize + pixel_i] ^= true;
+                    self.display.screen[y][x] ^= true;
                 }
             }
         }
@@ -420,7 +682,7 @@ impl CPU {
         let multiplier : i16 = 10;
         for i in 0..3 {
             let dec_place : u8  = (num_to_conv as i16 % multiplier) as u8;
-            self.mem.write8((self.ireg + (2-i as u16)) as usize, dec_place);
+            self.write_i(2 - i, dec_place);
             num_to_conv /= 10;
         }
     }
@@ -437,16 +699,14 @@ impl CPU {
     /* Stores the values @ the address inside of ireg to the variable regs upto X*/
     fn store(&mut self, instruction : Instruction) {
         for i in 0..instruction.nib2+1 {
-            self.mem.write8((self.ireg + (i as u16)) as usize,
-            self.regs[i as usize]);
+            self.write_i(i as usize, self.regs[i as usize]);
         }
     }
 
     /* Load the values from the variable regs into i reg upto X */
     fn load(&mut self, instruction : Instruction) {
         for i in 0..instruction.nib2+1 {
-            self.regs[i as usize] = self.mem.read8(
-                (self.ireg + (i as u16)) as usize);
+            self.regs[i as usize] = self.read_i(i as usize);
         }
     }
 
@@ -460,13 +720,21 @@ impl CPU {
         /* Look at the last nibble of VX, and set 
          * ireg to the font corresponding to it */
         let font_num : u8 = (self.regs[instruction.nib2 as usize] << 4) >> 4;
-        self.ireg = (_FONT_START as u16) + ((5 * font_num) as u16);
-        dbg!(font_num);
+        self.ireg = self.font.small_address(font_num) as u16;
+        if self.debug {
+            dbg!(font_num);
+        }
+    }
+
+    /* Same as font_char, for the SCHIP 8x10 digits. Only 0-9 have one */
+    fn big_font_char(&mut self, instruction : Instruction) {
+        let digit : u8 = self.regs[instruction.nib2 as usize] & 0xF;
+        self.ireg = self.font.big_address(digit) as u16;
     }
 
     /* Stall until a key is sent through, and when it is store key value in VX */
     fn get_key(&mut self, instruction : Instruction, input_key : u8) {
-        if input_key == 0xf0 { self.pc -= 2; return; }
+        if input_key == 0xf0 { self.pc -= 2; self.waited = true; return; }
         self.regs[instruction.nib2 as usize] = input_key;
     }
 
@@ -490,21 +758,29 @@ impl World {
     // Create a new `World` instance.
     pub fn new() -> Self {
         return Self {
+            filter : filter::make_filter(filter::FilterMode::Off),
+            palette : display::make_palette(),
         }
     }
 
+    // Colour of the pixel @ (X, Y) of the chip8 screen, with the filter and palette applied
+    pub fn pixel_rgb<B : Bus>(&self, core : &CPU<B>, x : usize, y : usize) -> [u8; 3] {
+        return self.palette.mix(self.filter.pixel(&core.display, x, y));
+    }
+
 
     // Draw the `World` state to the frame buffer.
     // Assumes the default texture format: `wgpu::TextureFormat::Rgba8UnormSrgb`
-    pub fn draw(&self, core : &CPU, frame: &mut [u8]) {
+    pub fn draw<B : Bus>(&self, core : &CPU<B>, frame: &mut [u8]) {
 
         for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
             let x = (i % WIDTH as usize) as usize;
             let y = (i / WIDTH as usize) as usize;
 
             let in_screen = x < core.display.screen[0].len() && y < core.display.screen.len();
-            let rgba = if in_screen && core.display.screen[y][x] == false {
-                [0x00, 0x00, 0x00, 0x00]
+            let rgba = if in_screen {
+                let rgb : [u8; 3] = self.pixel_rgb(core, x, y);
+                [rgb[0], rgb[1], rgb[2], 0xff]
             } else {
                 [0xff, 0xff, 0xff, 0xff]
             };
@@ -517,52 +793,25 @@ impl World {
 impl Timer {
     pub fn new() -> Self {
         return Self {
-            start_time : Instant::now(),
-            curr_second : 0,
-            base_value : 0x00,
             value : 0x00,
         };
     }
 
-    /* Get the amount of elapsed time since we have started this timer */
-    pub fn get_elapsed_time(&self) -> u64 {
-        return self.start_time.elapsed().as_seconds_f32().floor() as u64;
-    }
-
-    /* Do one tick. If we have executed more than 60 ticks in a second, hold off
-     * until 1 second passes, then update Timer.curr_sec += 1, and continue 
-     * ticking */
+    /* Do one tick, counting down until we hit 0. Called once per frame */
     pub fn tick(&mut self)  {
-        let elapsed = self.get_elapsed_time();
-
-        /* If >= 1 second has elapsed and 60 ticks haven't went by yet */
-        if elapsed >= self.curr_second && self.base_value - self.value <= 60 &&
-            self.base_value - self.value > 0 && self.value != 0 {
-                self.value -= 1;
-            }
-        else if elapsed > self.curr_second && self.base_value - self.value > 60 {
-            /* if > 1 sec has elapsed and we have been dec. 60x  */
-            if self.value != 0 {
-                self.base_value = self.value+1;
-                self.value = self.base_value;
-            }  else { self.base_value = 0; }
-
-            /* Update curr_sec for the next 60 tick interval */
-            self.curr_second = elapsed;
+        if self.value != 0 {
+            self.value -= 1;
         }
     }
 
+    /* Still counting down. For the sound timer that means the buzzer is on */
+    pub fn is_running(&self) -> bool {
+        return self.value != 0;
+    }
+
     /* Set the timer to TIME_AMOUNT */
     pub fn set_timer(&mut self, time_amount : u8) {
-        /* If we set them to be the same value, tick will never dec */
-        if time_amount == 0xff {
-            self.base_value = time_amount;
-            self.value = time_amount - 1;
-        } else {
-
-            self.base_value = time_amount+1;
-            self.value = time_amount;
-        }
+        self.value = time_amount;
     }
 }
 
@@ -19,8 +19,8 @@ fn test_cpu_fetch() {
     let w1 : [u8; 2] = [0xde, 0xad];
     let w2 : [u8; 2] = [0xbe, 0xef];
 
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
 
     let e1 = Instruction {
         inst: w1,
@@ -52,8 +52,8 @@ fn test_cpu_clear_display() {
     let mut c : CPU = make_cpu(mem);
     let w1 : [u8; 2] = [0x00, 0xE0]; // Clear display
     let w2 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
     c.display.screen[0][0] = true;
     c.display.screen[1][2] = true;
     c.display.screen[2][4] = true;
@@ -71,9 +71,9 @@ fn test_cpu_jump() {
     let w1 : [u8; 2] = [0x19, 0x99]; // Jump to address 0x999
     let w2 : [u8; 2] = [0x00, 0xE0]; // Clear display
     let w3 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16(0x999, w2);
-    c.mem.write16(0x999 + 2, w3);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16(0x999, w2).unwrap();
+    c.mem.write16(0x999 + 2, w3).unwrap();
     c.display.screen[0][0] = true;
     c.display.screen[1][2] = true;
     c.display.screen[2][4] = true;
@@ -91,15 +91,15 @@ fn test_cpu_jump_and_store() {
     let mut c : CPU = make_cpu(mem);
     let w1 : [u8; 2] = [0x29, 0x99]; // Jump to address 0x999
     let w2 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16(0x999, w2);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16(0x999, w2).unwrap();
 
     let og_stack = c.pc;
     c._run();
 
     assert_eq!(c.pc as u16, 0x999 + 2);
     assert_eq!(c.stack.len(), 1);
-    assert_eq!(c.stack.pop(), Some(og_stack+2));
+    assert_eq!(c.stack.pop(&mut c.mem), Ok(og_stack+2));
 }
 
 #[test]
@@ -109,9 +109,9 @@ fn test_cpu_load_and_jump() {
     let w1 : [u8; 2] = [0x29, 0x99]; // Jump to address 0x999, store pc in stack
     let w2 : [u8; 2] = [0x00, 0xEE]; // Load address in stack, jump to address
     let w3 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16(0x999, w2);
-    c.mem.write16((c.pc+2).into(), w3);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16(0x999, w2).unwrap();
+    c.mem.write16((c.pc+2).into(), w3).unwrap();
 
     let og_stack = c.pc;
     c._run();
@@ -129,11 +129,11 @@ fn test_cpu_skip3() {
     let w3 : [u8; 2] = [0x00, 0x00]; // Exit
     let w4 : [u8; 2] = [0x38, 0x10]; // Skip instruction but no skip
     let w5 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    c.mem.write16((c.pc+4).into(), w3);
-    c.mem.write16((c.pc+6).into(), w4);
-    c.mem.write16((c.pc+8).into(), w5);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
+    c.mem.write16((c.pc+6).into(), w4).unwrap();
+    c.mem.write16((c.pc+8).into(), w5).unwrap();
 
     let og_pc = c.pc;
     c._run();
@@ -151,11 +151,11 @@ fn test_cpu_skip4() {
     let w3 : [u8; 2] = [0x00, 0x00]; // Exit
     let w4 : [u8; 2] = [0x48, 0x99]; // Skip instruction but no skip
     let w5 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    c.mem.write16((c.pc+4).into(), w3);
-    c.mem.write16((c.pc+6).into(), w4);
-    c.mem.write16((c.pc+8).into(), w5);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
+    c.mem.write16((c.pc+6).into(), w4).unwrap();
+    c.mem.write16((c.pc+8).into(), w5).unwrap();
 
     let og_pc = c.pc;
     c._run();
@@ -174,12 +174,12 @@ fn test_cpu_skip5() {
     let w4 : [u8; 2] = [0x00, 0x00]; // Exit
     let w5 : [u8; 2] = [0x98, 0x90]; // Skip instruction == false
     let w6 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    c.mem.write16((c.pc+4).into(), w3);
-    c.mem.write16((c.pc+6).into(), w4);
-    c.mem.write16((c.pc+8).into(), w5);
-    c.mem.write16((c.pc+10).into(), w6);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
+    c.mem.write16((c.pc+6).into(), w4).unwrap();
+    c.mem.write16((c.pc+8).into(), w5).unwrap();
+    c.mem.write16((c.pc+10).into(), w6).unwrap();
 
     let og_pc = c.pc;
     c._run();
@@ -199,12 +199,12 @@ fn test_cpu_skip9() {
     let w4 : [u8; 2] = [0x00, 0x00]; // Exit
     let w5 : [u8; 2] = [0x58, 0x90]; // Skip instruction == false
     let w6 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    c.mem.write16((c.pc+4).into(), w3);
-    c.mem.write16((c.pc+6).into(), w4);
-    c.mem.write16((c.pc+8).into(), w5);
-    c.mem.write16((c.pc+10).into(), w6);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
+    c.mem.write16((c.pc+6).into(), w4).unwrap();
+    c.mem.write16((c.pc+8).into(), w5).unwrap();
+    c.mem.write16((c.pc+10).into(), w6).unwrap();
 
     let og_pc = c.pc;
     c._run();
@@ -220,8 +220,8 @@ fn test_cpu_set() {
     let mut c : CPU = make_cpu(mem);
     let w1 : [u8; 2] = [0x68, 0x99]; // Set reg 8 to 0x99
     let w2 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
     c._run();
     assert_eq!(c.regs[8], 0x99);
 }
@@ -233,9 +233,9 @@ fn test_cpu_add() {
     let w1 : [u8; 2] = [0x78, 0x20]; // Jump to address 0x999
     let w2 : [u8; 2] = [0x78, 0x15]; // Jump to address 0x999
     let w3 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    c.mem.write16((c.pc+4).into(), w3);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
     c._run();
     assert_eq!(c.regs[8], 0x20 + 0x15);
 }
@@ -247,9 +247,9 @@ fn test_cpu_set_reg() {
     let w1 : [u8; 2] = [0x68, 0x99]; // Set reg 8 to 0x99
     let w2 : [u8; 2] = [0x89, 0x80]; // Set reg 9 to 0x99
     let w3 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    c.mem.write16((c.pc+4).into(), w3);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
     c._run();
     assert_eq!(c.regs[8], 0x99);
     assert_eq!(c.regs[9], 0x99);
@@ -263,10 +263,10 @@ fn test_cpu_bin_or() {
     let w2 : [u8; 2] = [0x69, 0xef]; // Set reg 9 to 0xad
     let w3 : [u8; 2] = [0x88, 0x91]; // reg 8 (logical or) reg 9
     let w4 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    c.mem.write16((c.pc+4).into(), w3);
-    c.mem.write16((c.pc+6).into(), w4);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
+    c.mem.write16((c.pc+6).into(), w4).unwrap();
     c._run();
     assert_eq!(c.regs[8], 0xbe | 0xef);
     assert_eq!(c.regs[9], 0xef);
@@ -280,10 +280,10 @@ fn test_cpu_bin_and() {
     let w2 : [u8; 2] = [0x69, 0xef]; // Set reg 9 to 0xad
     let w3 : [u8; 2] = [0x88, 0x92]; // reg 8 (logical and) reg 9
     let w4 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    c.mem.write16((c.pc+4).into(), w3);
-    c.mem.write16((c.pc+6).into(), w4);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
+    c.mem.write16((c.pc+6).into(), w4).unwrap();
     c._run();
     assert_eq!(c.regs[8], 0xbe & 0xef);
     assert_eq!(c.regs[9], 0xef);
@@ -297,10 +297,10 @@ fn test_cpu_bin_xor() {
     let w2 : [u8; 2] = [0x69, 0xef]; // Set reg 9 to 0xad
     let w3 : [u8; 2] = [0x88, 0x93]; // reg 8 (logical xor) reg 9
     let w4 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    c.mem.write16((c.pc+4).into(), w3);
-    c.mem.write16((c.pc+6).into(), w4);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
+    c.mem.write16((c.pc+6).into(), w4).unwrap();
     c._run();
     assert_eq!(c.regs[8], 0xbe ^ 0xef);
     assert_eq!(c.regs[9], 0xef);
@@ -314,10 +314,10 @@ fn test_cpu_reg_add() {
     let w2 : [u8; 2] = [0x69, 0xef]; // Set reg 9 to 0xad
     let w3 : [u8; 2] = [0x88, 0x94]; // reg 8 (+) reg 9
     let w4 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    c.mem.write16((c.pc+4).into(), w3);
-    c.mem.write16((c.pc+6).into(), w4);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
+    c.mem.write16((c.pc+6).into(), w4).unwrap();
     c._run();
     assert_eq!(c.regs[8], (0xbe + 0xef) as u8);
     assert_eq!(c.regs[9], 0xef);
@@ -329,10 +329,10 @@ fn test_cpu_reg_add() {
     let w2 : [u8; 2] = [0x69, 0x50]; // Set reg 9 to 0xad
     let w3 : [u8; 2] = [0x88, 0x94]; // reg 8 (+) reg 9
     let w4 : [u8; 2] = [0x00, 0x00]; // Exit
-    c2.mem.write16((c2.pc).into(), w1);
-    c2.mem.write16((c2.pc+2).into(), w2);
-    c2.mem.write16((c2.pc+4).into(), w3);
-    c2.mem.write16((c2.pc+6).into(), w4);
+    c2.mem.write16((c2.pc).into(), w1).unwrap();
+    c2.mem.write16((c2.pc+2).into(), w2).unwrap();
+    c2.mem.write16((c2.pc+4).into(), w3).unwrap();
+    c2.mem.write16((c2.pc+6).into(), w4).unwrap();
     c2._run();
     assert_eq!(c2.regs[8], (0x02 + 0x50) as u8);
     assert_eq!(c2.regs[9], 0x50);
@@ -347,10 +347,10 @@ fn test_cpu_reg_subxy() {
     let w2 : [u8; 2] = [0x69, 0xef]; // Set reg 9 to 0xad
     let w3 : [u8; 2] = [0x88, 0x95]; // reg 8 (+) reg 9
     let w4 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    c.mem.write16((c.pc+4).into(), w3);
-    c.mem.write16((c.pc+6).into(), w4);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
+    c.mem.write16((c.pc+6).into(), w4).unwrap();
     c._run();
     assert_eq!(c.regs[8], (0xbe - 0xef) as u8, "Testing underflow");
     assert_eq!(c.regs[9], 0xef);
@@ -362,10 +362,10 @@ fn test_cpu_reg_subxy() {
     let w2 : [u8; 2] = [0x69, 0x02]; // Set reg 9 to 0xad
     let w3 : [u8; 2] = [0x88, 0x95]; // reg 8 (+) reg 9
     let w4 : [u8; 2] = [0x00, 0x00]; // Exit
-    c2.mem.write16((c2.pc).into(), w1);
-    c2.mem.write16((c2.pc+2).into(), w2);
-    c2.mem.write16((c2.pc+4).into(), w3);
-    c2.mem.write16((c2.pc+6).into(), w4);
+    c2.mem.write16((c2.pc).into(), w1).unwrap();
+    c2.mem.write16((c2.pc+2).into(), w2).unwrap();
+    c2.mem.write16((c2.pc+4).into(), w3).unwrap();
+    c2.mem.write16((c2.pc+6).into(), w4).unwrap();
     c2._run();
     assert_eq!(c2.regs[8], (0x50 - 0x02) as u8, "Testing no underflow");
     assert_eq!(c2.regs[9], 0x02);
@@ -380,10 +380,10 @@ fn test_cpu_reg_subyx() {
     let w2 : [u8; 2] = [0x69, 0xef]; // Set reg 9 to 0xad
     let w3 : [u8; 2] = [0x88, 0x97]; // reg 8 (+) reg 9
     let w4 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    c.mem.write16((c.pc+4).into(), w3);
-    c.mem.write16((c.pc+6).into(), w4);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
+    c.mem.write16((c.pc+6).into(), w4).unwrap();
     c._run();
     assert_eq!(c.regs[8], (0xef - 0xbe) as u8, "Testing no underflow");
     assert_eq!(c.regs[9], 0xef);
@@ -395,10 +395,10 @@ fn test_cpu_reg_subyx() {
     let w2 : [u8; 2] = [0x69, 0x02]; // Set reg 9 to 0xad
     let w3 : [u8; 2] = [0x88, 0x97]; // reg 8 (+) reg 9
     let w4 : [u8; 2] = [0x00, 0x00]; // Exit
-    c2.mem.write16((c2.pc).into(), w1);
-    c2.mem.write16((c2.pc+2).into(), w2);
-    c2.mem.write16((c2.pc+4).into(), w3);
-    c2.mem.write16((c2.pc+6).into(), w4);
+    c2.mem.write16((c2.pc).into(), w1).unwrap();
+    c2.mem.write16((c2.pc+2).into(), w2).unwrap();
+    c2.mem.write16((c2.pc+4).into(), w3).unwrap();
+    c2.mem.write16((c2.pc+6).into(), w4).unwrap();
     c2._run();
     assert_eq!(c2.regs[8], (0x02 - 0x50) as u8, "Testing underflow");
     assert_eq!(c2.regs[9], 0x02);
@@ -412,18 +412,18 @@ fn test_cpu_left_shift() {
     let w1 : [u8; 2] = [0x68, 0x80]; // Set reg 8 to 0x80
     let w2 : [u8; 2] = [0x88, 0x0E]; // Shift reg 8 left 1
     let w3 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    c.mem.write16((c.pc+4).into(), w3);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
     c._run();
     assert_eq!(c.regs[8], 0x00);
     assert_eq!(c.regs[15], 0x01);
     let w4 : [u8; 2] = [0x68, 0x01]; // Set reg 8 to 0x01
     let w5 : [u8; 2] = [0x88, 0x0E]; // Shift reg 8 left 1
     let w6 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w4);
-    c.mem.write16((c.pc+2).into(), w5);
-    c.mem.write16((c.pc+4).into(), w6);
+    c.mem.write16((c.pc).into(), w4).unwrap();
+    c.mem.write16((c.pc+2).into(), w5).unwrap();
+    c.mem.write16((c.pc+4).into(), w6).unwrap();
     c._run();
     assert_eq!(c.regs[8], 0x02);
     assert_eq!(c.regs[15], 0x00);
@@ -436,18 +436,18 @@ fn test_cpu_right_shift() {
     let w1 : [u8; 2] = [0x68, 0x80]; // Set reg 8 to 0x80
     let w2 : [u8; 2] = [0x88, 0x06]; // Shift reg 8 left 1
     let w3 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    c.mem.write16((c.pc+4).into(), w3);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
     c._run();
     assert_eq!(c.regs[8], 0x40);
     assert_eq!(c.regs[15], 0x00);
     let w4 : [u8; 2] = [0x68, 0x01]; // Set reg 8 to 0x01
     let w5 : [u8; 2] = [0x88, 0x06]; // Shift reg 8 left 1
     let w6 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w4);
-    c.mem.write16((c.pc+2).into(), w5);
-    c.mem.write16((c.pc+4).into(), w6);
+    c.mem.write16((c.pc).into(), w4).unwrap();
+    c.mem.write16((c.pc+2).into(), w5).unwrap();
+    c.mem.write16((c.pc+4).into(), w6).unwrap();
     c._run();
     assert_eq!(c.regs[8], 0x00);
     assert_eq!(c.regs[15], 0x01);
@@ -459,8 +459,8 @@ fn test_cpu_set_ireg() {
     let mut c : CPU = make_cpu(mem);
     let w1 : [u8; 2] = [0xA9, 0x99]; // Set I reg to 0x999
     let w2 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
     c._run();
     assert_eq!(c.ireg, 0x999);
 }
@@ -473,9 +473,9 @@ fn test_cpu_jump_offset() {
     let w1 : [u8; 2] = [0xB8, 0x00]; // Jump to address 0x999
     let w2 : [u8; 2] = [0x00, 0xE0]; // Clear display
     let w3 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16(0x820, w2);
-    c.mem.write16(0x822 + 2, w3);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16(0x820, w2).unwrap();
+    c.mem.write16(0x822 + 2, w3).unwrap();
     c.display.screen[0][0] = true;
     c.display.screen[1][2] = true;
     c.display.screen[2][4] = true;
@@ -494,8 +494,8 @@ fn test_cpu_radnom() {
     c.regs[0] = 0x20;
     let w1 : [u8; 2] = [0xC8, 0x25]; // Jump to address 0x999
     let w2 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
     c._run();
 
     assert_eq!(c.regs[8] | 0x25, 0x25);
@@ -508,9 +508,9 @@ fn test_cpu_dxyn() {
     let mut c : CPU = make_cpu(mem);
     let w1 : [u8; 2] = [0xA9, 0x99]; // Jump to address 0x999
     let w2 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    // c.mem.write16((c.pc+4).into(), w3);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    // c.mem.write16((c.pc+4).into(), w3).unwrap();
     c._run();
     assert_eq!(c.ireg, 0x999);
 }
@@ -525,12 +525,12 @@ fn test_cpu_time() {
     let w4 : [u8; 2] = [0xF9, 0x18]; // Set Sound timer to be value in reg 9
     let w5 : [u8; 2] = [0xF2, 0x07]; // Set reg 2 to Delay timer
     let w6 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    c.mem.write16((c.pc+4).into(), w3);
-    c.mem.write16((c.pc+6).into(), w4);
-    c.mem.write16((c.pc+8).into(), w5);
-    c.mem.write16((c.pc+10).into(), w6);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
+    c.mem.write16((c.pc+6).into(), w4).unwrap();
+    c.mem.write16((c.pc+8).into(), w5).unwrap();
+    c.mem.write16((c.pc+10).into(), w6).unwrap();
     c._run();
     assert_eq!(c.regs[8], 0x80);
     assert_eq!(c.regs[9], 0x20);
@@ -547,10 +547,10 @@ fn test_cpu_add_regi() {
     let w2 : [u8; 2] = [0xA0, 0x20]; // Set I reg to 0x020
     let w3 : [u8; 2] = [0xF8, 0x1E]; // reg 8 + i reg
     let w4 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    c.mem.write16((c.pc+4).into(), w3);
-    c.mem.write16((c.pc+6).into(), w4);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
+    c.mem.write16((c.pc+6).into(), w4).unwrap();
     c._run();
     assert_eq!(c.regs[8], 0x80);
     assert_eq!(c.ireg, 0x80 + 0x20, "Failed the 1st test");
@@ -559,10 +559,10 @@ fn test_cpu_add_regi() {
     let w5 : [u8; 2] = [0xAF, 0xFF]; // Set I reg to 0xFFF
     let w6 : [u8; 2] = [0xF8, 0x1E]; // reg 8 + i reg
     let w7 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w4);
-    c.mem.write16((c.pc+2).into(), w5);
-    c.mem.write16((c.pc+4).into(), w6);
-    c.mem.write16((c.pc+6).into(), w7);
+    c.mem.write16((c.pc).into(), w4).unwrap();
+    c.mem.write16((c.pc+2).into(), w5).unwrap();
+    c.mem.write16((c.pc+4).into(), w6).unwrap();
+    c.mem.write16((c.pc+6).into(), w7).unwrap();
     c._run();
     assert_eq!(c.regs[8], 0x80);
     assert_eq!(c.ireg, (0x080 + 0xfff), "Failed the second test");
@@ -576,15 +576,45 @@ fn test_cpu_font_char() {
     let w1 : [u8; 2] = [0x68, 0x84]; // Set reg 8 to 0x84
     let w2 : [u8; 2] = [0xF8, 0x29]; // Set I reg to address of the 4th char
     let w3 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    c.mem.write16((c.pc+4).into(), w3);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
     c._run();
     assert_eq!(c.regs[8], 0x84);
     assert_eq!(c.ireg, (_FONT_START as u16) + ((5 * 4) as u16) , "Checking for font");
     assert_eq!(c.mem.data[c.ireg as usize], 0x90 , "Checking for first byte of font");
 }
 
+#[test]
+fn test_cpu_set_font() {
+    let mut mem: memory::Mem = memory::make_memory();
+    mem.store_game(vec![0x68, 0x01,  // v8 = 1
+                        0xF8, 0x29,  // i = small 1
+                        0x00, 0x00,
+                        0x68, 0x07,  // v8 = 7
+                        0xF8, 0x30,  // i = big 7
+                        0x00, 0x00]);
+    let mut c : CPU = make_cpu(mem);
+    let vip = font::Font { style: font::Style::Vip, address: 0x000 };
+    c.set_font(vip);
+    assert_eq!(c.mem.data[..font::FONT_SIZE], vip.bytes()[..]);
+    /* The old font is gone */
+    assert!(c.mem.data[font::FONT_SIZE..memory::_PROG_START].iter().all(|b| *b == 0));
+
+    c._run();
+    assert_eq!(c.ireg, 0x005);
+    assert_eq!(c.mem.data[0x005..0x00A], font::VIP[5..10]);
+    c._run();
+    assert_eq!(c.ireg, (font::SMALL_SIZE + 70) as u16);
+    assert_eq!(c.mem.data[c.ireg as usize..c.ireg as usize + 10], font::BIG_DIGITS[70..80]);
+
+    /* Still there after starting over with fresh memory */
+    c.hard_reset(vec![0x00, 0x00]);
+    assert_eq!(c.font, vip);
+    assert_eq!(c.mem.data[..font::FONT_SIZE], vip.bytes()[..]);
+    assert_eq!(c.mem.data[_FONT_START + font::FONT_SIZE - 1], 0x00);
+}
+
 #[test]
 fn test_cpu_binary_coded_dec_conv() {
     let mem: memory::Mem = memory::make_memory();
@@ -593,29 +623,29 @@ fn test_cpu_binary_coded_dec_conv() {
     let w2 : [u8; 2] = [0xA9, 0x99]; // Set I reg to 0x999
     let w3 : [u8; 2] = [0xF8, 0x33]; // Set mem[ireg] = dec version of 0x9c = 156
     let w4 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    c.mem.write16((c.pc+4).into(), w3);
-    c.mem.write16((c.pc+6).into(), w4);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
+    c.mem.write16((c.pc+6).into(), w4).unwrap();
     c._run();
     assert_eq!(c.regs[8], 0x9c);
-    assert_eq!(c.mem.read8(0x999), 0x01, "Checking first address");
-    assert_eq!(c.mem.read8(0x999+1), 0x05, "Checking second address");
-    assert_eq!(c.mem.read8(0x999+2), 0x06, "Checking third address");
+    assert_eq!(c.mem.read8(0x999).unwrap(), 0x01, "Checking first address");
+    assert_eq!(c.mem.read8(0x999+1).unwrap(), 0x05, "Checking second address");
+    assert_eq!(c.mem.read8(0x999+2).unwrap(), 0x06, "Checking third address");
 
     let w5 : [u8; 2] = [0x68, 0xff]; // Set reg 8 to 0x9c
     let w6 : [u8; 2] = [0xA9, 0x99]; // Set I reg to 0x999
     let w7 : [u8; 2] = [0xF8, 0x33]; // Set mem[ireg] = dec version of 0x9c = 156
     let w8 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w5);
-    c.mem.write16((c.pc+2).into(), w6);
-    c.mem.write16((c.pc+4).into(), w7);
-    c.mem.write16((c.pc+6).into(), w8);
+    c.mem.write16((c.pc).into(), w5).unwrap();
+    c.mem.write16((c.pc+2).into(), w6).unwrap();
+    c.mem.write16((c.pc+4).into(), w7).unwrap();
+    c.mem.write16((c.pc+6).into(), w8).unwrap();
     c._run();
     assert_eq!(c.regs[8], 0xff);
-    assert_eq!(c.mem.read8(0x999), 0x02, "Checking first address");
-    assert_eq!(c.mem.read8(0x999+1), 0x05, "Checking second address");
-    assert_eq!(c.mem.read8(0x999+2), 0x05, "Checking third address");
+    assert_eq!(c.mem.read8(0x999).unwrap(), 0x02, "Checking first address");
+    assert_eq!(c.mem.read8(0x999+1).unwrap(), 0x05, "Checking second address");
+    assert_eq!(c.mem.read8(0x999+2).unwrap(), 0x05, "Checking third address");
 }
 
 #[test]
@@ -625,7 +655,7 @@ fn test_cpu_store() {
     /* Store values into the registers */
     for i in 0..16 {
         let w : [u8; 2] = [0x60 + i, i as u8]; // Set reg 8 to 0x9c
-        c.mem.write16((c.pc+((2*i) as u16)).into(), w);
+        c.mem.write16((c.pc+((2*i) as u16)).into(), w).unwrap();
     }
     c._run();
     assert_eq!(c.regs[0], 0x0);
@@ -636,20 +666,20 @@ fn test_cpu_store() {
     let w1 : [u8; 2] = [0xA9, 0x99]; // Set I reg to 0x999
     let w2 : [u8; 2] = [0xF8, 0x55]; // Load the values from registers 0-8 into memory @ i reg
     let w3 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    c.mem.write16((c.pc+4).into(), w3);
-    c._run();
-    assert_eq!(c.mem.read8(0x999), 0x0, "Checking 0 address");
-    assert_eq!(c.mem.read8(0x999+1), 0x01, "Checking 1 address");
-    assert_eq!(c.mem.read8(0x999+2), 0x02, "Checking 2 address");
-    assert_eq!(c.mem.read8(0x999+3), 0x03, "Checking 3 address");
-    assert_eq!(c.mem.read8(0x999+4), 0x04, "Checking 4 address");
-    assert_eq!(c.mem.read8(0x999+5), 0x05, "Checking 5 address");
-    assert_eq!(c.mem.read8(0x999+6), 0x06, "Checking 6 address");
-    assert_eq!(c.mem.read8(0x999+7), 0x07, "Checking 7 address");
-    assert_eq!(c.mem.read8(0x999+8), 0x08, "Checking 8 address");
-    assert_eq!(c.mem.read8(0x999+9), 0x00, "Checking 9 address");
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
+    c._run();
+    assert_eq!(c.mem.read8(0x999).unwrap(), 0x0, "Checking 0 address");
+    assert_eq!(c.mem.read8(0x999+1).unwrap(), 0x01, "Checking 1 address");
+    assert_eq!(c.mem.read8(0x999+2).unwrap(), 0x02, "Checking 2 address");
+    assert_eq!(c.mem.read8(0x999+3).unwrap(), 0x03, "Checking 3 address");
+    assert_eq!(c.mem.read8(0x999+4).unwrap(), 0x04, "Checking 4 address");
+    assert_eq!(c.mem.read8(0x999+5).unwrap(), 0x05, "Checking 5 address");
+    assert_eq!(c.mem.read8(0x999+6).unwrap(), 0x06, "Checking 6 address");
+    assert_eq!(c.mem.read8(0x999+7).unwrap(), 0x07, "Checking 7 address");
+    assert_eq!(c.mem.read8(0x999+8).unwrap(), 0x08, "Checking 8 address");
+    assert_eq!(c.mem.read8(0x999+9).unwrap(), 0x00, "Checking 9 address");
 }
 
 #[test]
@@ -658,19 +688,19 @@ fn test_cpu_load() {
     let mut c : CPU = make_cpu(mem);
     /* Store values into 0x999 + i */
     for i in 0..16 {
-        c.mem.write8((0x999+i as u16).into(), i as u8);
+        c.mem.write8((0x999+i as u16).into(), i as u8).unwrap();
     }
-    assert_eq!(c.mem.read8(0x999), 0x0);
-    assert_eq!(c.mem.read8(0x999+1), 0x1);
-    assert_eq!(c.mem.read8(0x999+0xf), 0xf);
+    assert_eq!(c.mem.read8(0x999).unwrap(), 0x0);
+    assert_eq!(c.mem.read8(0x999+1).unwrap(), 0x1);
+    assert_eq!(c.mem.read8(0x999+0xf).unwrap(), 0xf);
 
     /* Load values from the registers into the address of 0x999 */
     let w1 : [u8; 2] = [0xA9, 0x99]; // Set I reg to 0x999
     let w2 : [u8; 2] = [0xF6, 0x65]; // Load the values from registers 0-6 into memory @ i reg
     let w3 : [u8; 2] = [0x00, 0x00]; // Exit
-    c.mem.write16((c.pc).into(), w1);
-    c.mem.write16((c.pc+2).into(), w2);
-    c.mem.write16((c.pc+4).into(), w3);
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
     c._run();
     assert_eq!(c.regs[0], 0x00, "Checking 0 address");
     assert_eq!(c.regs[1], 0x01, "Checking 1 address");
@@ -682,3 +712,286 @@ fn test_cpu_load() {
     assert_eq!(c.regs[7], 0x00, "Checking 7 address");
     assert_eq!(c.regs[8], 0x00, "Checking 8 address");
 }
+
+#[test]
+fn test_cpu_run_frame_timers() {
+    let mem: memory::Mem = memory::make_memory();
+    let mut c : CPU = make_cpu(mem);
+    c.delay_timer.set_timer(2);
+    c.sound_timer.set_timer(1);
+    c.mem.write16((c.pc).into(), [0x12, 0x00]).unwrap(); // Jump to self
+    assert!(c.sound_timer.is_running());
+
+    /* Timers go down once per frame, and stop at 0 */
+    c.run_frame(10, 0xf0);
+    assert_eq!(c.delay_timer.value, 1);
+    assert_eq!(c.sound_timer.value, 0);
+    assert!(!c.sound_timer.is_running());
+    c.run_frame(10, 0xf0);
+    c.run_frame(10, 0xf0);
+    assert_eq!(c.delay_timer.value, 0);
+    assert_eq!(c.sound_timer.value, 0);
+}
+
+#[test]
+fn test_cpu_display_wait() {
+    let mem: memory::Mem = memory::make_memory();
+    let mut c : CPU = make_cpu(mem);
+    let w1 : [u8; 2] = [0xD0, 0x01]; // Draw 1 row @ (v0, v0)
+    let w2 : [u8; 2] = [0xD0, 0x01]; // Draw it again
+    let w3 : [u8; 2] = [0x12, 0x04]; // Jump to self
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.mem.write16((c.pc+4).into(), w3).unwrap();
+    c.ireg = _FONT_START as u16; // Top row of 0 => 0xF0
+
+    /* Without the quirk both draws happen in the same frame */
+    c.run_frame(10, 0xf0);
+    assert_eq!(c.pc, 0x204);
+    assert!(!c.display.screen[0][0]);
+
+    /* With it, the second draw waits for the next frame */
+    c.pc = 0x200;
+    c.quirks.display_wait = true;
+    c.run_frame(10, 0xf0);
+    assert_eq!(c.pc, 0x202);
+    assert!(c.display.screen[0][0]);
+    c.run_frame(10, 0xf0);
+    assert_eq!(c.pc, 0x204);
+    assert!(!c.display.screen[0][0]);
+}
+
+#[test]
+fn test_cpu_dxyn_clip() {
+    let mem: memory::Mem = memory::make_memory();
+    let mut c : CPU = make_cpu(mem);
+    let w1 : [u8; 2] = [0xD0, 0x15]; // Draw the 0 font @ (v0, v1)
+    let w2 : [u8; 2] = [0x00, 0x00]; // Exit
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.ireg = _FONT_START as u16; // 0xF0, 0x90, 0x90, 0x90, 0xF0
+    c.regs[0] = 62;
+    c.regs[1] = 29;
+    c._run();
+
+    /* Only the part of the sprite inside of the screen gets drawn */
+    assert!(c.display.screen[29][62]);
+    assert!(c.display.screen[29][63]);
+    assert!(c.display.screen[31][62]);
+    assert!(!c.display.screen[31][63]);
+    assert!(!c.display.screen[0][62]);
+    assert!(!c.display.screen[29][0]);
+    assert!(!c.display.screen[29][1]);
+}
+
+#[test]
+fn test_cpu_dxyn_wrap() {
+    let mem: memory::Mem = memory::make_memory();
+    let mut c : CPU = make_cpu(mem);
+    let w1 : [u8; 2] = [0xD0, 0x15]; // Draw the 0 font @ (v0, v1)
+    let w2 : [u8; 2] = [0x00, 0x00]; // Exit
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.ireg = _FONT_START as u16; // 0xF0, 0x90, 0x90, 0x90, 0xF0
+    c.quirks.wrap_sprites = true;
+    c.regs[0] = 62;
+    c.regs[1] = 29;
+    c._run();
+
+    /* Whatever goes off the edge comes back on the other side */
+    assert!(c.display.screen[29][62]);
+    assert!(c.display.screen[29][1]);
+    assert!(c.display.screen[30][1]);
+    assert!(!c.display.screen[30][0]);
+    assert!(c.display.screen[0][1]);
+    assert!(c.display.screen[1][62]);
+    assert!(c.display.screen[1][63]);
+}
+
+#[test]
+fn test_cpu_dxyn_start_wraps() {
+    let mem: memory::Mem = memory::make_memory();
+    let mut c : CPU = make_cpu(mem);
+    let w1 : [u8; 2] = [0xD0, 0x11]; // Draw a row of the 0 font @ (v0, v1)
+    let w2 : [u8; 2] = [0x00, 0x00]; // Exit
+    c.mem.write16((c.pc).into(), w1).unwrap();
+    c.mem.write16((c.pc+2).into(), w2).unwrap();
+    c.ireg = _FONT_START as u16;
+    c.regs[0] = 64 + 4;
+    c.regs[1] = 32 + 2;
+    c._run();
+
+    /* Starting coords get wrapped even when clipping */
+    assert!(c.display.screen[2][4]);
+    assert!(c.display.screen[2][7]);
+    assert!(!c.display.screen[2][8]);
+}
+
+#[test]
+fn test_cpu_soft_reset() {
+    let mut mem: memory::Mem = memory::make_memory();
+    mem.store_game(vec![0x60, 0x05,  // v0 = 5
+                        0xC1, 0xFF,  // v1 = rand
+                        0xA2, 0x34,  // i = 0x234
+                        0x22, 0x0A,  // call 0x20a
+                        0x00, 0x00,
+                        0xF0, 0x15,  // delay = v0
+                        0xD0, 0x05,  // draw
+                        0x12, 0x0E]); // loop
+    let mut c : CPU = make_cpu(mem);
+    c.set_seed(99);
+    c.run_frame(8, 0xf0);
+    let first_rand : u8 = c.regs[1];
+    assert_ne!(c.pc, memory::_PROG_START as u16);
+    assert_eq!(c.stack.len(), 1);
+
+    c.soft_reset();
+    assert_eq!(c.pc, memory::_PROG_START as u16);
+    assert_eq!(c.regs, [0; 16]);
+    assert_eq!(c.ireg, 0);
+    assert!(c.stack.is_empty());
+    assert!(!c.delay_timer.is_running());
+    assert!(c.display.screen.iter().flatten().all(|p| !p));
+    assert_eq!(c.mem.read8(0x200).unwrap(), 0x60);
+
+    /* Same seed, so the same random numbers the second time around */
+    c.run_frame(8, 0xf0);
+    assert_eq!(c.regs[1], first_rand);
+}
+
+#[test]
+fn test_cpu_hard_reset() {
+    let mut mem: memory::Mem = memory::make_memory();
+    mem.store_game(vec![0x60, 0x05, 0x12, 0x02]);
+    let mut c : CPU = make_cpu(mem);
+    c.run_frame(4, 0xf0);
+    c.mem.write8(0x300, 0xAB).unwrap();
+
+    c.hard_reset(vec![0x61, 0x07, 0x12, 0x02]);
+    assert_eq!(c.pc, memory::_PROG_START as u16);
+    assert_eq!(c.mem.read8(0x300).unwrap(), 0x00);
+    c.run_frame(4, 0xf0);
+    assert_eq!(c.regs[0], 0);
+    assert_eq!(c.regs[1], 7);
+}
+
+#[test]
+fn test_cpu_address_zero() {
+    let mut mem: memory::Mem = memory::make_memory();
+    mem.store_game(vec![0x60, 0x2a,  // v0 = 0x2a
+                        0xA0, 0x00,  // i = 0
+                        0xF0, 0x55,  // store v0 @ 0
+                        0x61, 0x00,  // v1 = 0
+                        0xF1, 0x65,  // load v0, v1 from 0
+                        0x00, 0x00]);
+    let mut c : CPU = make_cpu(mem);
+    c._run();
+    assert_eq!(c.mem.data[0x000], 0x2a);
+    assert_eq!(c.regs[0], 0x2a);
+    assert_eq!(c.regs[1], 0x00);
+    assert_eq!(c.fault, None);
+}
+
+#[test]
+fn test_cpu_memory_fault() {
+    /* Store runs off the end of memory */
+    let mut mem: memory::Mem = memory::make_memory();
+    mem.store_game(vec![0xAF, 0xFE,  // i = 0xffe
+                        0xF2, 0x55,  // store v0 - v2
+                        0x60, 0x01]); // never gets here
+    let mut c : CPU = make_cpu(mem);
+    assert!(!c.run_frame(10, 0xf0));
+    assert_eq!(c.fault, Some(Fault::Bus(BusError::OutOfRange(0x1000))));
+    assert_eq!(c.pc, 0x204);
+    assert_eq!(c.regs[0], 0x00);
+
+    /* Stays stopped until it's reset */
+    assert!(!c.run_frame(10, 0xf0));
+    assert_eq!(c.pc, 0x204);
+    c.soft_reset();
+    assert_eq!(c.fault, None);
+
+    /* So does loading, BCD and drawing */
+    for (i, inst) in [[0xF2, 0x65], [0xF0, 0x33], [0xD0, 0x05]].iter().enumerate() {
+        let mut mem: memory::Mem = memory::make_memory();
+        mem.store_game(vec![0xAF, 0xFE, inst[0], inst[1]]);
+        let mut c : CPU = make_cpu(mem);
+        assert!(!c.run_frame(10, 0xf0), "instruction {}", i);
+        assert_eq!(c.fault, Some(Fault::Bus(BusError::OutOfRange(0x1000))), "instruction {}", i);
+    }
+
+    /* Running off the end of memory */
+    let mut c : CPU = make_cpu(memory::make_memory());
+    c.pc = 0xfff;
+    assert!(!c.exec(0xf0));
+    assert_eq!(c.fault, Some(Fault::Bus(BusError::OutOfRange(0x1000))));
+
+    /* With 64K there's no address after the last word, running it or
+     * skipping past it stops too */
+    for (pc, inst) in [(0xfffe, [0x60, 0x01]), (0xfffc, [0x30, 0x00])] {
+        let mut c : CPU<bus::XoMem> = make_cpu(bus::make_xo_memory());
+        c.mem.write16(pc, inst).unwrap();
+        c.mem.write16(0xfffe, [0x60, 0x01]).unwrap();
+        c.pc = pc as u16;
+        while c.exec(0xf0) {}
+        assert_eq!(c.fault, Some(Fault::Bus(BusError::OutOfRange(0x10000))), "pc {:#x}", pc);
+    }
+}
+
+#[test]
+fn test_cpu_stack_limits() {
+    /* Calls itself forever, the 17th call doesn't fit */
+    let mut mem: memory::Mem = memory::make_memory();
+    mem.store_game(vec![0x22, 0x00]);
+    let mut c : CPU = make_cpu(mem);
+    assert!(!c.run_frame(100, 0xf0));
+    assert_eq!(c.fault, Some(Fault::StackOverflow(stack::SCHIP_DEPTH)));
+    assert_eq!(c.stack.pointer(), stack::SCHIP_DEPTH);
+
+    /* Returning with nothing to return to */
+    let mut mem: memory::Mem = memory::make_memory();
+    mem.store_game(vec![0x00, 0xEE]);
+    let mut c : CPU = make_cpu(mem);
+    assert!(!c.exec(0xf0));
+    assert_eq!(c.fault, Some(Fault::StackUnderflow));
+
+    /* Wrapping keeps going */
+    let mut mem: memory::Mem = memory::make_memory();
+    mem.store_game(vec![0x22, 0x00]);
+    let mut c : CPU = make_cpu(mem);
+    c.stack = stack::make_stack(stack::VIP_DEPTH, stack::Overflow::Wrap);
+    assert!(c.run_frame(100, 0xf0));
+    assert_eq!(c.stack.pointer(), 100 % stack::VIP_DEPTH);
+    c.soft_reset();
+    assert!(c.stack.is_empty());
+}
+
+#[test]
+fn test_cpu_wrap_memory() {
+    let mut mem: memory::Mem = memory::make_memory();
+    mem.store_game(vec![0x60, 0x01,  // v0 = 1
+                        0x61, 0x02,  // v1 = 2
+                        0x62, 0x03,  // v2 = 3
+                        0xAF, 0xFE,  // i = 0xffe
+                        0xF2, 0x55,  // store v0 - v2, the last one @ 0
+                        0x00, 0x00]);
+    let mut c : CPU = make_cpu(mem);
+    c.quirks.wrap_memory = true;
+    c._run();
+    assert_eq!(c.fault, None);
+    assert_eq!(c.mem.data[0xffe], 0x01);
+    assert_eq!(c.mem.data[0xfff], 0x02);
+    assert_eq!(c.mem.data[0x000], 0x03);
+
+    /* I past the end starts over at 0 too */
+    c.soft_reset();
+    c.mem.store_game(vec![0x60, 0x10,  // v0 = 0x10
+                          0xAF, 0xFF,  // i = 0xfff
+                          0xF0, 0x1E,  // i = 0x100f
+                          0xF0, 0x65,  // load v0 from 0x00f
+                          0x00, 0x00]);
+    c.mem.data[0x00f] = 0x77;
+    c._run();
+    assert_eq!(c.fault, None);
+    assert_eq!(c.regs[0], 0x77);
+}
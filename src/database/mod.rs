@@ -0,0 +1,202 @@
+use super::*;
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Deserialize;
+
+/* Built in rom database, in the same shape as programs.json from the
+ * community chip-8-database (https://github.com/chip-8/chip-8-database),
+ * so entries can be copied over from there as they are. Only the fields we
+ * use are read, everything else in an entry is ignored:
+ *
+ *   [{
+ *     "title": "Pong",
+ *     "release": "1990",
+ *     "authors": ["Paul Vervalin"],
+ *     "roms": {
+ *       "<sha1>": {
+ *         "platforms": ["chip48"],           <- best platform first
+ *         "quirkyPlatforms": {"chip48": {"vblank": true}},
+ *         "tickrate": 15,                    <- instructions per frame
+ *         "colors": {"pixels": ["#000000", "#ffffff"]},
+ *         "keys": {"up": 1, "down": 4}
+ *       }
+ *     }
+ *   }]
+ *
+ * Whatever the database says comes after the command line and the config */
+
+/* The database that ships with the emulator */
+const BUILTIN: &str = include_str!("../../data/programs.json");
+
+/* A platform, (id, name, display_wait, wrap_sprites, font, stack depth, memory, tickrate).
+ * The tickrate is the defaultTickrate from the chip-8-database's platforms.json,
+ * for roms that don't have their own */
+pub type Platform = (&'static str, &'static str, bool, bool, font::Style, usize, usize, u16);
+
+/* Platforms, only the chip8 ones can actually be run */
+pub const PLATFORMS: [Platform; 8] = [
+    ("originalChip8", "CHIP-8", true, false, font::Style::Vip, stack::VIP_DEPTH, bus::CHIP8_SIZE, 15),
+    ("hybridVIP", "CHIP-8 (hybrid VIP)", true, false, font::Style::Vip, stack::VIP_DEPTH, bus::CHIP8_SIZE, 15),
+    ("modernChip8", "CHIP-8 (modern)", false, false, font::Style::Chip48, stack::SCHIP_DEPTH, bus::CHIP8_SIZE, 12),
+    ("chip48", "CHIP-48", false, false, font::Style::Chip48, stack::SCHIP_DEPTH, bus::CHIP8_SIZE, 30),
+    ("superchip1", "SCHIP 1.0", false, false, font::Style::Schip, stack::SCHIP_DEPTH, bus::CHIP8_SIZE, 30),
+    ("superchip", "SCHIP 1.1", false, false, font::Style::Schip, stack::SCHIP_DEPTH, bus::CHIP8_SIZE, 30),
+    ("megachip8", "MEGA-CHIP", false, false, font::Style::Schip, stack::SCHIP_DEPTH, bus::CHIP8_SIZE, 1000),
+    ("xochip", "XO-CHIP", false, true, font::Style::Chip48, stack::SCHIP_DEPTH, bus::XO_SIZE, 100),
+];
+
+/* Platforms this emulator runs */
+pub const SUPPORTED: [&str; 4] = ["originalChip8", "hybridVIP", "modernChip8", "chip48"];
+
+/* A game, possibly with a few versions of the rom */
+#[derive(Deserialize)]
+pub struct Program {
+    pub title : String,
+    pub release : Option<String>,
+    #[serde(default)]
+    pub authors : Vec<String>,
+    pub roms : HashMap<String, Rom>, /* By sha1 */
+}
+
+/* One version of a game */
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rom {
+    #[serde(default)]
+    pub platforms : Vec<String>, /* Platform ids it runs on, best first */
+    #[serde(default)]
+    pub quirky_platforms : HashMap<String, HashMap<String, bool>>, /* Quirks that differ from the platform's */
+    pub tickrate : Option<u16>, /* Instructions per frame */
+    pub colors : Option<Colors>,
+    #[serde(default)]
+    pub keys : BTreeMap<String, u8>, /* What a key does => chip8 key */
+}
+
+/* Colours the game looks best in */
+#[derive(Deserialize)]
+pub struct Colors {
+    #[serde(default)]
+    pub pixels : Vec<String>, /* Background first, then the foreground */
+}
+
+pub struct Database {
+    pub programs : Vec<Program>,
+    by_sha1 : HashMap<String, usize>, /* Rom sha1 => index into programs */
+}
+
+/* A rom that was found, and the game it is from */
+pub struct Entry<'a> {
+    pub program : &'a Program,
+    pub rom : &'a Rom,
+}
+
+/* Read a database out of TEXT, a programs.json */
+pub fn parse(text : &str) -> Result<Database, serde_json::Error> {
+    let programs : Vec<Program> = serde_json::from_str(text)?;
+    let mut db = Database { programs: programs, by_sha1: HashMap::new() };
+    for (i, program) in db.programs.iter().enumerate() {
+        for sha1 in program.roms.keys() {
+            db.by_sha1.insert(sha1.to_lowercase(), i);
+        }
+    }
+    return Ok(db);
+}
+
+/* The database that ships with the emulator */
+pub fn builtin() -> Database {
+    return parse(BUILTIN).expect("built in rom database is broken");
+}
+
+/* Name of the platform with ID, or ID itself if we don't know it */
+pub fn platform_name(id : &str) -> &str {
+    for (platform, name, _, _, _, _, _, _) in PLATFORMS {
+        if platform == id {
+            return name;
+        }
+    }
+    return id;
+}
+
+impl Database {
+    /* Look up the rom with ROM_SHA1 */
+    pub fn lookup(&self, rom_sha1 : &str) -> Option<Entry<'_>> {
+        let i : usize = *self.by_sha1.get(&rom_sha1.to_lowercase())?;
+        let program : &Program = &self.programs[i];
+        let rom : &Rom = program.roms.iter()
+            .find(|(sha1, _)| sha1.to_lowercase() == rom_sha1.to_lowercase())?.1;
+        return Some(Entry { program: program, rom: rom });
+    }
+}
+
+impl<'a> Entry<'a> {
+    /* Best platform the rom runs on that we can run too, None if there isn't one */
+    pub fn platform(&self) -> Option<&'a str> {
+        let platform : &String = self.rom.platforms.iter().find(|p| SUPPORTED.contains(&p.as_str()))?;
+        return Some(platform.as_str());
+    }
+
+    /* Quirks for PLATFORM, with the rom's own quirks on top */
+    pub fn quirks(&self, platform : &str) -> cpu::Quirks {
+        let mut quirks : cpu::Quirks = cpu::make_quirks();
+        for (id, _, display_wait, wrap_sprites, _, _, _, _) in PLATFORMS {
+            if id == platform {
+                quirks.display_wait = display_wait;
+                quirks.wrap_sprites = wrap_sprites;
+            }
+        }
+        if let Some(quirky) = self.rom.quirky_platforms.get(platform) {
+            for (name, on) in quirky.iter() {
+                match name.as_str() {
+                    "vblank" => quirks.display_wait = *on,
+                    "wrap" => quirks.wrap_sprites = *on,
+                    _ => {}, /* Quirks we don't have */
+                }
+            }
+        }
+        return quirks;
+    }
+
+    /* What the database would set, as if it came from the config file */
+    pub fn settings(&self) -> config::Settings {
+        let mut out : config::Settings = config::make_settings();
+        out.quirks = self.platform().map(|p| self.quirks(p));
+        let platform = self.platform().and_then(|p| PLATFORMS.iter().find(|platform| platform.0 == p));
+        out.speed = self.rom.tickrate.filter(|t| *t > 0).or(platform.map(|platform| platform.7));
+        out.font = platform.map(|platform| platform.4);
+        out.stack_depth = platform.map(|platform| platform.5);
+        out.memory = platform.map(|platform| platform.6);
+        if let Some(colors) = &self.rom.colors {
+            if let [off, on, ..] = colors.pixels.as_slice() {
+                out.palette = display::parse_palette(&format!("{},{}", on, off));
+            }
+        }
+        return out;
+    }
+
+    /* One line about the game, like: Pong (1990) by Paul Vervalin, CHIP-48 */
+    pub fn describe(&self) -> String {
+        let mut out : String = self.program.title.clone();
+        if let Some(release) = &self.program.release {
+            out.push_str(&format!(" ({})", release));
+        }
+        if !self.program.authors.is_empty() {
+            out.push_str(&format!(" by {}", self.program.authors.join(", ")));
+        }
+        let platforms : Vec<&str> = self.rom.platforms.iter().map(|p| platform_name(p)).collect();
+        if !platforms.is_empty() {
+            out.push_str(&format!(", {}", platforms.join(" / ")));
+        }
+        return out;
+    }
+
+    /* What the keys do, like: down 4, up 1. Empty if the database doesn't say */
+    pub fn key_help(&self) -> String {
+        let keys : Vec<String> = self.rom.keys.iter()
+            .map(|(what, key)| format!("{} {:X}", what, key))
+            .collect();
+        return keys.join(", ");
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,61 @@
+use super::*;
+
+#[test]
+fn test_database_builtin_games() {
+    /* Every game that ships in roms/ has an entry */
+    let db : Database = builtin();
+    let mut found : usize = 0;
+    for file in fs::read_dir("roms/ac8e_roms/games").unwrap() {
+        let path = file.unwrap().path();
+        let sha1 : String = rom::sha1_hex(&fs::read(&path).unwrap());
+        assert!(db.lookup(&sha1).is_some(), "{} isn't in the database", path.display());
+        found += 1;
+    }
+    assert_eq!(found, 23);
+    assert_eq!(db.programs.len(), 23);
+
+    let pong : Entry = db.lookup("B232EF880BD6060FB45FA6EFFED7EDF0AE95670E").unwrap();
+    assert_eq!(pong.describe(), "Pong (1990) by Paul Vervalin, CHIP-48");
+    assert_eq!(pong.key_help(), "down 4, player2Down D, player2Up C, up 1");
+    assert_eq!(pong.settings().speed, Some(30)); /* CHIP-48's, the rom doesn't have one */
+    assert!(db.lookup(&rom::sha1_hex(b"not a game")).is_none());
+}
+
+#[test]
+fn test_database_settings() {
+    let db : Database = parse(r##"[
+        {"title": "A", "roms": {"aa": {"platforms": ["originalChip8"], "tickrate": 15,
+                                       "colors": {"pixels": ["#202020", "#ffb000"]}}}},
+        {"title": "B", "roms": {"bb": {"platforms": ["xochip", "modernChip8"],
+                                       "quirkyPlatforms": {"modernChip8": {"wrap": true, "shift": true}}}}},
+        {"title": "C", "description": "ignored", "roms": {"cc": {"platforms": ["superchip"]}}}
+    ]"##).unwrap();
+
+    let a : config::Settings = db.lookup("aa").unwrap().settings();
+    assert_eq!(a.speed, Some(15));
+    assert_eq!(a.quirks.unwrap().enabled(), vec!["display_wait"]);
+    assert_eq!(a.palette.unwrap().on, [0xff, 0xb0, 0x00]);
+    assert_eq!(a.palette.unwrap().off, [0x20, 0x20, 0x20]);
+    assert_eq!(a.font, Some(font::Style::Vip));
+    assert_eq!(a.stack_depth, Some(stack::VIP_DEPTH));
+    assert_eq!(a.memory, Some(bus::CHIP8_SIZE));
+
+    /* Picks the first platform we can run, with the rom's quirks on top */
+    let b : Entry = db.lookup("bb").unwrap();
+    assert_eq!(b.platform(), Some("modernChip8"));
+    assert_eq!(b.settings().quirks.unwrap().enabled(), vec!["wrap_sprites"]);
+    assert_eq!(b.settings().speed, Some(12)); /* modernChip8's own */
+    assert_eq!(b.settings().font, Some(font::Style::Chip48));
+    assert_eq!(b.settings().stack_depth, Some(stack::SCHIP_DEPTH));
+
+    let c : Entry = db.lookup("cc").unwrap();
+    assert_eq!(c.platform(), None);
+    assert_eq!(c.settings().quirks, None);
+    assert_eq!(c.settings().font, None);
+    assert_eq!(c.settings().stack_depth, None);
+    assert_eq!(c.settings().memory, None);
+    assert_eq!(c.settings().speed, None);
+    assert_eq!(c.describe(), "C, SCHIP 1.1");
+
+    assert!(parse("{}").is_err());
+}
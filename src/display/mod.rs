@@ -5,6 +5,7 @@
  * 
  * It will stay despite its uselessness though.
  */
+#[derive(Clone)]
 pub struct Display {
     pub screen: [[bool; 64]; 32],
 }
@@ -31,5 +32,46 @@ pub fn make_display()-> Display {
     return d;
 }
 
+/* Colours the display gets drawn with, as rgb */
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub on : [u8; 3], /* Lit pixels */
+    pub off : [u8; 3], /* Background */
+}
+
+/* White on black, what we have always drawn with */
+pub fn make_palette() -> Palette {
+    let p = Palette { on: [0xff, 0xff, 0xff], off: [0x00, 0x00, 0x00] };
+    return p;
+}
+
+/* Read a palette out of TEXT, the on and off colours as hex, like ffb000,202020 */
+pub fn parse_palette(text : &str) -> Option<Palette> {
+    let (on, off) : (&str, &str) = text.split_once(',')?;
+    let colour = |hex : &str| -> Option<[u8; 3]> {
+        let hex : &str = hex.trim().trim_start_matches('#');
+        if hex.len() != 6 {
+            return None;
+        }
+        let rgb : u32 = u32::from_str_radix(hex, 16).ok()?;
+        return Some([(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8]);
+    };
+    return Some(Palette { on: colour(on)?, off: colour(off)? });
+}
+
+impl Palette {
+    /* Mix between the off and on colour. LEVEL 0 => off, 0xff => on */
+    pub fn mix(&self, level : u8) -> [u8; 3] {
+        let mut out : [u8; 3] = [0x00; 3];
+        for (i, channel) in out.iter_mut().enumerate() {
+            let off : i32 = self.off[i] as i32;
+            let on : i32 = self.on[i] as i32;
+            *channel = (off + ((on - off) * level as i32) / 0xff) as u8;
+        }
+        return out;
+    }
+}
+
 #[cfg(test)]
 mod test;
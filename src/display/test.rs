@@ -14,3 +14,24 @@ fn test_display() {
         }
     }
 }
+
+#[test]
+fn test_palette_mix() {
+    let p : Palette = Palette { on: [0xff, 0x80, 0x00], off: [0x00, 0x00, 0x40] };
+
+    assert_eq!(p.mix(0x00), p.off);
+    assert_eq!(p.mix(0xff), p.on);
+    assert_eq!(p.mix(0x80), [0x80, 0x40, 0x20]);
+    assert_eq!(make_palette().mix(0xff), [0xff, 0xff, 0xff]);
+}
+
+#[test]
+fn test_parse_palette() {
+    let p : Palette = parse_palette("ffb000, #202020").unwrap();
+    assert_eq!(p.on, [0xff, 0xb0, 0x00]);
+    assert_eq!(p.off, [0x20, 0x20, 0x20]);
+
+    assert_eq!(parse_palette("ffffff"), None);
+    assert_eq!(parse_palette("fff,000"), None);
+    assert_eq!(parse_palette("gggggg,000000"), None);
+}
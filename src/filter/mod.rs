@@ -0,0 +1,101 @@
+use super::*;
+
+/* Phosphor persistence filter. CHIP-8 games XOR sprites on and off to
+ * move them, so anything that moves flickers. The filter sits between
+ * Display::screen and World::draw and turns the on/off pixels into an
+ * intensity per pixel. Emulation never sees it, it only changes what 
+ * gets drawn. Everything is plain arrays so it works without a window. */
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum FilterMode {
+    Off, /* Draw Display::screen as is */
+    Blend(usize), /* Average each pixel over the last N frames */
+    Decay(u8), /* Lit pixels fade out over N frames once turned off */
+}
+
+/* Brightest a pixel can get */
+pub const MAX_INTENSITY: u8 = 0xff;
+
+pub struct Filter {
+    pub mode : FilterMode, /* Which filter to run */
+    history : Vec<[[bool; 64]; 32]>, /* Last N frames for Blend, oldest first */
+    intensity : [[u8; 64]; 32], /* Filtered output, 0 => off, 0xff => fully lit */
+}
+
+/* Make a filter that runs in MODE */
+pub fn make_filter(mode : FilterMode) -> Filter {
+    let f = Filter {
+        mode: mode,
+        history: vec![],
+        intensity: [[0x00; 64]; 32],
+    };
+    return f;
+}
+
+impl Filter {
+    /* Switch to MODE, and forget everything from the old mode */
+    pub fn set_mode(&mut self, mode : FilterMode) {
+        self.mode = mode;
+        self.history.clear();
+        self.intensity = [[0x00; 64]; 32];
+    }
+
+    /* Cycle Off -> Blend -> Decay -> Off. Used by the hotkey */
+    pub fn next_mode(&mut self) {
+        let next : FilterMode = match self.mode {
+            FilterMode::Off => FilterMode::Blend(3),
+            FilterMode::Blend(_) => FilterMode::Decay(8),
+            FilterMode::Decay(_) => FilterMode::Off,
+        };
+        self.set_mode(next);
+    }
+
+    /* Feed the next frame of DISPLAY through the filter. Call once per frame */
+    pub fn update(&mut self, display : &display::Display) {
+        match self.mode {
+            FilterMode::Off => {},
+            FilterMode::Blend(frames) => {
+                let frames : usize = if frames == 0 { 1 } else { frames };
+                self.history.push(display.screen);
+                while self.history.len() > frames {
+                    self.history.remove(0);
+                }
+
+                for y in 0..display.screen.len() {
+                    for x in 0..display.screen[y].len() {
+                        let lit = self.history.iter().filter(|frame| frame[y][x]).count();
+                        self.intensity[y][x] = 
+                            ((lit * MAX_INTENSITY as usize) / self.history.len()) as u8;
+                    }
+                }
+            },
+            FilterMode::Decay(frames) => {
+                let frames : u8 = if frames == 0 { 1 } else { frames };
+                /* How much a pixel fades each frame, round up so it always hits 0 */
+                let step : u8 = (MAX_INTENSITY as u16).div_ceil(frames as u16) as u8;
+
+                for y in 0..display.screen.len() {
+                    for x in 0..display.screen[y].len() {
+                        self.intensity[y][x] = if display.screen[y][x] {
+                            MAX_INTENSITY
+                        } else {
+                            self.intensity[y][x].saturating_sub(step)
+                        };
+                    }
+                }
+            },
+        }
+    }
+
+    /* Get the intensity of the pixel @ (X, Y). With the filter off, this is
+     * just the pixel in DISPLAY */
+    pub fn pixel(&self, display : &display::Display, x : usize, y : usize) -> u8 {
+        if self.mode == FilterMode::Off {
+            return if display.screen[y][x] { MAX_INTENSITY } else { 0x00 };
+        }
+        return self.intensity[y][x];
+    }
+}
+
+#[cfg(test)]
+mod test;
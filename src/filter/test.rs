@@ -0,0 +1,67 @@
+use super::*;
+
+#[test]
+fn test_filter_off() {
+    let f : Filter = make_filter(FilterMode::Off);
+    let mut d : display::Display = display::make_display();
+    d.screen[3][4] = true;
+
+    assert_eq!(f.pixel(&d, 4, 3), MAX_INTENSITY);
+    assert_eq!(f.pixel(&d, 5, 3), 0x00);
+}
+
+#[test]
+fn test_filter_blend() {
+    let mut f : Filter = make_filter(FilterMode::Blend(2));
+    let mut d : display::Display = display::make_display();
+
+    /* Pixel flickering on and off every frame should sit at half */
+    d.screen[0][0] = true;
+    f.update(&d);
+    assert_eq!(f.pixel(&d, 0, 0), MAX_INTENSITY);
+    d.screen[0][0] = false;
+    f.update(&d);
+    assert_eq!(f.pixel(&d, 0, 0), MAX_INTENSITY / 2);
+    d.screen[0][0] = true;
+    f.update(&d);
+    assert_eq!(f.pixel(&d, 0, 0), MAX_INTENSITY / 2);
+
+    /* Only the last 2 frames count */
+    d.screen[0][0] = false;
+    f.update(&d);
+    f.update(&d);
+    assert_eq!(f.pixel(&d, 0, 0), 0x00);
+}
+
+#[test]
+fn test_filter_decay() {
+    let mut f : Filter = make_filter(FilterMode::Decay(4));
+    let mut d : display::Display = display::make_display();
+
+    d.screen[31][63] = true;
+    f.update(&d);
+    assert_eq!(f.pixel(&d, 63, 31), MAX_INTENSITY);
+
+    /* Fades out over 4 frames after it is turned off */
+    d.screen[31][63] = false;
+    let mut last : u8 = MAX_INTENSITY;
+    for _ in 0..3 {
+        f.update(&d);
+        assert!(f.pixel(&d, 63, 31) < last);
+        assert!(f.pixel(&d, 63, 31) > 0x00);
+        last = f.pixel(&d, 63, 31);
+    }
+    f.update(&d);
+    assert_eq!(f.pixel(&d, 63, 31), 0x00);
+}
+
+#[test]
+fn test_filter_next_mode() {
+    let mut f : Filter = make_filter(FilterMode::Off);
+    f.next_mode();
+    assert_eq!(f.mode, FilterMode::Blend(3));
+    f.next_mode();
+    assert_eq!(f.mode, FilterMode::Decay(8));
+    f.next_mode();
+    assert_eq!(f.mode, FilterMode::Off);
+}
@@ -0,0 +1,174 @@
+use super::*;
+
+/* Fonts for FX29 (and FX30). Interpreters didn't agree on what the hex
+ * digits look like or where they live, and a few roms peek at the font or
+ * depend on its exact shapes. The small 4x5 font goes at the font's
+ * address, the SCHIP 8x10 digits for FX30 right after it:
+ *
+ *   address       16 small glyphs, 5 bytes each
+ *   address + 80  10 big digits, 10 bytes each
+ */
+
+/* Bytes of the small font, and of small and big together */
+pub const SMALL_SIZE: usize = 80;
+pub const FONT_SIZE: usize = SMALL_SIZE + 100;
+
+/* Highest address the font can go at, it has to stay out of the program's way */
+pub const MAX_ADDRESS: usize = memory::_PROG_START - FONT_SIZE;
+
+/* Names of the styles, for the command line and files */
+pub const STYLE_NAMES: [&str; 5] = ["chip48", "vip", "dream6800", "eti660", "schip"];
+
+/* Which interpreter's small font to use */
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Style {
+    Chip48, /* CHIP-48 and most modern interpreters, what we've always had */
+    Vip, /* COSMAC VIP */
+    Dream6800, /* DREAM 6800 */
+    Eti660, /* ETI-660 */
+    Schip, /* SUPER-CHIP */
+}
+
+/* A font style and where it gets loaded */
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+pub struct Font {
+    pub style : Style,
+    pub address : usize, /* Where the small font starts */
+}
+
+pub const VIP: [u8; SMALL_SIZE] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+    0x60, 0x20, 0x20, 0x20, 0x70, // 1
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+    0xA0, 0xA0, 0xF0, 0x20, 0x20, // 4
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+    0xF0, 0x10, 0x10, 0x10, 0x10, // 7
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+    0xF0, 0x50, 0x70, 0x50, 0xF0, // B
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+    0xF0, 0x50, 0x50, 0x50, 0xF0, // D
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+    0xF0, 0x80, 0xF0, 0x80, 0x80  // F
+];
+
+pub const DREAM6800: [u8; SMALL_SIZE] = [
+    0xE0, 0xA0, 0xA0, 0xA0, 0xE0, // 0
+    0x40, 0x40, 0x40, 0x40, 0x40, // 1
+    0xE0, 0x20, 0xE0, 0x80, 0xE0, // 2
+    0xE0, 0x20, 0xE0, 0x20, 0xE0, // 3
+    0x80, 0xA0, 0xA0, 0xE0, 0x20, // 4
+    0xE0, 0x80, 0xE0, 0x20, 0xE0, // 5
+    0xE0, 0x80, 0xE0, 0xA0, 0xE0, // 6
+    0xE0, 0x20, 0x20, 0x20, 0x20, // 7
+    0xE0, 0xA0, 0xE0, 0xA0, 0xE0, // 8
+    0xE0, 0xA0, 0xE0, 0x20, 0xE0, // 9
+    0xE0, 0xA0, 0xE0, 0xA0, 0xA0, // A
+    0xC0, 0xA0, 0xE0, 0xA0, 0xC0, // B
+    0xE0, 0x80, 0x80, 0x80, 0xE0, // C
+    0xC0, 0xA0, 0xA0, 0xA0, 0xC0, // D
+    0xE0, 0x80, 0xE0, 0x80, 0xE0, // E
+    0xE0, 0x80, 0xC0, 0x80, 0x80  // F
+];
+
+pub const ETI660: [u8; SMALL_SIZE] = [
+    0xE0, 0xA0, 0xA0, 0xA0, 0xE0, // 0
+    0x20, 0x20, 0x20, 0x20, 0x20, // 1
+    0xE0, 0x20, 0xE0, 0x80, 0xE0, // 2
+    0xE0, 0x20, 0xE0, 0x20, 0xE0, // 3
+    0xA0, 0xA0, 0xE0, 0x20, 0x20, // 4
+    0xE0, 0x80, 0xE0, 0x20, 0xE0, // 5
+    0xE0, 0x80, 0xE0, 0xA0, 0xE0, // 6
+    0xE0, 0x20, 0x20, 0x20, 0x20, // 7
+    0xE0, 0xA0, 0xE0, 0xA0, 0xE0, // 8
+    0xE0, 0xA0, 0xE0, 0x20, 0xE0, // 9
+    0xE0, 0xA0, 0xE0, 0xA0, 0xA0, // A
+    0x80, 0x80, 0xE0, 0xA0, 0xE0, // B
+    0xE0, 0x80, 0x80, 0x80, 0xE0, // C
+    0x20, 0x20, 0xE0, 0xA0, 0xE0, // D
+    0xE0, 0x80, 0xE0, 0x80, 0xE0, // E
+    0xE0, 0x80, 0xE0, 0x80, 0x80  // F
+];
+
+/* SCHIP 1.1's 8x10 digits, for FX30 */
+pub const BIG_DIGITS: [u8; FONT_SIZE - SMALL_SIZE] = [
+    0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+    0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+    0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+    0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+    0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+    0x3E, 0x7C, 0xC0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+    0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C  // 9
+];
+
+/* The font we've always had, where we've always had it */
+pub fn make_font() -> Font {
+    let f = Font { style: Style::Chip48, address: memory::_FONT_START };
+    return f;
+}
+
+/* Style called NAME, None if there isn't one */
+pub fn parse_style(name : &str) -> Option<Style> {
+    match name {
+        "chip48" => return Some(Style::Chip48),
+        "vip" => return Some(Style::Vip),
+        "dream6800" => return Some(Style::Dream6800),
+        "eti660" => return Some(Style::Eti660),
+        "schip" => return Some(Style::Schip),
+        _ => return None,
+    }
+}
+
+impl Style {
+    /* Name of the style, what parse_style takes */
+    pub fn name(&self) -> &'static str {
+        match self {
+            Style::Chip48 => return "chip48",
+            Style::Vip => return "vip",
+            Style::Dream6800 => return "dream6800",
+            Style::Eti660 => return "eti660",
+            Style::Schip => return "schip",
+        }
+    }
+
+    /* The 16 small glyphs. SCHIP kept CHIP-48's, it only added the big digits */
+    pub fn small(&self) -> &'static [u8; SMALL_SIZE] {
+        match self {
+            Style::Chip48 | Style::Schip => return &memory::FONTS,
+            Style::Vip => return &VIP,
+            Style::Dream6800 => return &DREAM6800,
+            Style::Eti660 => return &ETI660,
+        }
+    }
+}
+
+impl Font {
+    /* Everything that goes into memory at the font's address, small then big */
+    pub fn bytes(&self) -> Vec<u8> {
+        let mut out : Vec<u8> = self.style.small().to_vec();
+        out.extend_from_slice(&BIG_DIGITS);
+        return out;
+    }
+
+    /* Address of the small glyph for DIGIT */
+    pub fn small_address(&self, digit : u8) -> usize {
+        return self.address + 5 * (digit & 0xF) as usize;
+    }
+
+    /* Address of the big glyph for DIGIT. Only 0-9 have one, A-F get 9's
+     * instead of whatever comes after the font */
+    pub fn big_address(&self, digit : u8) -> usize {
+        return self.address + SMALL_SIZE + 10 * (digit & 0xF).min(9) as usize;
+    }
+}
+
+#[cfg(test)]
+mod test;
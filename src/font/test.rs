@@ -0,0 +1,33 @@
+use super::*;
+
+#[test]
+fn test_font_styles() {
+    for name in STYLE_NAMES {
+        assert_eq!(parse_style(name).unwrap().name(), name);
+    }
+    assert_eq!(parse_style("octo"), None);
+
+    /* They really are different, 1 is the easy one to tell apart */
+    assert_eq!(&Style::Chip48.small()[5..10], &[0x20, 0x60, 0x20, 0x20, 0x70]);
+    assert_eq!(&Style::Vip.small()[5..10], &[0x60, 0x20, 0x20, 0x20, 0x70]);
+    assert_eq!(&Style::Dream6800.small()[5..10], &[0x40, 0x40, 0x40, 0x40, 0x40]);
+    assert_eq!(&Style::Eti660.small()[5..10], &[0x20, 0x20, 0x20, 0x20, 0x20]);
+    assert_eq!(Style::Schip.small(), Style::Chip48.small());
+}
+
+#[test]
+fn test_font_layout() {
+    let f = Font { style: Style::Vip, address: 0x000 };
+    let bytes : Vec<u8> = f.bytes();
+    assert_eq!(bytes.len(), FONT_SIZE);
+    assert_eq!(bytes[..SMALL_SIZE], VIP[..]);
+    assert_eq!(bytes[SMALL_SIZE..], BIG_DIGITS[..]);
+
+    assert_eq!(f.small_address(0x0), 0x000);
+    assert_eq!(f.small_address(0xF), 0x04B);
+    assert_eq!(f.small_address(0x1F), 0x04B); /* Only the low nibble counts */
+    assert_eq!(f.big_address(0), 0x050);
+    assert_eq!(f.big_address(9), 0x0AA);
+    assert_eq!(f.big_address(0xF), 0x0AA); /* Past 9 stays inside the font */
+    assert_eq!(make_font().small_address(1), memory::_FONT_START + 5);
+}
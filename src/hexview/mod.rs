@@ -0,0 +1,318 @@
+use super::*;
+use cpu::CPU;
+
+/* Hex view of memory. Gives a formatted dump for logs, and a viewer that
+ * the window frontend draws over the screen while paused, where bytes can
+ * be looked at, jumped to and edited in place. Memory is read through the
+ * bus, so the dump shows what the program would see */
+
+/* Bytes on each row of the viewer, and rows it shows at once */
+pub const COLUMNS: usize = 8;
+pub const ROWS: usize = 22;
+
+/* Bytes on each line of a dump */
+pub const DUMP_COLUMNS: usize = 16;
+
+/* Size of a character cell in the viewer, a 4x5 glyph with a gap */
+pub const CELL_WIDTH: usize = 5;
+pub const CELL_HEIGHT: usize = 6;
+
+/* Where the rows start, under the line showing the cursor */
+pub const TOP: usize = 1 + CELL_HEIGHT + 1;
+
+/* What a byte of memory is being used for, most specific first */
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Region {
+    Pc, /* The instruction about to run */
+    I, /* Where I points */
+    Stack, /* A return address on the stack */
+    Font, /* The font */
+    Program, /* From 0x200 up */
+    Interpreter, /* The rest of the bottom 0x200 */
+}
+
+/* Keys the viewer takes, the frontend maps its own keys onto these */
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Key {
+    Hex(u8), /* A hex digit, for editing or typing an address */
+    Up, Down, Left, Right,
+    PageUp, PageDown,
+    Goto, /* Start typing an address to jump to */
+    Enter, /* Jump to the address typed */
+    Cancel, /* Forget the half typed byte or address */
+}
+
+/* The viewer's state */
+#[derive(Debug)]
+pub struct Viewer {
+    pub open : bool, /* Being shown */
+    pub cursor : usize, /* Address of the selected byte */
+    pub top : usize, /* Address of the first row on screen */
+    pub pending : Option<u8>, /* High nibble typed, waiting for the low one */
+    pub goto : Option<String>, /* Address being typed, None when not typing one */
+    pub marked : Vec<usize>, /* Addresses to pick out, like cheats and search results */
+}
+
+pub fn make_viewer() -> Viewer {
+    let v = Viewer {
+        open: false,
+        cursor: memory::_PROG_START,
+        top: memory::_PROG_START,
+        pending: None,
+        goto: None,
+        marked: vec![],
+    };
+    return v;
+}
+
+/* What the byte @ ADDRESS is being used for */
+pub fn region<B : Bus>(core : &CPU<B>, address : usize) -> Region {
+    let pc : usize = core.pc as usize;
+    let i : usize = core.ireg as usize;
+    if (pc..pc + 2).contains(&address) {
+        return Region::Pc;
+    }
+    if address == i {
+        return Region::I;
+    }
+    if core.stack.memory().is_some_and(|stack| stack.contains(&address)) {
+        return Region::Stack;
+    }
+    if core.stack.entries(&core.mem).iter().any(|ret| (*ret as usize..*ret as usize + 2).contains(&address)) {
+        return Region::Stack;
+    }
+    if (core.font.address..core.font.address + font::FONT_SIZE).contains(&address) {
+        return Region::Font;
+    }
+    if address >= memory::_PROG_START {
+        return Region::Program;
+    }
+    return Region::Interpreter;
+}
+
+/* LEN bytes of memory from START as text, DUMP_COLUMNS to a line:
+ *
+ *   0200  00 e0 a2 2a 60 0c 61 08  d0 1f 70 09 a2 39 d0 1f  |...*`.a...p..9..| pc i
+ *
+ * Lines get tagged with pc, i and stack when those point into them. Anything
+ * past the end of memory is left off */
+pub fn dump<B : Bus>(core : &CPU<B>, start : usize, len : usize) -> String {
+    let end : usize = start.saturating_add(len).min(core.mem.size());
+    let mut out : String = String::new();
+    let mut address : usize = start - start % DUMP_COLUMNS;
+    while address < end {
+        let mut hex : String = String::new();
+        let mut ascii : String = String::new();
+        let mut tags : Vec<&str> = vec![];
+        for a in address..address + DUMP_COLUMNS {
+            if a == address + DUMP_COLUMNS / 2 {
+                hex.push(' ');
+            }
+            let byte : Option<u8> = if a < start || a >= end { None } else { core.mem.peek(a).ok() };
+            match byte {
+                Some(b) => {
+                    hex.push_str(&format!(" {:02x}", b));
+                    ascii.push(if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' });
+                },
+                None => {
+                    hex.push_str("   ");
+                    ascii.push(' ');
+                },
+            }
+            let tag : Option<&str> = match byte.map(|_| region(core, a)) {
+                Some(Region::Pc) => Some("pc"),
+                Some(Region::I) => Some("i"),
+                Some(Region::Stack) => Some("stack"),
+                _ => None,
+            };
+            if let Some(t) = tag.filter(|t| !tags.contains(t)) {
+                tags.push(t);
+            }
+        }
+        out.push_str(&format!("{:04x} {}  |{}|", address, hex, ascii));
+        if !tags.is_empty() {
+            out.push(' ');
+            out.push_str(&tags.join(" "));
+        }
+        out.push('\n');
+        address += DUMP_COLUMNS;
+    }
+    return out;
+}
+
+/* Colours of a cell, text then background */
+fn colours(region : Region) -> ([u8; 3], [u8; 3]) {
+    let dark : [u8; 3] = [0x18, 0x18, 0x18];
+    match region {
+        Region::Pc => return ([0xff, 0xff, 0xff], [0x90, 0x20, 0x20]),
+        Region::I => return ([0xff, 0xff, 0xff], [0x20, 0x70, 0x20]),
+        Region::Stack => return ([0xd0, 0x90, 0xff], dark),
+        Region::Font => return ([0x70, 0xb0, 0xff], dark),
+        Region::Program => return ([0xe0, 0xe0, 0xe0], dark),
+        Region::Interpreter => return ([0x80, 0x80, 0x80], dark),
+    }
+}
+
+/* Fill the cell @ (COL, ROW) with BG and draw hex DIGIT in FG, if it fits in the frame */
+fn draw_digit(frame : &mut [u8], width : usize, col : usize, row_y : usize, digit : Option<u8>, fg : [u8; 3], bg : [u8; 3]) {
+    let glyph : &[u8] = match digit {
+        Some(d) => &memory::FONTS[5 * (d & 0xF) as usize..5 * (d & 0xF) as usize + 5],
+        None => &[],
+    };
+    for dy in 0..CELL_HEIGHT {
+        for dx in 0..CELL_WIDTH {
+            let (x, y) : (usize, usize) = (1 + col * CELL_WIDTH + dx, row_y + dy);
+            let i : usize = 4 * (y * width + x);
+            if x >= width || i + 4 > frame.len() {
+                continue;
+            }
+            let lit : bool = dx < 4 && glyph.get(dy).is_some_and(|bits| bits & (0x80 >> dx) != 0);
+            let rgb : [u8; 3] = if lit { fg } else { bg };
+            frame[i..i + 4].copy_from_slice(&[rgb[0], rgb[1], rgb[2], 0xff]);
+        }
+    }
+}
+
+impl Viewer {
+    /* Show or hide the viewer */
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.pending = None;
+        self.goto = None;
+    }
+
+    /* Move the cursor to ADDRESS, scrolling so it's on screen */
+    pub fn goto_address(&mut self, address : usize, size : usize) {
+        self.cursor = address.min(size - 1);
+        self.pending = None;
+        let row : usize = self.cursor - self.cursor % COLUMNS;
+        if row < self.top {
+            self.top = row;
+        } else if row >= self.top + ROWS * COLUMNS {
+            self.top = row + COLUMNS - ROWS * COLUMNS;
+        }
+    }
+
+    /* Handle KEY. Bytes only get edited when EDITABLE, and the byte typed
+     * goes through the bus like the program writing it. Returns the address
+     * written to, if a byte got written */
+    pub fn key<B : Bus>(&mut self, core : &mut CPU<B>, key : Key, editable : bool) -> Option<usize> {
+        let size : usize = core.mem.size();
+        if let Some(typed) = self.goto.as_mut() {
+            match key {
+                Key::Hex(d) => {
+                    if typed.len() < format!("{:x}", size - 1).len() {
+                        typed.push(char::from_digit(d as u32, 16).unwrap_or('0'));
+                    }
+                },
+                Key::Enter => {
+                    let address : Option<usize> = usize::from_str_radix(typed, 16).ok();
+                    self.goto = None;
+                    if let Some(a) = address {
+                        self.goto_address(a, size);
+                    }
+                },
+                _ => self.goto = None,
+            }
+            return None;
+        }
+
+        let cursor : usize = self.cursor;
+        let step = |by : isize| -> usize { (cursor as isize + by).clamp(0, size as isize - 1) as usize };
+        let page : isize = (ROWS * COLUMNS) as isize;
+        match key {
+            Key::Up => self.goto_address(step(-(COLUMNS as isize)), size),
+            Key::Down => self.goto_address(step(COLUMNS as isize), size),
+            Key::Left => self.goto_address(step(-1), size),
+            Key::Right => self.goto_address(step(1), size),
+            Key::PageUp => self.goto_address(step(-page), size),
+            Key::PageDown => self.goto_address(step(page), size),
+            Key::Goto => self.goto = Some(String::new()),
+            Key::Cancel | Key::Enter => self.pending = None,
+            Key::Hex(d) => {
+                if !editable {
+                    return None;
+                }
+                match self.pending.take() {
+                    None => self.pending = Some(d & 0xF),
+                    Some(high) => {
+                        let address : usize = self.cursor;
+                        if core.mem.write8(address, (high << 4) | (d & 0xF)).is_err() {
+                            return None;
+                        }
+                        self.goto_address(step(1), size);
+                        return Some(address);
+                    },
+                }
+            },
+        }
+        return None;
+    }
+
+    /* Draw the viewer over FRAME, an rgba frame WIDTH pixels across. The top
+     * line is the cursor's address and byte, or the address being typed.
+     * Each row under it is an address then COLUMNS bytes, coloured by region */
+    pub fn draw<B : Bus>(&self, core : &CPU<B>, frame : &mut [u8], width : usize) {
+        for pixel in frame.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&[0x18, 0x18, 0x18, 0xff]);
+        }
+        let nibbles = |n : usize, count : usize| -> Vec<u8> {
+            (0..count).rev().map(|k| ((n >> (4 * k)) & 0xF) as u8).collect()
+        };
+        let text : [u8; 3] = [0xe0, 0xe0, 0xe0];
+        let dark : [u8; 3] = [0x18, 0x18, 0x18];
+        let highlight : [u8; 3] = [0xc0, 0xa0, 0x00];
+        let digits : usize = format!("{:x}", core.mem.size() - 1).len();
+
+        /* Cursor line */
+        let value : Option<u8> = core.mem.peek(self.cursor).ok();
+        for (col, d) in nibbles(self.cursor, digits).iter().enumerate() {
+            draw_digit(frame, width, col, 1, Some(*d), text, dark);
+        }
+        for (col, d) in nibbles(value.unwrap_or(0) as usize, 2).iter().enumerate() {
+            draw_digit(frame, width, digits + 1 + col, 1, value.map(|_| *d), text, dark);
+        }
+        if let Some(typed) = &self.goto {
+            for col in 0..digits {
+                let d : Option<u8> = typed.chars().nth(col).and_then(|c| c.to_digit(16)).map(|d| d as u8);
+                draw_digit(frame, width, digits + 5 + col, 1, d, dark, highlight);
+            }
+        }
+
+        /* Rows of bytes */
+        for row in 0..ROWS {
+            let address : usize = self.top + row * COLUMNS;
+            if address >= core.mem.size() {
+                break;
+            }
+            let y : usize = TOP + row * CELL_HEIGHT;
+            for (col, d) in nibbles(address, digits).iter().enumerate() {
+                draw_digit(frame, width, col, y, Some(*d), [0x80, 0x80, 0x80], dark);
+            }
+            for b in 0..COLUMNS {
+                let a : usize = address + b;
+                let byte : Option<u8> = core.mem.peek(a).ok();
+                let (mut fg, mut bg) : ([u8; 3], [u8; 3]) = colours(region(core, a));
+                if self.marked.contains(&a) {
+                    fg = [0xff, 0x90, 0x30];
+                }
+                if a == self.cursor {
+                    (fg, bg) = (dark, highlight);
+                }
+                let shown : Vec<u8> = match (a == self.cursor, self.pending) {
+                    (true, Some(high)) => vec![high, 0x10], /* 0x10 shows as blank */
+                    _ => nibbles(byte.unwrap_or(0) as usize, 2),
+                };
+                for (k, d) in shown.iter().enumerate() {
+                    let digit : Option<u8> = byte.filter(|_| *d < 0x10).map(|_| *d);
+                    draw_digit(frame, width, digits + 1 + 3 * b + k, y, digit, fg, bg);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
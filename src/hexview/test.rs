@@ -0,0 +1,99 @@
+use super::*;
+
+fn make_core() -> CPU {
+    let mut c : CPU = cpu::make_cpu(memory::make_memory());
+    c.mem.store_game(b"Hi! \x00\xe0\x22\x08".to_vec());
+    return c;
+}
+
+#[test]
+fn test_hexview_region() {
+    let mut c : CPU = make_core();
+    c.pc = 0x202;
+    c.ireg = 0x300;
+    c.stack.push(&mut c.mem, 0x210).unwrap();
+    assert_eq!(region(&c, 0x203), Region::Pc);
+    assert_eq!(region(&c, 0x300), Region::I);
+    assert_eq!(region(&c, 0x211), Region::Stack);
+    assert_eq!(region(&c, memory::_FONT_START + font::FONT_SIZE - 1), Region::Font);
+    assert_eq!(region(&c, memory::_FONT_START + font::FONT_SIZE), Region::Interpreter);
+    assert_eq!(region(&c, 0x204), Region::Program);
+
+    /* The memory backed stack is stack too, and returns where it says */
+    c.stack = stack::make_stack(stack::VIP_DEPTH, stack::Overflow::Memory);
+    c.stack.push(&mut c.mem, 0x220).unwrap();
+    c.mem.write8(stack::MEMORY_ADDRESS + 1, 0x30).unwrap();
+    assert_eq!(region(&c, stack::MEMORY_ADDRESS + 2 * stack::VIP_DEPTH - 1), Region::Stack);
+    assert_eq!(region(&c, 0x231), Region::Stack);
+    assert_eq!(region(&c, 0x221), Region::Program);
+}
+
+#[test]
+fn test_hexview_dump() {
+    let mut c : CPU = make_core();
+    c.ireg = 0x204;
+    let text : String = dump(&c, 0x200, 16);
+    assert_eq!(text, "0200  48 69 21 20 00 e0 22 08  00 00 00 00 00 00 00 00  |Hi! ..\".........| pc i\n");
+
+    /* Partial lines are padded, and nothing past the end of memory */
+    let text : String = dump(&c, 0xffc, 100);
+    assert_eq!(text.lines().count(), 1);
+    assert_eq!(text, format!("0ff0{}00 00 00 00  |{}....|\n", " ".repeat(39), " ".repeat(12)));
+
+    /* Everything from somewhere on */
+    assert_eq!(dump(&c, 0x10, usize::MAX).lines().count(), (0x1000 - 0x10) / DUMP_COLUMNS);
+}
+
+#[test]
+fn test_hexview_keys() {
+    let mut c : CPU = make_core();
+    let mut v : Viewer = make_viewer();
+
+    /* Moving off the bottom scrolls a row */
+    v.goto_address(0x200 + ROWS * COLUMNS - 1, c.mem.size());
+    assert_eq!(v.top, 0x200);
+    assert_eq!(v.key(&mut c, Key::Down, true), None);
+    assert_eq!(v.top, 0x200 + COLUMNS);
+    assert_eq!(v.key(&mut c, Key::PageUp, true), None);
+    assert_eq!(v.cursor, 0x207);
+    assert_eq!(v.top, 0x200);
+
+    /* Goto */
+    v.key(&mut c, Key::Goto, true);
+    for d in [0x2, 0x0, 0x4] {
+        v.key(&mut c, Key::Hex(d), true);
+    }
+    v.key(&mut c, Key::Enter, true);
+    assert_eq!(v.cursor, 0x204);
+    assert_eq!(v.goto, None);
+
+    /* Editing takes two digits, then moves along */
+    assert_eq!(v.key(&mut c, Key::Hex(0xa), false), None);
+    assert_eq!(v.pending, None);
+    assert_eq!(v.key(&mut c, Key::Hex(0xa), true), None);
+    assert_eq!(v.key(&mut c, Key::Hex(0x5), true), Some(0x204));
+    assert_eq!(c.mem.read8(0x204), Ok(0xa5));
+    assert_eq!(v.cursor, 0x205);
+
+    /* The last byte is as far as it goes */
+    v.goto_address(0x5000, c.mem.size());
+    assert_eq!(v.cursor, 0xfff);
+    v.key(&mut c, Key::Right, true);
+    assert_eq!(v.cursor, 0xfff);
+}
+
+#[test]
+fn test_hexview_draw() {
+    let c : CPU = make_core();
+    let mut v : Viewer = make_viewer();
+    v.open = true;
+    let width : usize = 220;
+    let mut frame : Vec<u8> = vec![0xff; 4 * width * 140];
+    v.draw(&c, &mut frame, width);
+
+    /* The cursor's byte is highlighted, it's the first byte of the first row */
+    let col : usize = 4;
+    let i : usize = 4 * ((TOP + 5) * width + 1 + col * CELL_WIDTH);
+    assert_eq!(&frame[i..i + 4], &[0xc0, 0xa0, 0x00, 0xff]);
+    assert!(frame.chunks_exact(4).all(|p| p[3] == 0xff));
+}
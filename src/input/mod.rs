@@ -0,0 +1,240 @@
+use super::*;
+
+/* Input layer. Frontends turn whatever their devices report (winit keys,
+ * terminal characters, gamepads) into InputEvents, and this keeps track of
+ * what is held down and what that means for the 16 key hex keypad. Nothing
+ * in here talks to a device, so synthetic events work just as well */
+
+/* Gamepad buttons. Stick directions count as buttons too, so they can be
+ * bound to keys the same way */
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Button {
+    DPadUp, DPadDown, DPadLeft, DPadRight,
+    South, East, North, West,
+    LeftTrigger, RightTrigger, Select, Start,
+    LeftStickUp, LeftStickDown, LeftStickLeft, LeftStickRight,
+    RightStickUp, RightStickDown, RightStickLeft, RightStickRight,
+}
+
+/* Every button, for looking them up by name */
+pub const BUTTONS: [Button; 20] = [
+    Button::DPadUp, Button::DPadDown, Button::DPadLeft, Button::DPadRight,
+    Button::South, Button::East, Button::North, Button::West,
+    Button::LeftTrigger, Button::RightTrigger, Button::Select, Button::Start,
+    Button::LeftStickUp, Button::LeftStickDown, Button::LeftStickLeft, Button::LeftStickRight,
+    Button::RightStickUp, Button::RightStickDown, Button::RightStickLeft, Button::RightStickRight,
+];
+
+/* Stick axes. Values go from -1.0 to 1.0, up and right are positive */
+#[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Axis {
+    LeftStickX, LeftStickY, RightStickX, RightStickY,
+}
+
+/* How far a stick has to be pushed before it counts as a button press */
+pub const STICK_THRESHOLD: f32 = 0.5;
+
+/* Something happening on one of the host's input devices */
+#[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+#[derive(Debug)]
+#[derive(Clone, PartialEq)]
+pub enum InputEvent {
+    KeyPressed(String), /* Keyboard key, named like the keymap names them */
+    KeyReleased(String),
+    ButtonPressed(Button),
+    ButtonReleased(Button),
+    AxisMoved(Axis, f32),
+}
+
+/* State of the hex keypad, one bit per key */
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+pub struct Keypad {
+    pub state : u16,
+}
+
+/* Keeps track of held keys and buttons, and the keypad they add up to */
+pub struct Input {
+    pub keymap : keymap::Keymap, /* Keys and buttons => chip8 keys */
+    held_keys : Vec<String>, /* Keyboard keys that are down */
+    held_buttons : Vec<Button>, /* Gamepad buttons that are down */
+    pub keypad : Keypad, /* What the chip8 sees */
+}
+
+/* Look up a button by NAME */
+pub fn button(name : &str) -> Option<Button> {
+    for b in BUTTONS {
+        if format!("{:?}", b) == name {
+            return Some(b);
+        }
+    }
+    return None;
+}
+
+pub fn make_input(keymap : keymap::Keymap) -> Input {
+    let i = Input {
+        keymap: keymap,
+        held_keys: vec![],
+        held_buttons: vec![],
+        keypad: Keypad { state: 0 },
+    };
+    return i;
+}
+
+impl Keypad {
+    /* Is chip8 key KEY down */
+    pub fn is_down(&self, key : u8) -> bool {
+        return self.state & (1 << (key & 0xf)) != 0;
+    }
+
+    /* The key the cpu gets this frame. The cpu only takes one key at a time,
+     * so the lowest key that is down, or 0xf0 if nothing is */
+    pub fn input_key(&self) -> u8 {
+        for key in 0..16 {
+            if self.is_down(key) {
+                return key;
+            }
+        }
+        return 0xf0;
+    }
+}
+
+impl Input {
+    /* Handle EVENT, and update the keypad */
+    pub fn handle(&mut self, event : InputEvent) {
+        match event {
+            InputEvent::KeyPressed(name) => {
+                if !self.held_keys.contains(&name) { self.held_keys.push(name); }
+            },
+            InputEvent::KeyReleased(name) => { self.held_keys.retain(|k| *k != name); },
+            InputEvent::ButtonPressed(b) => { self.press_button(b); },
+            InputEvent::ButtonReleased(b) => { self.held_buttons.retain(|h| *h != b); },
+            InputEvent::AxisMoved(axis, value) => {
+                /* Turn the axis into a pair of buttons, one for each direction */
+                let (positive, negative) : (Button, Button) = match axis {
+                    Axis::LeftStickX => (Button::LeftStickRight, Button::LeftStickLeft),
+                    Axis::LeftStickY => (Button::LeftStickUp, Button::LeftStickDown),
+                    Axis::RightStickX => (Button::RightStickRight, Button::RightStickLeft),
+                    Axis::RightStickY => (Button::RightStickUp, Button::RightStickDown),
+                };
+                self.held_buttons.retain(|h| *h != positive && *h != negative);
+                if value >= STICK_THRESHOLD {
+                    self.press_button(positive);
+                } else if value <= -STICK_THRESHOLD {
+                    self.press_button(negative);
+                }
+            },
+        }
+        self.update_keypad();
+    }
+
+    /* Let go of everything, like when the window loses focus */
+    pub fn release_all(&mut self) {
+        self.held_keys.clear();
+        self.held_buttons.clear();
+        self.update_keypad();
+    }
+
+    fn press_button(&mut self, b : Button) {
+        if !self.held_buttons.contains(&b) { self.held_buttons.push(b); }
+    }
+
+    /* Work out the keypad from everything being held */
+    fn update_keypad(&mut self) {
+        let mut state : u16 = 0;
+        for name in self.held_keys.iter() {
+            if let Some(key) = self.keymap.chip8_key(name) { state |= 1 << key; }
+        }
+        for b in self.held_buttons.iter() {
+            if let Some(key) = self.keymap.gamepad_key(*b) { state |= 1 << key; }
+        }
+        self.keypad.state = state;
+    }
+}
+
+/* Connected gamepads, through gilrs. Built without the gamepad feature there
+ * never are any, since gilrs needs libudev on linux */
+pub struct Gamepads {
+    #[cfg(feature = "gamepad")]
+    gilrs : Option<gilrs::Gilrs>,
+}
+
+#[cfg(feature = "gamepad")]
+pub fn make_gamepads() -> Gamepads {
+    let gilrs = match gilrs::Gilrs::new() {
+        Ok(g) => Some(g),
+        Err(err) => {
+            println!("No gamepad support: {}", err);
+            None
+        },
+    };
+    return Gamepads { gilrs: gilrs };
+}
+
+#[cfg(not(feature = "gamepad"))]
+pub fn make_gamepads() -> Gamepads {
+    return Gamepads {};
+}
+
+impl Gamepads {
+    /* Pass everything the gamepads did since last time on to INPUT */
+    #[cfg(feature = "gamepad")]
+    pub fn poll(&mut self, input : &mut Input) {
+        if let Some(g) = self.gilrs.as_mut() {
+            while let Some(ev) = g.next_event() {
+                if let Some(event) = gilrs_event(ev.event) {
+                    input.handle(event);
+                }
+            }
+        }
+    }
+
+    #[cfg(not(feature = "gamepad"))]
+    pub fn poll(&mut self, _input : &mut Input) {
+    }
+}
+
+/* Turn a gilrs event into an InputEvent, if it is one we care about */
+#[cfg(feature = "gamepad")]
+pub fn gilrs_event(event : gilrs::EventType) -> Option<InputEvent> {
+    let to_button = |b : gilrs::Button| -> Option<Button> {
+        let out : Button = match b {
+            gilrs::Button::DPadUp => Button::DPadUp,
+            gilrs::Button::DPadDown => Button::DPadDown,
+            gilrs::Button::DPadLeft => Button::DPadLeft,
+            gilrs::Button::DPadRight => Button::DPadRight,
+            gilrs::Button::South => Button::South,
+            gilrs::Button::East => Button::East,
+            gilrs::Button::North => Button::North,
+            gilrs::Button::West => Button::West,
+            gilrs::Button::LeftTrigger => Button::LeftTrigger,
+            gilrs::Button::RightTrigger => Button::RightTrigger,
+            gilrs::Button::Select => Button::Select,
+            gilrs::Button::Start => Button::Start,
+            _ => return None,
+        };
+        return Some(out);
+    };
+
+    match event {
+        gilrs::EventType::ButtonPressed(b, _) => return Some(InputEvent::ButtonPressed(to_button(b)?)),
+        gilrs::EventType::ButtonReleased(b, _) => return Some(InputEvent::ButtonReleased(to_button(b)?)),
+        gilrs::EventType::AxisChanged(axis, value, _) => {
+            let out : Axis = match axis {
+                gilrs::Axis::LeftStickX => Axis::LeftStickX,
+                gilrs::Axis::LeftStickY => Axis::LeftStickY,
+                gilrs::Axis::RightStickX => Axis::RightStickX,
+                gilrs::Axis::RightStickY => Axis::RightStickY,
+                _ => return None,
+            };
+            return Some(InputEvent::AxisMoved(out, value));
+        },
+        _ => return None,
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,97 @@
+use super::*;
+
+#[test]
+fn test_input_keypad() {
+    let mut k : Keypad = Keypad { state: 0 };
+    assert_eq!(k.input_key(), 0xf0);
+
+    k.state = (1 << 0xc) | (1 << 0x5);
+    assert!(k.is_down(0x5));
+    assert!(k.is_down(0xc));
+    assert!(!k.is_down(0x0));
+    assert_eq!(k.input_key(), 0x5);
+}
+
+#[test]
+fn test_input_button_names() {
+    assert_eq!(button("DPadUp"), Some(Button::DPadUp));
+    assert_eq!(button("RightStickDown"), Some(Button::RightStickDown));
+    assert_eq!(button("Nope"), None);
+}
+
+#[test]
+fn test_input_keys() {
+    let mut i : Input = make_input(keymap::layout("qwerty").unwrap());
+    i.handle(InputEvent::KeyPressed(String::from("W")));
+    assert_eq!(i.keypad.input_key(), 0x5);
+
+    /* Key repeat doesn't count twice */
+    i.handle(InputEvent::KeyPressed(String::from("W")));
+    i.handle(InputEvent::KeyPressed(String::from("X")));
+    assert_eq!(i.keypad.state, (1 << 0x5) | (1 << 0x0));
+    i.handle(InputEvent::KeyReleased(String::from("W")));
+    assert_eq!(i.keypad.state, 1 << 0x0);
+
+    /* Unbound keys don't do anything */
+    i.handle(InputEvent::KeyPressed(String::from("P")));
+    assert_eq!(i.keypad.state, 1 << 0x0);
+
+    i.release_all();
+    assert_eq!(i.keypad.input_key(), 0xf0);
+}
+
+#[test]
+fn test_input_gamepad() {
+    let mut i : Input = make_input(keymap::layout("qwerty").unwrap());
+    i.handle(InputEvent::ButtonPressed(Button::DPadUp));
+    assert!(i.keypad.is_down(0x5));
+
+    /* Keyboard and gamepad on the same chip8 key, let go of one and it stays down */
+    i.handle(InputEvent::KeyPressed(String::from("W")));
+    i.handle(InputEvent::ButtonReleased(Button::DPadUp));
+    assert!(i.keypad.is_down(0x5));
+    i.handle(InputEvent::KeyReleased(String::from("W")));
+    assert!(!i.keypad.is_down(0x5));
+}
+
+#[test]
+fn test_input_sticks() {
+    let mut i : Input = make_input(keymap::layout("qwerty").unwrap());
+
+    i.handle(InputEvent::AxisMoved(Axis::LeftStickY, 0.2));
+    assert_eq!(i.keypad.state, 0);
+    i.handle(InputEvent::AxisMoved(Axis::LeftStickY, 0.9));
+    assert_eq!(i.keypad.state, 1 << 0x5);
+    i.handle(InputEvent::AxisMoved(Axis::LeftStickY, -0.9));
+    assert_eq!(i.keypad.state, 1 << 0x8);
+    i.handle(InputEvent::AxisMoved(Axis::LeftStickX, -0.7));
+    assert_eq!(i.keypad.state, (1 << 0x8) | (1 << 0x7));
+    i.handle(InputEvent::AxisMoved(Axis::LeftStickY, 0.0));
+    i.handle(InputEvent::AxisMoved(Axis::LeftStickX, 0.0));
+    assert_eq!(i.keypad.state, 0);
+
+    /* Right stick isn't bound to anything by default */
+    i.handle(InputEvent::AxisMoved(Axis::RightStickX, 1.0));
+    assert_eq!(i.keypad.state, 0);
+}
+
+#[test]
+fn test_input_rom_profile() {
+    /* PONG: left stick moves player 1 (1/4), right stick moves player 2 (C/D) */
+    let text : &str = r#"
+        [roms.PONG.gamepad]
+        1 = ["LeftStickUp"]
+        4 = ["LeftStickDown"]
+        C = ["RightStickUp"]
+        D = ["RightStickDown"]
+    "#;
+    let mut i : Input = make_input(keymap::parse(text, "PONG").unwrap());
+
+    i.handle(InputEvent::AxisMoved(Axis::LeftStickY, 1.0));
+    i.handle(InputEvent::AxisMoved(Axis::RightStickY, -1.0));
+    assert_eq!(i.keypad.state, (1 << 0x1) | (1 << 0xd));
+
+    /* D-pad still has the default bindings */
+    i.handle(InputEvent::ButtonPressed(Button::DPadLeft));
+    assert!(i.keypad.is_down(0x7));
+}
@@ -0,0 +1,293 @@
+use super::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+use winit::event::VirtualKeyCode;
+
+/* Keyboard bindings for the 16 key hex keypad. Host keys are named the same
+ * as winit's VirtualKeyCode (Key1, Q, Numpad0, Comma...). A keymap file is
+ * toml, starting from one of the built in layouts:
+ *
+ *   layout = "azerty"          # qwerty, azerty, dvorak or numpad
+ *
+ *   [keys]                     # chip8 key => host keys, replaces the layout's
+ *   5 = ["Z", "Up"]
+ *   8 = ["S", "Down"]
+ *
+ *   [gamepad]                  # chip8 key => gamepad buttons, see input::Button
+ *   6 = ["South", "RightTrigger"]
+ *
+ *   [roms.PONG]                # per rom overrides, by rom file name
+ *   layout = "qwerty"
+ *   [roms.PONG.keys]
+ *   1 = ["W"]
+ *   4 = ["Space"]
+ *   [roms.PONG.gamepad]
+ *   1 = ["LeftStickUp"]
+ */
+
+/* Chip8 keys, in the order they sit on the keypad:
+ * 1 2 3 C
+ * 4 5 6 D
+ * 7 8 9 E
+ * A 0 B F */
+pub const KEYPAD: [u8; 16] = [0x1, 0x2, 0x3, 0xC, 0x4, 0x5, 0x6, 0xD,
+                              0x7, 0x8, 0x9, 0xE, 0xA, 0x0, 0xB, 0xF];
+
+/* Built in layouts, host keys in the same order as KEYPAD */
+pub const QWERTY: [&str; 16] = ["Key1", "Key2", "Key3", "Key4", "Q", "W", "E", "R",
+                                "A", "S", "D", "F", "Z", "X", "C", "V"];
+pub const AZERTY: [&str; 16] = ["Key1", "Key2", "Key3", "Key4", "A", "Z", "E", "R",
+                                "Q", "S", "D", "F", "W", "X", "C", "V"];
+pub const DVORAK: [&str; 16] = ["Key1", "Key2", "Key3", "Key4", "Apostrophe", "Comma", "Period", "P",
+                                "A", "O", "E", "U", "Semicolon", "Q", "J", "K"];
+/* Hex digits on the numpad, with the operators standing in for A-F */
+pub const NUMPAD: [&str; 16] = ["Numpad1", "Numpad2", "Numpad3", "NumpadSubtract",
+                                "Numpad4", "Numpad5", "Numpad6", "NumpadAdd",
+                                "Numpad7", "Numpad8", "Numpad9", "NumpadEnter",
+                                "NumpadDivide", "Numpad0", "NumpadMultiply", "NumpadDecimal"];
+
+/* Gamepad buttons for every layout, as (chip8 key, button). The d-pad and
+ * left stick are on 5/7/8/9 (WASD on qwerty), the most common movement keys */
+pub const GAMEPAD: [(u8, input::Button); 10] = [
+    (0x5, input::Button::DPadUp), (0x5, input::Button::LeftStickUp),
+    (0x8, input::Button::DPadDown), (0x8, input::Button::LeftStickDown),
+    (0x7, input::Button::DPadLeft), (0x7, input::Button::LeftStickLeft),
+    (0x9, input::Button::DPadRight), (0x9, input::Button::LeftStickRight),
+    (0x6, input::Button::South), (0x4, input::Button::East),
+];
+
+/* Host keys that can be bound */
+const HOST_KEYS: [VirtualKeyCode; 76] = [
+    VirtualKeyCode::Key0, VirtualKeyCode::Key1, VirtualKeyCode::Key2, VirtualKeyCode::Key3,
+    VirtualKeyCode::Key4, VirtualKeyCode::Key5, VirtualKeyCode::Key6, VirtualKeyCode::Key7,
+    VirtualKeyCode::Key8, VirtualKeyCode::Key9,
+    VirtualKeyCode::A, VirtualKeyCode::B, VirtualKeyCode::C, VirtualKeyCode::D,
+    VirtualKeyCode::E, VirtualKeyCode::F, VirtualKeyCode::G, VirtualKeyCode::H,
+    VirtualKeyCode::I, VirtualKeyCode::J, VirtualKeyCode::K, VirtualKeyCode::L,
+    VirtualKeyCode::M, VirtualKeyCode::N, VirtualKeyCode::O, VirtualKeyCode::P,
+    VirtualKeyCode::Q, VirtualKeyCode::R, VirtualKeyCode::S, VirtualKeyCode::T,
+    VirtualKeyCode::U, VirtualKeyCode::V, VirtualKeyCode::W, VirtualKeyCode::X,
+    VirtualKeyCode::Y, VirtualKeyCode::Z,
+    VirtualKeyCode::Numpad0, VirtualKeyCode::Numpad1, VirtualKeyCode::Numpad2,
+    VirtualKeyCode::Numpad3, VirtualKeyCode::Numpad4, VirtualKeyCode::Numpad5,
+    VirtualKeyCode::Numpad6, VirtualKeyCode::Numpad7, VirtualKeyCode::Numpad8,
+    VirtualKeyCode::Numpad9, VirtualKeyCode::NumpadAdd, VirtualKeyCode::NumpadSubtract,
+    VirtualKeyCode::NumpadMultiply, VirtualKeyCode::NumpadDivide,
+    VirtualKeyCode::NumpadDecimal, VirtualKeyCode::NumpadEnter,
+    VirtualKeyCode::Up, VirtualKeyCode::Down, VirtualKeyCode::Left, VirtualKeyCode::Right,
+    VirtualKeyCode::Space, VirtualKeyCode::Return, VirtualKeyCode::Tab, VirtualKeyCode::Back,
+    VirtualKeyCode::LShift, VirtualKeyCode::RShift, VirtualKeyCode::LControl,
+    VirtualKeyCode::RControl,
+    VirtualKeyCode::Apostrophe, VirtualKeyCode::Comma, VirtualKeyCode::Period,
+    VirtualKeyCode::Semicolon, VirtualKeyCode::Slash, VirtualKeyCode::Backslash,
+    VirtualKeyCode::Minus, VirtualKeyCode::Equals, VirtualKeyCode::LBracket,
+    VirtualKeyCode::RBracket, VirtualKeyCode::Grave, VirtualKeyCode::Insert,
+];
+
+/* Host keys and gamepad buttons bound to each of the 16 chip8 keys */
+#[derive(Debug)]
+#[derive(Clone, PartialEq)]
+pub struct Keymap {
+    bindings : [Vec<String>; 16], /* Key names, indexed by chip8 key */
+    buttons : [Vec<input::Button>; 16], /* Gamepad buttons, indexed by chip8 key */
+}
+
+/* Things that can go wrong loading a keymap */
+#[derive(Debug)]
+pub enum KeymapError {
+    Io(io::Error), /* Couldn't read the file */
+    Parse(toml::de::Error), /* Not valid toml, or not shaped like a keymap */
+    UnknownLayout(String), /* Layout that isn't built in */
+    BadChip8Key(String), /* Not 0-F */
+    UnknownHostKey(String), /* Not in HOST_KEYS */
+    UnknownButton(String), /* Not an input::Button */
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeymapError::Io(err) => write!(f, "couldn't read keymap: {}", err),
+            KeymapError::Parse(err) => write!(f, "bad keymap file: {}", err),
+            KeymapError::UnknownLayout(name) => write!(f, "unknown keyboard layout {:?}", name),
+            KeymapError::BadChip8Key(key) => write!(f, "{:?} is not a chip8 key (0-F)", key),
+            KeymapError::UnknownHostKey(key) => write!(f, "unknown host key {:?}", key),
+            KeymapError::UnknownButton(b) => write!(f, "unknown gamepad button {:?}", b),
+        }
+    }
+}
+
+/* Shape of the keymap file */
+#[derive(Deserialize)]
+struct KeymapFile {
+    layout : Option<String>,
+    #[serde(default)]
+    keys : HashMap<String, Vec<String>>,
+    #[serde(default)]
+    gamepad : HashMap<String, Vec<String>>,
+    #[serde(default)]
+    roms : HashMap<String, RomKeys>,
+}
+
+/* Per rom section of the keymap file */
+#[derive(Deserialize)]
+struct RomKeys {
+    layout : Option<String>,
+    #[serde(default)]
+    keys : HashMap<String, Vec<String>>,
+    #[serde(default)]
+    gamepad : HashMap<String, Vec<String>>,
+}
+
+/* Make a keymap from one of the layouts, host keys in KEYPAD order */
+pub fn make_keymap(layout : &[&str; 16]) -> Keymap {
+    let mut k = Keymap { bindings: Default::default(), buttons: Default::default() };
+    for i in 0..KEYPAD.len() {
+        k.bindings[KEYPAD[i] as usize].push(layout[i].to_string());
+    }
+    for (key, b) in GAMEPAD {
+        k.buttons[key as usize].push(b);
+    }
+    return k;
+}
+
+/* Get a built in layout by NAME */
+pub fn layout(name : &str) -> Result<Keymap, KeymapError> {
+    let keys : &[&str; 16] = match name.to_ascii_lowercase().as_str() {
+        "qwerty" => &QWERTY,
+        "azerty" => &AZERTY,
+        "dvorak" => &DVORAK,
+        "numpad" => &NUMPAD,
+        _ => return Err(KeymapError::UnknownLayout(name.to_string())),
+    };
+    return Ok(make_keymap(keys));
+}
+
+/* Turn "A", "a" or "0xA" into a chip8 key, the keys are hex 0 to f */
+pub fn parse_chip8_key(key : &str) -> Result<u8, KeymapError> {
+    let trimmed : &str = key.trim();
+    let digits : &str = trimmed.strip_prefix("0x").unwrap_or(trimmed);
+    match u8::from_str_radix(digits, 16) {
+        Ok(k) if k < 16 => return Ok(k),
+        _ => return Err(KeymapError::BadChip8Key(key.to_string())),
+    }
+}
+
+/* Look up the winit key called NAME */
+pub fn virtual_key(name : &str) -> Option<VirtualKeyCode> {
+    for key in HOST_KEYS {
+        if format!("{:?}", key) == name {
+            return Some(key);
+        }
+    }
+    return None;
+}
+
+/* Read the keymap out of TEXT, with the overrides for ROM applied on top */
+pub fn parse(text : &str, rom : &str) -> Result<Keymap, KeymapError> {
+    let file : KeymapFile = match toml::from_str(text) {
+        Ok(file) => file,
+        Err(err) => return Err(KeymapError::Parse(err)),
+    };
+
+    let mut keymap : Keymap = match &file.layout {
+        Some(name) => layout(name)?,
+        None => make_keymap(&QWERTY),
+    };
+    keymap.apply(&file.keys)?;
+    keymap.apply_gamepad(&file.gamepad)?;
+
+    if let Some(overrides) = file.roms.get(rom) {
+        if let Some(name) = &overrides.layout {
+            keymap = layout(name)?;
+            keymap.apply(&file.keys)?;
+            keymap.apply_gamepad(&file.gamepad)?;
+        }
+        keymap.apply(&overrides.keys)?;
+        keymap.apply_gamepad(&overrides.gamepad)?;
+    }
+    return Ok(keymap);
+}
+
+/* Load the keymap file @ PATH, for the rom named ROM */
+pub fn load(path : &Path, rom : &str) -> Result<Keymap, KeymapError> {
+    let text : String = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) => return Err(KeymapError::Io(err)),
+    };
+    return parse(&text, rom);
+}
+
+impl Keymap {
+    /* Bind chip8 key KEY to HOSTS, replacing whatever it was bound to. A host
+     * key only ever presses one chip8 key, so HOSTS get taken off of the others */
+    pub fn set(&mut self, key : u8, hosts : Vec<String>) -> Result<(), KeymapError> {
+        for host in hosts.iter() {
+            if virtual_key(host).is_none() {
+                return Err(KeymapError::UnknownHostKey(host.to_string()));
+            }
+        }
+        for bound in self.bindings.iter_mut() {
+            bound.retain(|host| !hosts.contains(host));
+        }
+        self.bindings[key as usize & 0xf] = hosts;
+        return Ok(());
+    }
+
+    /* Bind chip8 key KEY to gamepad BUTTONS, same as set() does for keys */
+    pub fn set_buttons(&mut self, key : u8, buttons : Vec<input::Button>) {
+        for bound in self.buttons.iter_mut() {
+            bound.retain(|b| !buttons.contains(b));
+        }
+        self.buttons[key as usize & 0xf] = buttons;
+    }
+
+    /* Apply a [gamepad] table from the keymap file */
+    fn apply_gamepad(&mut self, buttons : &HashMap<String, Vec<String>>) -> Result<(), KeymapError> {
+        for (key, names) in buttons {
+            let mut bound : Vec<input::Button> = vec![];
+            for name in names {
+                match input::button(name) {
+                    Some(b) => bound.push(b),
+                    None => return Err(KeymapError::UnknownButton(name.to_string())),
+                }
+            }
+            self.set_buttons(parse_chip8_key(key)?, bound);
+        }
+        return Ok(());
+    }
+
+    /* Apply a [keys] table from the keymap file */
+    fn apply(&mut self, keys : &HashMap<String, Vec<String>>) -> Result<(), KeymapError> {
+        for (key, hosts) in keys {
+            self.set(parse_chip8_key(key)?, hosts.clone())?;
+        }
+        return Ok(());
+    }
+
+    /* Chip8 key that host key NAME is bound to */
+    pub fn chip8_key(&self, name : &str) -> Option<u8> {
+        for key in 0..self.bindings.len() {
+            if self.bindings[key].iter().any(|host| host == name) {
+                return Some(key as u8);
+            }
+        }
+        return None;
+    }
+
+    /* Chip8 key that gamepad button B is bound to */
+    pub fn gamepad_key(&self, b : input::Button) -> Option<u8> {
+        for key in 0..self.buttons.len() {
+            if self.buttons[key].contains(&b) {
+                return Some(key as u8);
+            }
+        }
+        return None;
+    }
+}
+
+#[cfg(test)]
+mod test;
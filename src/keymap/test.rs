@@ -0,0 +1,93 @@
+use super::*;
+
+#[test]
+fn test_keymap_layouts() {
+    let qwerty : Keymap = layout("qwerty").unwrap();
+    assert_eq!(qwerty.chip8_key("Key4"), Some(0xc));
+    assert_eq!(qwerty.chip8_key("X"), Some(0x0));
+    assert_eq!(qwerty.bindings[0xf], ["V"]);
+
+    let azerty : Keymap = layout("AZERTY").unwrap();
+    assert_eq!(azerty.chip8_key("A"), Some(0x4));
+    assert_eq!(azerty.chip8_key("W"), Some(0xa));
+
+    let dvorak : Keymap = layout("dvorak").unwrap();
+    assert_eq!(dvorak.chip8_key("Apostrophe"), Some(0x4));
+
+    let numpad : Keymap = layout("numpad").unwrap();
+    assert_eq!(numpad.chip8_key("Numpad7"), Some(0x7));
+    assert_eq!(numpad.chip8_key("NumpadDecimal"), Some(0xf));
+
+    assert!(matches!(layout("colemak"), Err(KeymapError::UnknownLayout(_))));
+}
+
+#[test]
+fn test_keymap_every_key_bound() {
+    for name in ["qwerty", "azerty", "dvorak", "numpad"] {
+        let k : Keymap = layout(name).unwrap();
+        for key in 0..16 {
+            assert_eq!(k.bindings[key].len(), 1);
+            assert!(virtual_key(&k.bindings[key][0]).is_some(), "{} has a bad host key", name);
+        }
+        assert_eq!(k.gamepad_key(input::Button::DPadUp), Some(0x5));
+    }
+}
+
+#[test]
+fn test_keymap_parse_chip8_key() {
+    assert_eq!(parse_chip8_key("0").unwrap(), 0x0);
+    assert_eq!(parse_chip8_key("a").unwrap(), 0xa);
+    assert_eq!(parse_chip8_key("0xF").unwrap(), 0xf);
+    assert!(parse_chip8_key("10").is_err());
+    assert!(parse_chip8_key("G").is_err());
+}
+
+#[test]
+fn test_keymap_parse() {
+    let text : &str = r#"
+        layout = "azerty"
+
+        [keys]
+        5 = ["Z", "Up"]
+        8 = ["S", "Down"]
+
+        [roms.PONG]
+        layout = "qwerty"
+        [roms.PONG.keys]
+        1 = ["W"]
+        4 = ["Space"]
+    "#;
+
+    /* Multiple host keys per chip8 key */
+    let k : Keymap = parse(text, "BRIX").unwrap();
+    assert_eq!(k.chip8_key("A"), Some(0x4));
+    assert_eq!(k.chip8_key("Up"), Some(0x5));
+    assert_eq!(k.chip8_key("Z"), Some(0x5));
+    assert_eq!(k.bindings[0x8], ["S", "Down"]);
+
+    /* PONG switches layout, but still gets the [keys] on top */
+    let pong : Keymap = parse(text, "PONG").unwrap();
+    assert_eq!(pong.chip8_key("W"), Some(0x1));
+    assert_eq!(pong.chip8_key("Space"), Some(0x4));
+    assert_eq!(pong.chip8_key("Up"), Some(0x5));
+    assert_eq!(pong.chip8_key("Q"), None);
+}
+
+#[test]
+fn test_keymap_parse_errors() {
+    assert!(matches!(parse("layout = 5", ""), Err(KeymapError::Parse(_))));
+    assert!(matches!(parse("layout = \"bad\"", ""), Err(KeymapError::UnknownLayout(_))));
+    assert!(matches!(parse("[keys]\nG = [\"A\"]", ""), Err(KeymapError::BadChip8Key(_))));
+    assert!(matches!(parse("[keys]\n1 = [\"Nope\"]", ""), Err(KeymapError::UnknownHostKey(_))));
+    assert!(matches!(parse("[gamepad]\n1 = [\"Nope\"]", ""), Err(KeymapError::UnknownButton(_))));
+}
+
+#[test]
+fn test_keymap_example_file() {
+    let k : Keymap = load(Path::new("keymap.example.toml"), "PONG").unwrap();
+    assert_eq!(k.chip8_key("Up"), Some(0xc));
+    assert_eq!(k.chip8_key("Left"), Some(0x7));
+    assert_eq!(k.chip8_key("S"), Some(0x4));
+    assert_eq!(k.chip8_key("Down"), Some(0xd));
+    assert_eq!(k.bindings[0x8], Vec::<String>::new());
+}
@@ -1,18 +1,43 @@
 /* Standard imports */
 use std::fs::File;
-use std::io::Read;
+use std::io::{self, Write};
 use std::fs;
 use std::env;
 use std::process;
+use std::path::{Path, PathBuf};
+use std::io::BufWriter;
+use std::time::Instant;
+use bus::Bus;
 
 /* Local imports */
 mod memory;
+mod font;
+mod bus;
+mod stack;
 mod display;
 mod cpu;
+mod filter;
+mod terminal;
+mod screenshot;
+mod hexview;
+mod profiler;
+mod cheat;
+mod recorder;
+mod scheduler;
+mod keymap;
+mod input;
+mod rom;
+mod cartridge;
+mod patch;
+mod movie;
+mod tas;
+mod cli;
+mod config;
+mod database;
 
 /* winit */
 use winit::{
-    event::{Event, VirtualKeyCode},
+    event::{ElementState, Event, VirtualKeyCode, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     window::WindowBuilder,
 };
@@ -26,43 +51,491 @@ use winit_input_helper::WinitInputHelper;
 const WIDTH: u32 = 220;
 const HEIGHT: u32 = 140;
 
-/* Check to see if they passed in enough args */
-fn process_args(args : &Vec<String>) {
-    if args.len() != 2 {
-        println!("Not enough arguments");
-        process::exit(-1);
+/* The cpu, on whichever bus the options picked */
+type Core = cpu::CPU<bus::Boxed>;
+
+/* Most memory accesses to print when the program stops */
+const SHOWN_ACCESSES: usize = 16;
+
+/* A recording in progress */
+type Recording = recorder::Recorder<BufWriter<File>>;
+
+/* Parse the command line. Exits on --help, --version, or a bad command line */
+fn process_args(args : &Vec<String>) -> cli::Args {
+    let out : cli::Args = match cli::parse(args) {
+        Ok(out) => out,
+        Err(cli::CliError::Help) => {
+            print!("{}", cli::USAGE);
+            process::exit(0);
+        },
+        Err(cli::CliError::Version) => {
+            println!("{}", cli::CliError::Version);
+            process::exit(0);
+        },
+        Err(err) => {
+            eprintln!("r_chip8: {}", err);
+            eprintln!("try r_chip8 --help");
+            process::exit(cli::EXIT_USAGE);
+        },
+    };
+    return out;
+}
+
+/* Fill in what the command line didn't say from the config file, using the
+ * section for the rom with ROM_SHA1. The default config file doesn't have
+ * to exist, one given with --config does */
+fn apply_config(args : &mut cli::Args, rom_sha1 : &str) {
+    let path : PathBuf = match (&args.config, config::default_path()) {
+        (Some(path), _) => PathBuf::from(path),
+        (None, Some(path)) if path.exists() => path,
+        _ => return,
+    };
+    match config::load(&path) {
+        Ok(c) => c.for_rom(rom_sha1).apply(args),
+        Err(err) => {
+            eprintln!("{}: {}", path.display(), err);
+            process::exit(cli::EXIT_USAGE);
+        },
+    }
+}
+
+/* Fill in the rest from the rom database, and say what the game is.
+ * Returns the game's title if it is in there */
+fn apply_database(args : &mut cli::Args, rom_sha1 : &str) -> Option<String> {
+    let db : database::Database = database::builtin();
+    let entry : database::Entry = db.lookup(rom_sha1)?;
+    println!("{}", entry.describe());
+    if !entry.key_help().is_empty() {
+        println!("Keys: {}", entry.key_help());
+    }
+    if entry.platform().is_none() {
+        println!("Only CHIP-8 roms are supported, this one might not run right");
+    }
+    entry.settings().apply(args);
+    return Some(entry.program.title.clone());
+}
+
+/* GAME has to fit in MEMORY_SIZE bytes of memory, after 0x200 */
+fn check_fits(game : &[u8], memory_size : usize) -> Result<(), String> {
+    let space : usize = rom::program_space(memory_size);
+    if game.len() > space {
+        return Err(rom::RomError::TooBig(game.len(), space).to_string());
+    }
+    return Ok(());
+}
+
+/* Apply the patch files @ PATCHES to GAME in order. The patched rom still has
+ * to fit in MEMORY_SIZE bytes of memory */
+fn apply_patches(game : Vec<u8>, patches : &[String], memory_size : usize) -> Result<Vec<u8>, String> {
+    let mut out : Vec<u8> = game;
+    for path in patches {
+        let data : Vec<u8> = fs::read(path).map_err(|err| format!("{}: {}", path, err))?;
+        out = patch::apply(&out, &data).map_err(|err| format!("{}: {}", path, err))?;
+    }
+    if out.is_empty() {
+        return Err(rom::RomError::Empty.to_string());
+    }
+    check_fits(&out, memory_size)?;
+    return Ok(out);
+}
+
+/* Load the rom @ FILE_PATH again with PATCHES and start it over, after it was rebuilt */
+fn reload(core : &mut Core, world : &mut cpu::World, file_path : &str, patches : &[String]) {
+    let memory_size : usize = core.mem.size();
+    let game : Result<Vec<u8>, String> = rom::load(Path::new(file_path), rom::program_space(memory_size))
+        .map_err(|err| err.to_string())
+        .and_then(|r| apply_patches(r.data, patches, memory_size));
+    match game {
+        Ok(game) => {
+            core.hard_reset(game);
+            world.filter.set_mode(world.filter.mode);
+            println!("Reloaded {}", file_path);
+        },
+        Err(err) => error!("Failed to reload {file_path}: {err}"),
+    }
+}
+
+/* The hex viewer's key for what was just pressed, if it is one it takes */
+fn viewer_key(input : &WinitInputHelper) -> Option<hexview::Key> {
+    const HEX_KEYS: [VirtualKeyCode; 16] = [
+        VirtualKeyCode::Key0, VirtualKeyCode::Key1, VirtualKeyCode::Key2, VirtualKeyCode::Key3,
+        VirtualKeyCode::Key4, VirtualKeyCode::Key5, VirtualKeyCode::Key6, VirtualKeyCode::Key7,
+        VirtualKeyCode::Key8, VirtualKeyCode::Key9, VirtualKeyCode::A, VirtualKeyCode::B,
+        VirtualKeyCode::C, VirtualKeyCode::D, VirtualKeyCode::E, VirtualKeyCode::F,
+    ];
+    if let Some(d) = HEX_KEYS.iter().position(|k| input.key_pressed(*k)) {
+        return Some(hexview::Key::Hex(d as u8));
+    }
+    let keys : [(VirtualKeyCode, hexview::Key); 9] = [
+        (VirtualKeyCode::Up, hexview::Key::Up), (VirtualKeyCode::Down, hexview::Key::Down),
+        (VirtualKeyCode::Left, hexview::Key::Left), (VirtualKeyCode::Right, hexview::Key::Right),
+        (VirtualKeyCode::PageUp, hexview::Key::PageUp), (VirtualKeyCode::PageDown, hexview::Key::PageDown),
+        (VirtualKeyCode::G, hexview::Key::Goto), (VirtualKeyCode::Return, hexview::Key::Enter),
+        (VirtualKeyCode::Back, hexview::Key::Cancel),
+    ];
+    return keys.iter().find(|(k, _)| input.key_pressed(*k)).map(|(_, key)| *key);
+}
+
+/* Where the program stopped and the memory around it, for the log */
+fn report_fault(core : &mut Core) {
+    if let Some(fault) = &core.fault {
+        println!("Stopped: {}", fault);
+
+        /* Only there with --debug. Taken first, the rest reads memory too */
+        let log : Vec<bus::Access> = core.mem.log();
+        if !log.is_empty() {
+            println!("Last memory accesses:");
+            for access in log.iter().skip(log.len().saturating_sub(SHOWN_ACCESSES)) {
+                println!("  {}", access);
+            }
+        }
+        if !core.stack.is_empty() {
+            let calls : Vec<String> = core.stack.entries(&core.mem).iter().map(|ret| format!("{:#05x}", ret)).collect();
+            println!("Stack ({} of {}): {}", core.stack.len(), core.stack.depth, calls.join(" "));
+        }
+        let pc : usize = core.pc as usize;
+        print!("{}", hexview::dump(core, pc.saturating_sub(16), 48));
+    }
+}
+
+/* Ring the terminal bell, the closest thing to a buzzer we have */
+fn beep() {
+    print!("\x07");
+    let _ = io::stdout().flush();
+}
+
+/* Get the keyboard bindings for the rom @ FILE_PATH. KEYMAP is either one of the
+ * built in layouts, or a keymap file */
+fn load_keymap(keymap : &Option<String>, file_path : &str) -> keymap::Keymap {
+    let name : &str = match keymap {
+        Some(name) => name,
+        None => return keymap::make_keymap(&keymap::QWERTY),
+    };
+    if let Ok(layout) = keymap::layout(name) {
+        return layout;
+    }
+
+    let rom : String = match Path::new(file_path).file_stem() {
+        Some(stem) => stem.to_string_lossy().to_string(),
+        None => String::new(),
+    };
+    match keymap::load(Path::new(name), &rom) {
+        Ok(k) => return k,
+        Err(err) => {
+            println!("{}: {}", name, err);
+            process::exit(cli::EXIT_USAGE);
+        },
+    }
+}
+
+/* Load the cheat file @ PATH, if there is anywhere to keep one */
+fn load_cheats(path : &Option<PathBuf>) -> cheat::CheatFile {
+    let path : &PathBuf = match path {
+        Some(path) => path,
+        None => return cheat::CheatFile::new(),
+    };
+    match cheat::load(path) {
+        Ok(f) => return f,
+        Err(err) => {
+            eprintln!("{}: {}", path.display(), err);
+            process::exit(cli::EXIT_USAGE);
+        },
+    }
+}
+
+/* Addresses the hex viewer should pick out: what CHEATS touch, and what's
+ * left of SEARCH once it's down to few enough to look through */
+fn marked_addresses(search : &Option<cheat::Search>, cheats : Option<&Vec<cheat::Cheat>>) -> Vec<usize> {
+    const MAX_CANDIDATES: usize = 256;
+    let mut out : Vec<usize> = vec![];
+    if let Some(s) = search.as_ref().filter(|s| s.candidates.len() <= MAX_CANDIDATES) {
+        out.extend_from_slice(&s.candidates);
+    }
+    for c in cheats.map_or(&[][..], |c| &c[..]) {
+        let size : usize = match &c.kind {
+            cheat::Kind::Freeze(size) => *size,
+            cheat::Kind::Patch(bytes) => bytes.len(),
+        };
+        out.extend(c.address..c.address + size);
+    }
+    return out;
+}
+
+/* The search comparison for what was just pressed in the hex viewer */
+fn search_key(input : &WinitInputHelper) -> Option<cheat::Compare> {
+    if input.key_pressed(VirtualKeyCode::X) {
+        return Some(cheat::Compare::Changed);
+    } else if input.key_pressed(VirtualKeyCode::Equals) {
+        return Some(cheat::Compare::Unchanged);
+    } else if input.key_pressed(VirtualKeyCode::RBracket) {
+        return Some(cheat::Compare::Increased);
+    } else if input.key_pressed(VirtualKeyCode::LBracket) {
+        return Some(cheat::Compare::Decreased);
     }
+    return None;
 }
 
-/* https://www.reddit.com/r/rust/comments/dekpl5/how_to_read_binary_data_from_a_file_into_a_vecu8/ */
-/* Read binary file back as a Vector of bytes */
-fn get_file_as_byte_vec(filename: &String) -> Vec<u8> {
-    let mut f = File::open(&filename).expect("no file found");
-    let metadata = fs::metadata(&filename).expect("unable to read metadata");
-    let mut buffer = vec![0; metadata.len() as usize];
-    f.read(&mut buffer).expect("buffer overflow");
+/* Load the movie @ PATH, and make sure it was made on the rom with ROM_SHA1 */
+fn load_movie(path : &str, rom_sha1 : &str) -> movie::Movie {
+    let m : movie::Movie = match movie::load(Path::new(path)) {
+        Ok(m) => m,
+        Err(err) => {
+            println!("{}: {}", path, err);
+            process::exit(cli::EXIT_USAGE);
+        },
+    };
+    if let Err(err) = m.check_rom(rom_sha1) {
+        println!("{}: {}", path, err);
+        process::exit(cli::EXIT_USAGE);
+    }
+    return m;
+}
 
-    return buffer;
+/* Save the movie being recorded, if there is one */
+fn save_movie(movie_recording : &Option<movie::Movie>, path : &Option<String>) {
+    if let (Some(m), Some(path)) = (movie_recording, path) {
+        match m.save(Path::new(path)) {
+            Ok(_) => println!("Saved {} frames of input to {}", m.frames.len(), path),
+            Err(err) => error!("Failed to save movie: {err}"),
+        }
+    }
+}
+
+/* Write out the profile, as a report to REPORT and folded stacks to FOLDED, if asked for */
+fn save_profile(core : &Core, report : &Option<String>, folded : &Option<String>) {
+    let Some(p) = &core.profiler else {
+        return;
+    };
+    let outputs : [(&Option<String>, String); 2] = [(report, p.report()), (folded, p.folded())];
+    for (path, text) in outputs {
+        if let Some(path) = path {
+            match fs::write(path, text) {
+                Ok(_) => println!("Wrote the profile of {} cycles to {}", p.cycles, path),
+                Err(err) => println!("Failed to write the profile to {}: {}", path, err),
+            }
+        }
+    }
+}
+
+/* Start recording gameplay into PATH, at the scale of the window */
+fn start_recording(path : &Path, world : &cpu::World, scale : u32) -> Option<Recording> {
+    match recorder::start(path, world.palette, scale) {
+        Ok(rec) => {
+            println!("Recording to {}", path.display());
+            return Some(rec);
+        },
+        Err(err) => {
+            error!("Failed to start recording: {err}");
+            return None;
+        },
+    }
+}
+
+/* Stop RECORDING if there is one, and finish the file off */
+fn stop_recording(recording : &mut Option<Recording>) {
+    if let Some(rec) = recording.take() {
+        let frames : u64 = rec.frames;
+        match rec.finish() {
+            Ok(_) => println!("Recorded {} frames", frames),
+            Err(err) => error!("Failed to finish recording: {err}"),
+        }
+    }
+}
+
+/* Write GAME out as an Octo cartridge @ PATH, with the speed, quirks and
+ * colours it would have been run with */
+fn export_cartridge(path : &str, game : &[u8], args : &cli::Args) {
+    let mut settings : config::Settings = config::make_settings();
+    settings.speed = args.speed;
+    settings.quirks = args.quirks;
+    settings.palette = args.palette;
+    settings.font = args.font;
+    let written = cartridge::encode(game, cartridge::make_options(&settings))
+        .map_err(|err| err.to_string())
+        .and_then(|gif| fs::write(path, gif).map_err(|err| err.to_string()));
+    match written {
+        Ok(_) => println!("Wrote cartridge {}", path),
+        Err(err) => {
+            eprintln!("Failed to write cartridge {}: {}", path, err);
+            process::exit(1);
+        },
+    }
+}
+
+/* Load the rom @ FILE_PATH, or say what's wrong with it and exit. Which
+ * platform it's for isn't known yet, so it only has to fit the most memory
+ * there can be. check_fits has the final say once it is */
+fn load_rom(file_path : &str) -> rom::Rom {
+    match rom::load(Path::new(file_path), rom::program_space(bus::XO_SIZE)) {
+        Ok(r) => {
+            match r.format {
+                rom::Format::Raw => {},
+                rom::Format::Zip => println!("Unzipped rom out of {}", file_path),
+                rom::Format::Cartridge => println!("Read the program out of cartridge {}", file_path),
+            }
+            return r;
+        },
+        Err(err) => {
+            eprintln!("{}: {}", file_path, err);
+            process::exit(cli::EXIT_USAGE);
+        },
+    }
 }
 
 fn main() -> Result<(), Error>{
     /* Process args */
     let args : Vec<String> = env::args().collect();
-    process_args(&args);
+    let mut args : cli::Args = process_args(&args);
 
     /* Get the file path */
-    let file_path : &String = &args[1];
-    dbg!(file_path);
+    let file_path : String = args.file_path.clone();
+    if args.debug {
+        dbg!(&file_path);
+    }
+
+    /* Read the game file, whatever it's packaged in */
+    let r : rom::Rom = load_rom(&file_path);
+
+    /* The config, database and cheats know the rom by the hash of the rom
+     * as it came. Options a cartridge came with go under the config, but
+     * over the database */
+    let rom_sha1 : String = rom::sha1_hex(&r.data);
+
+    /* Patches go on before anything runs. Movies are made on the patched
+     * rom, so they know it by the hash of that */
+    let game : Vec<u8> = match apply_patches(r.data, &args.patches, bus::XO_SIZE) {
+        Ok(game) => game,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(cli::EXIT_USAGE);
+        },
+    };
+    for path in &args.patches {
+        println!("Patched with {}", path);
+    }
+    let game_sha1 : String = rom::sha1_hex(&game);
+
+    apply_config(&mut args, &rom_sha1);
+    r.settings.apply(&mut args);
+    let title : String = match apply_database(&mut args, &rom_sha1) {
+        Some(title) => title,
+        None => Path::new(&file_path).file_name().map_or(file_path.clone(), |f| f.to_string_lossy().to_string()),
+    };
+    let audio : bool = args.audio.unwrap_or(true);
+
+    if let Some(path) = &args.export_cartridge {
+        export_cartridge(path, &game, &args);
+        return Ok(());
+    }
 
-    /* Read the game file */
-    let game : Vec<u8> = get_file_as_byte_vec(file_path);
+    /* A movie being played back runs the way it was recorded */
+    let playback : Option<movie::Movie> = args.movie_play.as_ref().map(|path| load_movie(path, &game_sha1));
+
+    /* Now the platform is known, the rom has to fit in its memory */
+    let memory_size : usize = match &playback {
+        Some(m) => m.memory,
+        None => args.memory.unwrap_or(bus::CHIP8_SIZE),
+    };
+    if let Err(err) = check_fits(&game, memory_size) {
+        eprintln!("{}: {}", file_path, err);
+        process::exit(cli::EXIT_USAGE);
+    }
 
     /* Create memroy and store game file into memory */
-    let mut mem: memory::Mem = memory::make_memory();
+    let mut mem : bus::Boxed = bus::make_bus(memory_size, args.protect, args.debug);
     mem.store_game(game);
 
     /* Create new cpu instance, and prepare to run */
-    let mut _c : cpu::CPU = cpu::make_cpu(mem);
+    let mut _c : Core = cpu::make_cpu(mem);
+    _c.quirks = args.quirks.unwrap_or(cpu::make_quirks());
+    _c.set_font(font::Font {
+        style: args.font.unwrap_or(font::Style::Chip48),
+        address: args.font_address.unwrap_or(memory::_FONT_START),
+    });
+    _c.stack = stack::make_stack(args.stack_depth.unwrap_or(stack::SCHIP_DEPTH),
+                                 args.stack.unwrap_or(stack::Overflow::Error));
+    _c.debug = args.debug;
+    if args.profile.is_some() || args.profile_folded.is_some() {
+        _c.profiler = Some(profiler::make_profiler(_c.pc));
+    }
+    if let Some(seed) = args.seed {
+        _c.set_seed(seed);
+    }
+
+    /* A movie being played back sets the cpu up the way it was recorded */
+    let mut ipf : u16 = args.speed.unwrap_or(scheduler::DEFAULT_IPF);
+    if let Some(m) = &playback {
+        m.prepare(&mut _c);
+        ipf = m.ipf;
+    }
+
+    /* Edit the movie with a script and re-run it, saving the edited movie
+     * if asked to. Fails if anything the script expected didn't happen */
+    if let Some(script_path) = &args.tas {
+        let Some(m) = playback else {
+            println!("--tas needs a movie to edit, use --movie-play");
+            process::exit(cli::EXIT_USAGE);
+        };
+        let script : String = fs::read_to_string(script_path).unwrap_or_else(|err| {
+            println!("{}: {}", script_path, err);
+            process::exit(cli::EXIT_USAGE);
+        });
+        let commands : Vec<tas::Command> = tas::parse_script(&script).unwrap_or_else(|err| {
+            println!("{}: {}", script_path, err);
+            process::exit(cli::EXIT_USAGE);
+        });
+
+        let mut timeline : tas::Timeline<bus::Boxed> = tas::make_timeline(m, &_c);
+        let failures : Vec<String> = tas::run_script(&mut timeline, &commands);
+        for failure in failures.iter() {
+            println!("{}", failure);
+        }
+        let edited : Option<movie::Movie> = Some(timeline.movie);
+        save_movie(&edited, &args.movie_record);
+        if !failures.is_empty() {
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    /* Run as fast as possible with no window, and report where it ended up.
+     * Plays the movie if there is one, otherwise nothing gets pressed */
+    if args.headless {
+        let m : movie::Movie = playback.unwrap_or(movie::make_movie(&game_sha1, &_c, ipf));
+        let frames_to_run : u64 = args.frames.unwrap_or(m.frames.len() as u64);
+        let frames : u64 = movie::replay(&m, &mut _c, frames_to_run);
+        println!("Replayed {} frames, pc {:#05x}, screen {}", frames, _c.pc, tas::screen_sha1(&_c.display));
+        if let Some(path) = &args.screenshot {
+            let mut world = cpu::World::new();
+            if let Some(palette) = args.palette {
+                world.palette = palette;
+            }
+            match screenshot::write_png(Path::new(path), &world, &_c, args.scale.unwrap_or(1)) {
+                Ok(_) => println!("Saved screenshot to {}", path),
+                Err(err) => {
+                    println!("Failed to save screenshot to {}: {}", path, err);
+                    process::exit(1);
+                },
+            }
+        }
+        report_fault(&mut _c);
+        save_profile(&_c, &args.profile, &args.profile_folded);
+        return Ok(());
+    }
+
+    /* Keyboard and gamepad bindings */
+    let keys : keymap::Keymap = load_keymap(&args.keymap, &file_path);
+
+    /* Run in the terminal instead of opening up a window */
+    if let Some(glyphs) = args.terminal {
+        if let Err(err) = terminal::run(&mut _c, &keys, glyphs, ipf, audio) {
+            println!("Terminal frontend failed: {err}");
+            process::exit(1);
+        }
+        report_fault(&mut _c);
+        save_profile(&_c, &args.profile, &args.profile_folded);
+        return Ok(());
+    }
 
     /* https://github.com/parasyte/pixels/blob/864a9c3491cb2aa778a8c0ae5742f760bcfac622/examples/minimal-winit/src/main.rs */
     /* Prepare window, and event loop for rendering */
@@ -71,9 +544,11 @@ fn main() -> Result<(), Error>{
     let mut input = WinitInputHelper::new();
     let window = {
         let size = LogicalSize::new(WIDTH as f64, HEIGHT as f64);
+        let scale : f64 = args.scale.unwrap_or(1) as f64;
+        let scaled = LogicalSize::new(WIDTH as f64 * scale, HEIGHT as f64 * scale);
         WindowBuilder::new()
-            .with_title("Hello Pixels")
-            .with_inner_size(size)
+            .with_title(format!("r_chip8 - {}", title))
+            .with_inner_size(scaled)
             .with_min_inner_size(size)
             .build(&event_loop)
             .unwrap()
@@ -83,22 +558,77 @@ fn main() -> Result<(), Error>{
         let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
         Pixels::new(WIDTH, HEIGHT, surface_texture)?
     };
-    let world = cpu::World::new();
+    let mut world = cpu::World::new();
+    if let Some(palette) = args.palette {
+        world.palette = palette;
+    }
+
+    /* Record from the very first frame if asked to */
+    let mut recording : Option<Recording> = None;
+    if let Some(path) = &args.record {
+        recording = start_recording(Path::new(path), &world, window.inner_size().width / WIDTH);
+    }
+
+    /* Keyboard and gamepads => chip8 keypad */
+    let mut keypad_input : input::Input = input::make_input(keys);
+    let mut gamepads : input::Gamepads = input::make_gamepads();
 
-    /* The number of instructions executed, keep at 700 / second */
-    let mut num_instructions_finished : u16 = 0;
-    /* Timer to help with 700 / second instructions exec */
-    let mut sys_timer : cpu::Timer = cpu::Timer::new();
+    /* Runs the cpu in 60hz frames, ~700 instructions / second */
+    let mut scheduler : scheduler::Scheduler = scheduler::make_scheduler(ipf);
+
+    /* Record the keypad every frame into a movie */
+    let mut movie_recording : Option<movie::Movie> = args.movie_record.as_ref()
+        .map(|_| movie::make_movie(&game_sha1, &_c, ipf));
+    let mut movie_frame : u64 = 0;
+    let mut shown_status : String = String::new();
+
+    /* Hex view of memory, shown over the screen while paused */
+    let mut viewer : hexview::Viewer = hexview::make_viewer();
+    let mut viewer_paused : bool = false; /* Opening the viewer paused the game */
+
+    /* Cheats for this rom, and the search for new ones */
+    let cheat_path : Option<PathBuf> = args.cheats.as_ref().map(PathBuf::from).or(cheat::default_path());
+    let mut cheat_file : cheat::CheatFile = load_cheats(&cheat_path);
+    let mut cheats_on : bool = true;
+    let mut search : Option<cheat::Search> = None;
+    if let Some(cheats) = cheat_file.get(&rom_sha1).filter(|c| !c.is_empty()) {
+        println!("{} cheat(s) for this rom, F4 turns them off and on", cheats.len());
+        for c in cheats.iter() {
+            println!("  {}", c);
+        }
+    }
+
+    /* Reload the rom when it gets rebuilt */
+    let mut watcher : Option<rom::Watcher> = None;
+    if args.watch {
+        watcher = Some(rom::make_watcher(PathBuf::from(&file_path)));
+    }
     event_loop.run(move |event, _, control_flow| {
 
-        /* Get new input key and update timers */
-        let mut input_key : u8 =  0xf0;
-        _c.sound_timer.tick();
-        _c.delay_timer.tick();
+        /* Keyboard events go through the keymap onto the keypad */
+        if let Event::WindowEvent { event: WindowEvent::KeyboardInput { input: key, .. }, .. } = &event {
+            if let Some(vk) = key.virtual_keycode {
+                let name : String = format!("{:?}", vk);
+                match key.state {
+                    ElementState::Pressed => keypad_input.handle(input::InputEvent::KeyPressed(name)),
+                    ElementState::Released => keypad_input.handle(input::InputEvent::KeyReleased(name)),
+                }
+            }
+        }
+        /* Don't leave keys stuck down when the window loses focus */
+        if let Event::WindowEvent { event: WindowEvent::Focused(false), .. } = &event {
+            keypad_input.release_all();
+        }
+        gamepads.poll(&mut keypad_input);
 
         // Draw the current frame
         if let Event::RedrawRequested(_) = event {
-            world.draw(&_c, pixels.get_frame_mut());
+            if viewer.open {
+                viewer.marked = marked_addresses(&search, cheat_file.get(&rom_sha1));
+                viewer.draw(&_c, pixels.get_frame_mut(), WIDTH as usize);
+            } else {
+                world.draw(&_c, pixels.get_frame_mut());
+            }
             if let Err(err) = pixels.render() {
                 error!("pixels.render() failed: {err}");
                 *control_flow = ControlFlow::Exit;
@@ -108,58 +638,121 @@ fn main() -> Result<(), Error>{
         // Handle input events
         if input.update(&event) {
             // Close events
-            if input.key_pressed(VirtualKeyCode::Escape) || input.quit() {
+            let out_of_frames : bool = args.frames.is_some_and(|frames| movie_frame >= frames);
+            if input.key_pressed(VirtualKeyCode::F9) || (viewer.open && input.key_pressed(VirtualKeyCode::Escape)) {
+                /* Hex viewer, the game stays paused while it's open. Escape only closes it */
+                viewer.toggle();
+                if viewer.open {
+                    viewer_paused = !scheduler.paused;
+                    if viewer_paused {
+                        scheduler.toggle_pause();
+                    }
+                    let pc : usize = _c.pc as usize;
+                    viewer.goto_address(pc, _c.mem.size());
+                    if args.debug {
+                        print!("{}", hexview::dump(&_c, pc, 16));
+                    }
+                } else if viewer_paused && scheduler.paused {
+                    scheduler.toggle_pause();
+                }
+            } else if input.key_pressed(VirtualKeyCode::Escape) || input.quit() || out_of_frames {
+                stop_recording(&mut recording);
+                save_movie(&movie_recording, &args.movie_record);
+                save_profile(&_c, &args.profile, &args.profile_folded);
                 *control_flow = ControlFlow::Exit;
                 return;
-            } else if input.key_pressed(VirtualKeyCode::Key1) || input.key_held(VirtualKeyCode::Key1) {
-                // println!("1 is button is pushed");
-                input_key = 0x01;
-            } else if input.key_pressed(VirtualKeyCode::Key2) || input.key_held(VirtualKeyCode::Key2){
-                // println!("2 is button is pushed");
-                input_key = 0x02;
-            } else if input.key_pressed(VirtualKeyCode::Key3) || input.key_held(VirtualKeyCode::Key3) {
-                // println!("3 is button is pushed");
-                input_key = 0x03;
-            } else if input.key_pressed(VirtualKeyCode::Key4) || input.key_held(VirtualKeyCode::Key4) {
-                // println!("4 is button is pushed");
-                input_key = 0x0C;
-            } else if input.key_pressed(VirtualKeyCode::Q) || input.key_held(VirtualKeyCode::Q) {
-                // println!("Q is button is pushed");
-                input_key = 0x04;
-            } else if input.key_pressed(VirtualKeyCode::W) || input.key_held(VirtualKeyCode::W) {
-                // println!("W is button is pushed");
-                input_key = 0x05;
-            } else if input.key_pressed(VirtualKeyCode::E) || input.key_held(VirtualKeyCode::E) {
-                // println!("E is button is pushed");
-                input_key = 0x06;
-            } else if input.key_pressed(VirtualKeyCode::R) || input.key_held(VirtualKeyCode::R) {
-                // println!("R is button is pushed");
-                input_key = 0x0D;
-            } else if input.key_pressed(VirtualKeyCode::A) || input.key_held(VirtualKeyCode::A) {
-                // println!("A is button is pushed");
-                input_key = 0x07;
-            } else if input.key_pressed(VirtualKeyCode::S) || input.key_held(VirtualKeyCode::S) {
-                // println!("S is button is pushed");
-                input_key = 0x08;
-            } else if input.key_pressed(VirtualKeyCode::D) || input.key_held(VirtualKeyCode::D) {
-                // println!("D is button is pushed");
-                input_key = 0x09;
-            } else if input.key_pressed(VirtualKeyCode::F) || input.key_held(VirtualKeyCode::F) {
-                // println!("F is button is pushed");
-                input_key = 0x0e;
-            } else if input.key_pressed(VirtualKeyCode::Z) || input.key_held(VirtualKeyCode::Z) {
-                // println!("Z is button is pushed");
-                input_key = 0x0a;
-            } else if input.key_pressed(VirtualKeyCode::X) || input.key_held(VirtualKeyCode::X) {
-                // println!("X is button is pushed");
-                input_key = 0x00;
-            } else if input.key_pressed(VirtualKeyCode::C) || input.key_held(VirtualKeyCode::C) {
-                // println!("C is button is pushed");
-                input_key = 0x0b;
-            } else if input.key_pressed(VirtualKeyCode::V) || input.key_held(VirtualKeyCode::V) {
-                // println!("V is button is pushed");
-                input_key = 0x0f;
-            }         
+            } else if input.key_pressed(VirtualKeyCode::F2) || input.key_pressed(VirtualKeyCode::F3) {
+                /* A movie only plays out the same from the start */
+                if playback.is_some() || movie_recording.is_some() {
+                    println!("Can't reset while a movie is playing or recording");
+                } else if input.key_pressed(VirtualKeyCode::F2) {
+                    _c.soft_reset();
+                    world.filter.set_mode(world.filter.mode);
+                } else {
+                    reload(&mut _c, &mut world, &file_path, &args.patches);
+                }
+            } else if let Some(key) = viewer_key(&input).filter(|_| viewer.open) {
+                /* Memory can only change while paused, and never under a movie */
+                let editable : bool = scheduler.paused && playback.is_none() && movie_recording.is_none();
+                if let Some(address) = viewer.key(&mut _c, key, editable) {
+                    println!("Wrote {:#05x} = {:#04x}", address, _c.mem.peek(address).unwrap_or(0));
+                }
+            } else if viewer.open && input.key_pressed(VirtualKeyCode::N) {
+                search = Some(cheat::make_search(&_c));
+                println!("Searching all {} bytes of memory", _c.mem.size());
+            } else if let Some(compare) = search_key(&input).filter(|_| viewer.open) {
+                match search.as_mut() {
+                    Some(s) => {
+                        let left : usize = s.narrow(&_c, compare);
+                        let shown : Vec<String> = s.candidates.iter().take(16).map(|a| format!("{:#05x}", a)).collect();
+                        println!("{:?}: {} left {}", compare, left, shown.join(" "));
+                    },
+                    None => println!("Press N to start a search first"),
+                }
+            } else if viewer.open && input.key_pressed(VirtualKeyCode::Z) {
+                /* Freeze the byte under the cursor, or turn the cheat already there off and on */
+                let cheats : &mut Vec<cheat::Cheat> = cheat_file.entry(rom_sha1.clone()).or_default();
+                match cheats.iter_mut().find(|c| c.address == viewer.cursor) {
+                    Some(c) => c.toggle(&_c),
+                    None => {
+                        let mut c : cheat::Cheat = cheat::make_freeze(&format!("{:#05x}", viewer.cursor), viewer.cursor, 1);
+                        c.hold(&_c);
+                        cheats.push(c);
+                    },
+                }
+                if let Some(c) = cheats.iter().find(|c| c.address == viewer.cursor) {
+                    println!("{}", c);
+                }
+                if let Some(path) = &cheat_path {
+                    if let Err(err) = cheat::save(path, &cheat_file) {
+                        error!("Failed to save cheats to {}: {err}", path.display());
+                    }
+                }
+            } else if input.key_pressed(VirtualKeyCode::F4) {
+                /* Freezes waiting to be turned on hold what's there now */
+                cheats_on = !cheats_on;
+                if let Some(cheats) = cheat_file.get_mut(&rom_sha1).filter(|_| cheats_on) {
+                    cheat::hold(cheats, &_c);
+                }
+                println!("Cheats {}", if cheats_on { "on" } else { "off" });
+                for c in cheat_file.get(&rom_sha1).map_or(&[][..], |c| &c[..]) {
+                    println!("  {}", c);
+                }
+            } else if input.key_pressed(VirtualKeyCode::F5) {
+                scheduler.toggle_pause();
+            } else if input.key_pressed(VirtualKeyCode::F6) {
+                scheduler.step();
+            } else if input.key_pressed(VirtualKeyCode::F7) || input.key_pressed(VirtualKeyCode::F8) {
+                /* Movies only have the one speed, so it can't change part way through */
+                if playback.is_some() || movie_recording.is_some() {
+                    println!("Can't change the speed while a movie is playing or recording");
+                } else if input.key_pressed(VirtualKeyCode::F7) {
+                    scheduler.slower();
+                } else {
+                    scheduler.faster();
+                }
+            } else if input.key_pressed(VirtualKeyCode::F1) {
+                /* Cycle through the anti-flicker filters */
+                world.filter.next_mode();
+                println!("Display filter: {:?}", world.filter.mode);
+            } else if input.key_pressed(VirtualKeyCode::F12) {
+                /* Screenshot at the same scale the window is drawing at */
+                let scale : u32 = window.inner_size().width / WIDTH;
+                match screenshot::save_png(Path::new("."), &file_path, &world, &_c, scale) {
+                    Ok(path) => println!("Saved screenshot to {}", path.display()),
+                    Err(err) => error!("Failed to save screenshot: {err}"),
+                }
+            } else if input.key_pressed(VirtualKeyCode::F11) {
+                /* Start or stop recording gameplay into a gif */
+                if recording.is_some() {
+                    stop_recording(&mut recording);
+                } else {
+                    let name : String = screenshot::file_name(&file_path,
+                                                              &screenshot::timestamp(), "gif");
+                    let scale : u32 = window.inner_size().width / WIDTH;
+                    recording = start_recording(Path::new(&name), &world, scale);
+                }
+            }
 
         // Resize the window
         if let Some(size) = input.window_resized() {
@@ -169,16 +762,57 @@ fn main() -> Result<(), Error>{
                 return;
             }
         }
-        /* Execute instruction, keep within 700 instructions / second */
-        if num_instructions_finished % 700 == 0 {
-            if sys_timer.get_elapsed_time() > sys_timer.curr_second {
-                sys_timer.curr_second = sys_timer.get_elapsed_time();
-                _c.exec(input_key);
-                num_instructions_finished += 1;
+        if watcher.as_mut().is_some_and(|w| w.changed()) {
+            reload(&mut _c, &mut world, &file_path, &args.patches);
+        }
+
+        /* Fast forward while tab is held */
+        scheduler.fast_forward = input.key_held(VirtualKeyCode::Tab);
+        let mut status : String = scheduler.status();
+        if let Some(fault) = &_c.fault {
+            status.push_str(&format!(", stopped: {}", fault));
+        }
+        if status != shown_status {
+            window.set_title(&format!("r_chip8 - {} - {}", title, status));
+            shown_status = status;
+        }
+
+        /* Run however many frames are due, so we stay at 60 frames / second.
+         * Fast forward runs as many as fit in one frame of wall clock time */
+        let batch_start : Instant = Instant::now();
+        for _ in 0..scheduler.frames_due() {
+            if scheduler.fast_forward && batch_start.elapsed() >= scheduler::FRAME_TIME {
+                break;
+            }
+            /* A movie being played back has the keypad until it runs out */
+            let keypad : input::Keypad = match &playback {
+                Some(m) => m.keypad(movie_frame).unwrap_or(keypad_input.keypad),
+                None => keypad_input.keypad,
+            };
+            movie_frame += 1;
+            if let Some(m) = movie_recording.as_mut() {
+                m.record(keypad);
+            }
+
+            /* Cheats stay out of movies, they wouldn't play back the same without them */
+            if cheats_on && playback.is_none() && movie_recording.is_none() {
+                if let Some(cheats) = cheat_file.get(&rom_sha1) {
+                    cheat::apply(cheats, &mut _c);
+                }
+            }
+
+            let was_beeping : bool = _c.sound_timer.is_running();
+            scheduler.run_frame(&mut _c, keypad.input_key());
+            world.filter.update(&_c.display);
+            if audio && !was_beeping && _c.sound_timer.is_running() {
+                beep();
+            }
+
+            if let Some(rec) = recording.as_mut() {
+                if let Err(err) = rec.capture(&world, &_c) {
+                    error!("Failed to record frame: {err}");
+                }
             }
-        } else {
-            _c.exec(input_key);
-            num_instructions_finished += 1;
         }
 
         /* Request a redraw */
@@ -1,7 +1,11 @@
+use crate::bus::{self, Bus, BusError};
+use crate::font;
+
 /* 
  * The struct that holds our memory. All memory is r/w
  * Holds up to 4096 bytes.
  */
+#[derive(Clone)]
 pub struct Mem {
     pub data: [u8; 4096],
 }
@@ -44,61 +48,33 @@ pub fn make_memory() -> Mem {
     let data:[u8; 4096] = [0x0; 4096];
     let mut new_mem: Mem = Mem{data: data};
 
-    /* Store the fonts into the memory */
-    let mut fonts_counter: usize = 0;
-    for i in _FONT_START.._FONT_END {
-        new_mem.data[i] = FONTS[fonts_counter];
-        fonts_counter += 1;
-    }
+    /* Store the fonts into the memory, the big digits go right after */
+    let fonts : Vec<u8> = font::make_font().bytes();
+    new_mem.data[_FONT_START.._FONT_START + fonts.len()].copy_from_slice(&fonts);
     return new_mem;
 }
 
-impl Mem {
-    /* Write a byte to memory at ADDRESS */
-    pub fn write8(&mut self, address : usize, data : u8)->bool {
-        if (address < 1) || (address > self.data.len() - 1) {
-            return false;
-        }
-        self.data[address] = data;
-        return true;
-    }
-    /* Write 16 bits to memory @ ADDRESS */
-    pub fn write16(&mut self, address : usize, data : [u8; 2])->bool {
-        if (address < 1) || (address > self.data.len() - 2) {
-            return false;
-        }
-        self.data[address] = data[0];
-        self.data[address+1] = data[1];
-        return true;
+/* Plain chip8 memory, everything from 0x000 to 0xfff can be read and written */
+impl Bus for Mem {
+    fn read8(&mut self, address : usize) -> Result<u8, BusError> {
+        return self.peek(address);
     }
 
-    /* Read a byte from memory @ ADDRESS */
-    pub fn read8(&mut self, address : usize)->u8 {
-        let mut out : u8 = 0x00;
-        if (address < 1) || (address > self.data.len() - 1) {
-            return out; 
-        }
-        out = self.data[address];
-        return out;
+    fn peek(&self, address : usize) -> Result<u8, BusError> {
+        return Ok(self.data[bus::check_range(address, self.data.len())?]);
     }
 
-    /* Read 16 bits to memory @ ADDRESS */
-    pub fn read16(&self, address : usize)->[u8; 2] {
-        let mut out : [u8; 2] = [0x00, 0x00];
-        if (address < 1) || (address > self.data.len() - 2) {
-            return out; 
-        }
+    fn write8(&mut self, address : usize, data : u8) -> Result<(), BusError> {
+        self.data[bus::check_range(address, self.data.len())?] = data;
+        return Ok(());
+    }
 
-        out[0] = self.data[address];
-        out[1] = self.data[address+1];
-        return out;
+    fn size(&self) -> usize {
+        return self.data.len();
     }
 
-    /* Write a game file to memory */
-    pub fn store_game(&mut self, contents : Vec<u8>) {
-        for i in 0..contents.len() {
-            self.data[_PROG_START + i] = contents[i];
-        }
+    fn reset(&mut self) {
+        *self = make_memory();
     }
 }
 
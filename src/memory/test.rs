@@ -5,16 +5,20 @@ fn test_make_mem() {
     let m1: Mem = make_memory();
     assert_eq!(m1.data.len(), 4096);
 
-    // Ensure that the font was inserted in 
+    // Ensure that the font was inserted in, all of it, with the big digits after
     let mut font_counter: usize = 0;
+    let big_start: usize = _FONT_END + 1;
     for byte in 0..m1.data.len() {
-        if byte >= _FONT_START && byte < _FONT_END {
+        if (_FONT_START..=_FONT_END).contains(&byte) {
             assert_eq!(m1.data[byte], FONTS[font_counter]);
             font_counter += 1;
+        } else if byte >= big_start && byte < big_start + font::BIG_DIGITS.len() {
+            assert_eq!(m1.data[byte], font::BIG_DIGITS[byte - big_start]);
         } else {
             assert_eq!(m1.data[byte], 0x0);
         }
     }
+    assert_eq!(font_counter, FONTS.len());
 }
 
 
@@ -26,8 +30,8 @@ fn test_mem_wr16() {
 
     let b1 : u8 = 0xbe;
     let b2 : u8 = 0xef;
-    assert_eq!(m1.write16(0x100, [b1, b2]), true, "Testing write");
-    assert_eq!(m1.read16(0x100), [b1, b2], "Testing read");
+    assert_eq!(m1.write16(0x100, [b1, b2]), Ok(()), "Testing write");
+    assert_eq!(m1.read16(0x100), Ok([b1, b2]), "Testing read");
 
 }
 
@@ -38,12 +42,36 @@ fn test_mem_wr8() {
 
     let b1 : u8 = 0xbe;
     let b2 : u8 = 0xef;
-    assert_eq!(m1.write8(0x100, b1), true, "Testing write");
-    assert_eq!(m1.write8(0x101, b2), true, "Testing write");
-    assert_eq!(m1.read16(0x100), [b1, b2], "Testing read");
-    assert_eq!(m1.read8(0x100), b1, "Testing read");
-    assert_eq!(m1.read8(0x101), b2, "Testing read");
+    assert_eq!(m1.write8(0x100, b1), Ok(()), "Testing write");
+    assert_eq!(m1.write8(0x101, b2), Ok(()), "Testing write");
+    assert_eq!(m1.read16(0x100), Ok([b1, b2]), "Testing read");
+    assert_eq!(m1.read8(0x100), Ok(b1), "Testing read");
+    assert_eq!(m1.read8(0x101), Ok(b2), "Testing read");
 
 }
 
 
+
+#[test]
+fn test_mem_store_game() {
+    let mut m1: Mem = make_memory();
+    m1.store_game(vec![0xab; 8192]);
+    assert_eq!(m1.data.len(), 4096);
+    assert_eq!(m1.read8(0x200), Ok(0xab));
+    assert_eq!(m1.read8(0xfff), Ok(0xab));
+}
+
+/* The whole 4K can be used, address 0 too, and nothing past it */
+#[test]
+fn test_mem_bounds() {
+    let mut m1: Mem = make_memory();
+    assert_eq!(m1.write8(0x000, 0x12), Ok(()));
+    assert_eq!(m1.read8(0x000), Ok(0x12));
+    assert_eq!(m1.write16(0xffe, [0xbe, 0xef]), Ok(()));
+    assert_eq!(m1.read16(0xffe), Ok([0xbe, 0xef]));
+
+    assert_eq!(m1.read8(0x1000), Err(BusError::OutOfRange(0x1000)));
+    assert_eq!(m1.write8(0x1000, 0x12), Err(BusError::OutOfRange(0x1000)));
+    assert_eq!(m1.read16(0xfff), Err(BusError::OutOfRange(0x1000)));
+    assert_eq!(m1.write16(0xfff, [0x12, 0x34]), Err(BusError::OutOfRange(0x1000)));
+}
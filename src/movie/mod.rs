@@ -0,0 +1,227 @@
+use super::*;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+/* Input movies. Records the keypad every frame, along with everything else
+ * that decides how a run goes (rom, random seed, speed, quirks), so a session
+ * can be played back through the core and come out exactly the same. The
+ * file is plain text:
+ *
+ *   chip8-movie 1
+ *   rom <sha1 of the rom>
+ *   seed <seed for CXNN>
+ *   ipf <instructions per frame>
+ *   quirks <quirks that were on, space separated>
+ *   font <font style> <address>     <- optional, chip48 @ 0x050 if it's missing
+ *   stack <overflow> <depth>        <- optional, error 16 if it's missing
+ *   memory <size>                   <- optional, 4k if it's missing
+ *   frames
+ *   0000        <- keypad state as hex, one line per frame, bit N => key N
+ *   0020
+ */
+
+/* First line of every movie file */
+pub const MOVIE_HEADER: &str = "chip8-movie 1";
+
+#[derive(Clone)]
+pub struct Movie {
+    pub rom_sha1 : String, /* Rom the movie was recorded on */
+    pub seed : u64, /* Random seed the cpu started with */
+    pub ipf : u16, /* Instructions per frame */
+    pub quirks : cpu::Quirks, /* Quirks that were on */
+    pub font : font::Font, /* Font that was loaded */
+    pub stack_depth : usize, /* Return addresses the stack held */
+    pub stack : stack::Overflow, /* What going past either end of the stack did */
+    pub memory : usize, /* Bytes of memory, the bus has to be made this size before prepare */
+    pub frames : Vec<u16>, /* Keypad state for every frame */
+}
+
+/* Things that can go wrong with a movie file */
+#[derive(Debug)]
+pub enum MovieError {
+    Io(io::Error), /* Couldn't read the file */
+    Parse(usize, String), /* Bad line, line number and what was wrong */
+    RomMismatch(String, String), /* Movie is for another rom, (expected, found) */
+}
+
+impl fmt::Display for MovieError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MovieError::Io(err) => write!(f, "couldn't read movie: {}", err),
+            MovieError::Parse(line, what) => write!(f, "bad movie, line {}: {}", line, what),
+            MovieError::RomMismatch(expected, found) =>
+                write!(f, "movie was recorded on rom {} but this rom is {}", expected, found),
+        }
+    }
+}
+
+/* Start a new movie for CORE running the rom with ROM_SHA1 at IPF */
+pub fn make_movie<B : Bus>(rom_sha1 : &str, core : &cpu::CPU<B>, ipf : u16) -> Movie {
+    let m = Movie {
+        rom_sha1: rom_sha1.to_string(),
+        seed: core.seed,
+        ipf: ipf,
+        quirks: core.quirks,
+        font: core.font,
+        stack_depth: core.stack.depth,
+        stack: core.stack.overflow,
+        memory: core.mem.size(),
+        frames: vec![],
+    };
+    return m;
+}
+
+/* Read a movie out of TEXT */
+pub fn parse(text : &str) -> Result<Movie, MovieError> {
+    let mut movie : Movie = Movie {
+        rom_sha1: String::new(),
+        seed: 0,
+        ipf: scheduler::DEFAULT_IPF,
+        quirks: cpu::make_quirks(),
+        font: font::make_font(),
+        stack_depth: stack::SCHIP_DEPTH,
+        stack: stack::Overflow::Error,
+        memory: bus::CHIP8_SIZE,
+        frames: vec![],
+    };
+
+    let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim()));
+    match lines.next() {
+        Some((_, MOVIE_HEADER)) => {},
+        _ => return Err(MovieError::Parse(1, String::from("not a chip8 movie"))),
+    }
+
+    /* Header, up until "frames" */
+    let mut in_frames : bool = false;
+    for (n, line) in lines {
+        if line.is_empty() {
+            continue;
+        }
+        let bad = |what : &str| MovieError::Parse(n, what.to_string());
+
+        if in_frames {
+            match u16::from_str_radix(line, 16) {
+                Ok(state) => movie.frames.push(state),
+                Err(_) => return Err(bad("keypad state isn't hex")),
+            }
+            continue;
+        }
+
+        let (key, value) : (&str, &str) = match line.split_once(' ') {
+            Some((key, value)) => (key, value.trim()),
+            None => (line, ""),
+        };
+        match key {
+            "rom" => movie.rom_sha1 = value.to_lowercase(),
+            "seed" => movie.seed = value.parse().map_err(|_| bad("bad seed"))?,
+            "ipf" => movie.ipf = value.parse().map_err(|_| bad("bad ipf"))?,
+            "quirks" => {
+                for name in value.split_whitespace() {
+                    if !movie.quirks.set(name, true) {
+                        return Err(bad("unknown quirk"));
+                    }
+                }
+            },
+            "font" => {
+                let (style, address) : (&str, &str) = value.split_once(' ').ok_or(bad("bad font"))?;
+                movie.font.style = font::parse_style(style).ok_or(bad("unknown font"))?;
+                movie.font.address = address.strip_prefix("0x")
+                    .and_then(|hex| usize::from_str_radix(hex, 16).ok())
+                    .filter(|a| *a <= font::MAX_ADDRESS)
+                    .ok_or(bad("bad font address"))?;
+            },
+            "stack" => {
+                let (overflow, depth) : (&str, &str) = value.split_once(' ').ok_or(bad("bad stack"))?;
+                movie.stack = stack::parse_overflow(overflow).ok_or(bad("unknown stack mode"))?;
+                movie.stack_depth = depth.parse().ok()
+                    .filter(|d| (1..=stack::MAX_DEPTH).contains(d))
+                    .ok_or(bad("bad stack depth"))?;
+            },
+            "memory" => movie.memory = bus::parse_memory(value).ok_or(bad("unknown memory size"))?,
+            "frames" => in_frames = true,
+            _ => return Err(bad("unknown header")),
+        }
+    }
+    return Ok(movie);
+}
+
+/* Load the movie @ PATH */
+pub fn load(path : &Path) -> Result<Movie, MovieError> {
+    match fs::read_to_string(path) {
+        Ok(text) => return parse(&text),
+        Err(err) => return Err(MovieError::Io(err)),
+    }
+}
+
+/* Play FRAMES_TO_RUN frames of MOVIE back on CORE, no window needed. Frames past the
+ * end of the movie have nothing held. CORE should have the rom loaded and
+ * nothing run yet. Returns the number of frames that ran */
+pub fn replay<B : Bus>(movie : &Movie, core : &mut cpu::CPU<B>, frames_to_run : u64) -> u64 {
+    movie.prepare(core);
+    let mut frames : u64 = 0;
+    for frame in 0..frames_to_run {
+        let keypad : input::Keypad = movie.keypad(frame).unwrap_or(input::Keypad { state: 0 });
+        frames += 1;
+        if !core.run_frame(movie.ipf, keypad.input_key()) {
+            break;
+        }
+    }
+    return frames;
+}
+
+impl Movie {
+    /* Add a frame with the keypad in KEYPAD */
+    pub fn record(&mut self, keypad : input::Keypad) {
+        self.frames.push(keypad.state);
+    }
+
+    /* The keypad for frame FRAME, None once the movie is over */
+    pub fn keypad(&self, frame : u64) -> Option<input::Keypad> {
+        let state : &u16 = self.frames.get(frame as usize)?;
+        return Some(input::Keypad { state: *state });
+    }
+
+    /* Make sure the rom with ROM_SHA1 is the one the movie was made on */
+    pub fn check_rom(&self, rom_sha1 : &str) -> Result<(), MovieError> {
+        if self.rom_sha1 != rom_sha1.to_lowercase() {
+            return Err(MovieError::RomMismatch(self.rom_sha1.clone(), rom_sha1.to_string()));
+        }
+        return Ok(());
+    }
+
+    /* Set CORE up the same way it was when the movie was recorded */
+    pub fn prepare<B : Bus>(&self, core : &mut cpu::CPU<B>) {
+        core.set_seed(self.seed);
+        core.quirks = self.quirks;
+        core.set_font(self.font);
+        core.stack = stack::make_stack(self.stack_depth, self.stack);
+    }
+
+    /* Write the movie out as text */
+    pub fn to_text(&self) -> String {
+        let mut out : String = String::new();
+        out.push_str(MOVIE_HEADER);
+        out.push('\n');
+        out.push_str(&format!("rom {}\n", self.rom_sha1));
+        out.push_str(&format!("seed {}\n", self.seed));
+        out.push_str(&format!("ipf {}\n", self.ipf));
+        out.push_str(&format!("quirks {}\n", self.quirks.enabled().join(" ")));
+        out.push_str(&format!("font {} {:#05x}\n", self.font.style.name(), self.font.address));
+        out.push_str(&format!("stack {} {}\n", self.stack.name(), self.stack_depth));
+        out.push_str(&format!("memory {}\n", bus::memory_name(self.memory)));
+        out.push_str("frames\n");
+        for state in self.frames.iter() {
+            out.push_str(&format!("{:04x}\n", state));
+        }
+        return out;
+    }
+
+    /* Save the movie to PATH */
+    pub fn save(&self, path : &Path) -> io::Result<()> {
+        return fs::write(path, self.to_text());
+    }
+}
+
+#[cfg(test)]
+pub mod test;
@@ -0,0 +1,90 @@
+use super::*;
+
+/* Program that mixes random numbers and key presses:
+ * v0 = rand, wait for key into v1, draw v0's font char @ (v1, v0), loop.
+ * The tas tests run it too */
+pub fn make_test_cpu() -> cpu::CPU {
+    let mut mem: memory::Mem = memory::make_memory();
+    mem.store_game(vec![0xC0, 0x3F,  // v0 = rand & 0x3f
+                        0xF1, 0x0A,  // v1 = key
+                        0xF0, 0x29,  // i = font for v0
+                        0xD1, 0x05,  // draw @ (v1, v0)
+                        0x12, 0x00]); // loop
+    return cpu::make_cpu(mem);
+}
+
+#[test]
+fn test_movie_text() {
+    let c : cpu::CPU = make_test_cpu();
+    let mut m : Movie = make_movie("ABCDEF", &c, 9);
+    m.quirks.wrap_sprites = true;
+    m.seed = 1234;
+    m.font = font::Font { style: font::Style::Vip, address: 0x000 };
+    m.stack = stack::Overflow::Memory;
+    m.stack_depth = 12;
+    m.record(input::Keypad { state: 0x0000 });
+    m.record(input::Keypad { state: 0x8021 });
+
+    let text : String = m.to_text();
+    assert_eq!(text, "chip8-movie 1\nrom ABCDEF\nseed 1234\nipf 9\nquirks wrap_sprites\n\
+                      font vip 0x000\nstack memory 12\nmemory 4k\nframes\n0000\n8021\n");
+
+    let back : Movie = parse(&text).unwrap();
+    assert_eq!(back.rom_sha1, "abcdef");
+    assert_eq!(back.seed, 1234);
+    assert_eq!(back.ipf, 9);
+    assert_eq!(back.quirks, m.quirks);
+    assert_eq!(back.font, m.font);
+    assert_eq!((back.stack, back.stack_depth), (stack::Overflow::Memory, 12));
+    assert_eq!(back.memory, bus::CHIP8_SIZE);
+    assert_eq!(back.frames, m.frames);
+    assert_eq!(back.keypad(1), Some(input::Keypad { state: 0x8021 }));
+    assert_eq!(back.keypad(2), None);
+}
+
+#[test]
+fn test_movie_parse_errors() {
+    assert!(matches!(parse("hello"), Err(MovieError::Parse(1, _))));
+    assert!(matches!(parse("chip8-movie 1\nseed x"), Err(MovieError::Parse(2, _))));
+    assert!(matches!(parse("chip8-movie 1\nquirks nope"), Err(MovieError::Parse(2, _))));
+    assert!(matches!(parse("chip8-movie 1\nframes\n00\nzz"), Err(MovieError::Parse(4, _))));
+    assert!(matches!(parse("chip8-movie 1\nfont comic 0x000"), Err(MovieError::Parse(2, _))));
+    assert!(matches!(parse("chip8-movie 1\nfont vip 0x1ff"), Err(MovieError::Parse(2, _))));
+    assert!(matches!(parse("chip8-movie 1\nstack wrap 0"), Err(MovieError::Parse(2, _))));
+    assert!(matches!(parse("chip8-movie 1\nstack sideways 12"), Err(MovieError::Parse(2, _))));
+    assert!(matches!(parse("chip8-movie 1\nmemory 1k"), Err(MovieError::Parse(2, _))));
+    assert_eq!(parse("chip8-movie 1\nmemory 64k").unwrap().memory, bus::XO_SIZE);
+
+    /* Movies from before fonts could be picked had the usual one */
+    assert_eq!(parse("chip8-movie 1\nframes").unwrap().font, font::make_font());
+}
+
+#[test]
+fn test_movie_check_rom() {
+    let c : cpu::CPU = make_test_cpu();
+    let m : Movie = make_movie(&rom::sha1_hex(b"rom"), &c, 9);
+    assert!(m.check_rom(&rom::sha1_hex(b"rom")).is_ok());
+    assert!(matches!(m.check_rom(&rom::sha1_hex(b"other")), Err(MovieError::RomMismatch(_, _))));
+}
+
+#[test]
+fn test_movie_replay() {
+    /* Record a session with some key presses */
+    let mut c : cpu::CPU = make_test_cpu();
+    let mut m : Movie = make_movie("abc", &c, 10);
+    for frame in 0..60 {
+        let keypad = input::Keypad { state: if frame % 7 == 0 { 1 << (frame % 16) } else { 0 } };
+        m.record(keypad);
+        c.run_frame(m.ipf, keypad.input_key());
+    }
+
+    /* Play it back on a fresh cpu that was seeded differently, through the file format */
+    let mut replayed : cpu::CPU = make_test_cpu();
+    replayed.set_seed(c.seed.wrapping_add(1));
+    let back : Movie = parse(&m.to_text()).unwrap();
+    assert_eq!(replay(&back, &mut replayed, 60), 60);
+
+    assert_eq!(replayed.regs, c.regs);
+    assert_eq!(replayed.pc, c.pc);
+    assert_eq!(replayed.display.screen, c.display.screen);
+}
@@ -0,0 +1,227 @@
+use std::fmt;
+
+/* Rom patches, so fixes and translations can be passed around without the
+ * roms they go on. Both of the usual formats work:
+ *
+ *   IPS  "PATCH", then records of a 3 byte offset and 2 byte length followed
+ *        by that many bytes (or, with a length of 0, a 2 byte count and a
+ *        byte to repeat), then "EOF" and maybe a 3 byte size to cut the rom to.
+ *   BPS  "BPS1", then the sizes, metadata and copy actions, all numbers as
+ *        varints, then the CRC32s of the rom, the patched rom and the patch.
+ *        The CRC32s get checked, so a patch only goes on the rom it was made for
+ *
+ * Which one a patch is comes from its first bytes */
+
+/* Patch formats */
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Ips,
+    Bps,
+}
+
+/* Things that can go wrong applying a patch */
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum PatchError {
+    UnknownFormat, /* Not IPS or BPS */
+    Truncated, /* Patch ends part way through */
+    BadAction(usize), /* Offset in the patch of an action that reads or writes out of bounds */
+    WrongRom(u32, u32), /* CRC32 the patch was made for, CRC32 of the rom it went on */
+    BadChecksum(&'static str, u32, u32), /* What, CRC32 expected, CRC32 there is */
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatchError::UnknownFormat => write!(f, "not an IPS or BPS patch"),
+            PatchError::Truncated => write!(f, "patch ends part way through"),
+            PatchError::BadAction(at) => write!(f, "patch goes out of bounds at byte {}", at),
+            PatchError::WrongRom(want, got) =>
+                write!(f, "patch is for the rom with CRC32 {:08x}, this one is {:08x}", want, got),
+            PatchError::BadChecksum(what, want, got) =>
+                write!(f, "{} should have CRC32 {:08x}, but it's {:08x}", what, want, got),
+        }
+    }
+}
+
+/* CRC32 of DATA, the zip / png one */
+pub fn crc32(data : &[u8]) -> u32 {
+    let mut crc : u32 = 0xFFFFFFFF;
+    for b in data {
+        crc ^= *b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB88320 } else { crc >> 1 };
+        }
+    }
+    return !crc;
+}
+
+/* Work out which format PATCH is in */
+pub fn detect(patch : &[u8]) -> Option<Format> {
+    if patch.starts_with(b"PATCH") {
+        return Some(Format::Ips);
+    }
+    if patch.starts_with(b"BPS1") {
+        return Some(Format::Bps);
+    }
+    return None;
+}
+
+/* ROM with PATCH applied, whichever format it is */
+pub fn apply(rom : &[u8], patch : &[u8]) -> Result<Vec<u8>, PatchError> {
+    match detect(patch) {
+        Some(Format::Ips) => return apply_ips(rom, patch),
+        Some(Format::Bps) => return apply_bps(rom, patch),
+        None => return Err(PatchError::UnknownFormat),
+    }
+}
+
+/* Big endian number out of the N bytes of PATCH @ AT */
+fn read_be(patch : &[u8], at : usize, n : usize) -> Result<usize, PatchError> {
+    let bytes : &[u8] = patch.get(at..at + n).ok_or(PatchError::Truncated)?;
+    return Ok(bytes.iter().fold(0, |acc, b| (acc << 8) | *b as usize));
+}
+
+/* ROM with the IPS PATCH applied. Records past the end of the rom make it bigger */
+pub fn apply_ips(rom : &[u8], patch : &[u8]) -> Result<Vec<u8>, PatchError> {
+    let mut out : Vec<u8> = rom.to_vec();
+    let mut at : usize = 5;
+    loop {
+        if patch.get(at..at + 3) == Some(b"EOF") {
+            at += 3;
+            break;
+        }
+        let offset : usize = read_be(patch, at, 3)?;
+        let size : usize = read_be(patch, at + 3, 2)?;
+        at += 5;
+        let bytes : Vec<u8> = if size == 0 {
+            let count : usize = read_be(patch, at, 2)?;
+            let value : u8 = *patch.get(at + 2).ok_or(PatchError::Truncated)?;
+            at += 3;
+            vec![value; count]
+        } else {
+            let bytes : &[u8] = patch.get(at..at + size).ok_or(PatchError::Truncated)?;
+            at += size;
+            bytes.to_vec()
+        };
+        if out.len() < offset + bytes.len() {
+            out.resize(offset + bytes.len(), 0);
+        }
+        out[offset..offset + bytes.len()].copy_from_slice(&bytes);
+    }
+
+    /* Lunar IPS's extension, cut the rom down to size */
+    if patch.len() == at + 3 {
+        out.truncate(read_be(patch, at, 3)?);
+    }
+    return Ok(out);
+}
+
+/* BPS varint out of PATCH @ AT, moving AT past it */
+fn read_varint(patch : &[u8], at : &mut usize) -> Result<usize, PatchError> {
+    let mut data : usize = 0;
+    let mut shift : usize = 1;
+    loop {
+        let x : u8 = *patch.get(*at).ok_or(PatchError::Truncated)?;
+        *at += 1;
+        data = ((x & 0x7f) as usize).checked_mul(shift)
+            .and_then(|add| data.checked_add(add)).ok_or(PatchError::BadAction(*at))?;
+        if x & 0x80 != 0 {
+            break;
+        }
+        shift = shift.checked_shl(7).filter(|s| *s != 0).ok_or(PatchError::BadAction(*at))?;
+        data = data.checked_add(shift).ok_or(PatchError::BadAction(*at))?;
+    }
+    return Ok(data);
+}
+
+/* Relative offset out of the varint D, the low bit is the sign */
+fn read_offset(d : usize) -> isize {
+    let offset : isize = (d >> 1) as isize;
+    return if d & 1 != 0 { -offset } else { offset };
+}
+
+/* Little endian u32 out of PATCH @ AT */
+fn read_u32(patch : &[u8], at : usize) -> u32 {
+    return u32::from_le_bytes([patch[at], patch[at + 1], patch[at + 2], patch[at + 3]]);
+}
+
+/* ROM with the BPS PATCH applied, after checking it's the rom the patch is for */
+pub fn apply_bps(rom : &[u8], patch : &[u8]) -> Result<Vec<u8>, PatchError> {
+    if patch.len() < 4 + 12 {
+        return Err(PatchError::Truncated);
+    }
+    let footer : usize = patch.len() - 12;
+    let (source_crc, target_crc, patch_crc) : (u32, u32, u32) =
+        (read_u32(patch, footer), read_u32(patch, footer + 4), read_u32(patch, footer + 8));
+    let crc : u32 = crc32(&patch[..footer + 8]);
+    if crc != patch_crc {
+        return Err(PatchError::BadChecksum("patch", patch_crc, crc));
+    }
+    let crc : u32 = crc32(rom);
+    if crc != source_crc {
+        return Err(PatchError::WrongRom(source_crc, crc));
+    }
+
+    let mut at : usize = 4;
+    let _source_size : usize = read_varint(patch, &mut at)?;
+    let target_size : usize = read_varint(patch, &mut at)?;
+    let metadata_size : usize = read_varint(patch, &mut at)?;
+    at = at.checked_add(metadata_size).ok_or(PatchError::Truncated)?;
+
+    let mut out : Vec<u8> = Vec::with_capacity(target_size.min(1 << 20));
+    let mut source_offset : isize = 0;
+    let mut target_offset : isize = 0;
+    while at < footer {
+        let start : usize = at;
+        let bad = || PatchError::BadAction(start);
+        let data : usize = read_varint(patch, &mut at)?;
+        let length : usize = (data >> 2) + 1;
+        if out.len() + length > target_size {
+            return Err(bad());
+        }
+        match data & 3 {
+            /* Source read, the rom's bytes where we are now */
+            0 => {
+                let bytes : &[u8] = rom.get(out.len()..out.len() + length).ok_or_else(bad)?;
+                out.extend_from_slice(bytes);
+            },
+            /* Target read, bytes out of the patch */
+            1 => {
+                let bytes : &[u8] = patch.get(at..at + length).filter(|_| at + length <= footer).ok_or_else(bad)?;
+                out.extend_from_slice(bytes);
+                at += length;
+            },
+            /* Source copy, the rom's bytes from somewhere else */
+            2 => {
+                let d : usize = read_varint(patch, &mut at)?;
+                source_offset = source_offset.checked_add(read_offset(d)).ok_or_else(bad)?;
+                let from : usize = usize::try_from(source_offset).map_err(|_| bad())?;
+                let bytes : &[u8] = rom.get(from..from + length).ok_or_else(bad)?;
+                out.extend_from_slice(bytes);
+                source_offset = source_offset.checked_add(length as isize).ok_or_else(bad)?;
+            },
+            /* Target copy, bytes already written, a byte at a time since it can overlap */
+            _ => {
+                let d : usize = read_varint(patch, &mut at)?;
+                target_offset = target_offset.checked_add(read_offset(d)).ok_or_else(bad)?;
+                for _ in 0..length {
+                    let from : usize = usize::try_from(target_offset).map_err(|_| bad())?;
+                    let b : u8 = *out.get(from).ok_or_else(bad)?;
+                    out.push(b);
+                    target_offset += 1;
+                }
+            },
+        }
+    }
+
+    let crc : u32 = crc32(&out);
+    if crc != target_crc {
+        return Err(PatchError::BadChecksum("patched rom", target_crc, crc));
+    }
+    return Ok(out);
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,103 @@
+use super::*;
+
+/* BPS varint for N */
+fn varint(n : usize) -> Vec<u8> {
+    let mut n : usize = n;
+    let mut out : Vec<u8> = vec![];
+    loop {
+        let x : u8 = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            out.push(0x80 | x);
+            return out;
+        }
+        out.push(x);
+        n -= 1;
+    }
+}
+
+/* BPS patch turning SOURCE into TARGET with ACTIONS, footer and all */
+fn make_bps(source : &[u8], target : &[u8], actions : &[u8]) -> Vec<u8> {
+    let mut p : Vec<u8> = b"BPS1".to_vec();
+    p.extend(varint(source.len()));
+    p.extend(varint(target.len()));
+    p.extend(varint(3));
+    p.extend_from_slice(b"r8!");
+    p.extend_from_slice(actions);
+    p.extend(crc32(source).to_le_bytes());
+    p.extend(crc32(target).to_le_bytes());
+    let crc : u32 = crc32(&p);
+    p.extend(crc.to_le_bytes());
+    return p;
+}
+
+#[test]
+fn test_patch_crc32() {
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"123456789"), 0xCBF43926);
+}
+
+#[test]
+fn test_patch_ips() {
+    let rom : Vec<u8> = vec![0x00, 0xE0, 0x12, 0x00];
+    let mut p : Vec<u8> = b"PATCH".to_vec();
+    p.extend_from_slice(&[0x00, 0x00, 0x01, 0x00, 0x02, 0xAA, 0xBB]); /* 2 bytes @ 1 */
+    p.extend_from_slice(&[0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x03, 0xFF]); /* 3 0xff @ 5 */
+    p.extend_from_slice(b"EOF");
+    assert_eq!(detect(&p), Some(Format::Ips));
+    assert_eq!(apply(&rom, &p).unwrap(), vec![0x00, 0xAA, 0xBB, 0x00, 0x00, 0xFF, 0xFF, 0xFF]);
+
+    /* Cut down to size */
+    p.extend_from_slice(&[0x00, 0x00, 0x02]);
+    assert_eq!(apply(&rom, &p).unwrap(), vec![0x00, 0xAA]);
+
+    assert_eq!(apply(&rom, b"PATCH\x00\x00\x01\x00\x05\xAA"), Err(PatchError::Truncated));
+    assert_eq!(apply(&rom, b"PATCH"), Err(PatchError::Truncated));
+    assert_eq!(apply(&rom, b"NOPE"), Err(PatchError::UnknownFormat));
+}
+
+#[test]
+fn test_patch_bps() {
+    let source : &[u8] = b"HELLO WORLD";
+    let target : &[u8] = b"HELLO THERE WORLDWORLD";
+    let mut actions : Vec<u8> = vec![];
+    actions.extend(varint((6 - 1) << 2)); /* source read "HELLO " */
+    actions.extend(varint(((6 - 1) << 2) | 1)); /* target read */
+    actions.extend_from_slice(b"THERE ");
+    actions.extend(varint(((5 - 1) << 2) | 2)); /* source copy "WORLD" */
+    actions.extend(varint(6 << 1));
+    actions.extend(varint(((5 - 1) << 2) | 3)); /* target copy "WORLD" */
+    actions.extend(varint(12 << 1));
+    let p : Vec<u8> = make_bps(source, target, &actions);
+    assert_eq!(detect(&p), Some(Format::Bps));
+    assert_eq!(apply(source, &p).unwrap(), target.to_vec());
+
+    /* Only goes on the rom it was made for */
+    assert_eq!(apply(b"HELLO WORLE", &p), Err(PatchError::WrongRom(crc32(source), crc32(b"HELLO WORLE"))));
+
+    /* Damaged patches get caught */
+    let mut damaged : Vec<u8> = p.clone();
+    damaged[12] ^= 0x01;
+    assert!(matches!(apply(source, &damaged), Err(PatchError::BadChecksum("patch", _, _))));
+
+    /* A patch whose actions don't add up to the target it claims */
+    let wrong : Vec<u8> = make_bps(source, b"HELLO", &varint((6 - 1) << 2));
+    assert!(matches!(apply(source, &wrong), Err(PatchError::BadAction(_))));
+    let wrong : Vec<u8> = make_bps(source, b"HELLO!", &varint((6 - 1) << 2));
+    assert!(matches!(apply(source, &wrong), Err(PatchError::BadChecksum("patched rom", _, _))));
+
+    /* Sizes and lengths too big for a usize, or for the target */
+    let mut huge : Vec<u8> = b"BPS1".to_vec();
+    huge.extend_from_slice(&[0x7f; 10]);
+    huge.push(0x80);
+    huge.extend(crc32(source).to_le_bytes());
+    huge.extend(crc32(target).to_le_bytes());
+    let crc : u32 = crc32(&huge);
+    huge.extend(crc.to_le_bytes());
+    assert!(matches!(apply(source, &huge), Err(PatchError::BadAction(_))));
+    let mut actions : Vec<u8> = varint(0); /* source read "H" */
+    actions.extend(varint(((1 << 40) << 2) | 3)); /* target copy, far too long */
+    actions.extend(varint(0));
+    let wrong : Vec<u8> = make_bps(source, b"HH", &actions);
+    assert!(matches!(apply(source, &wrong), Err(PatchError::BadAction(_))));
+}
@@ -0,0 +1,154 @@
+use super::*;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/* Profiler, for finding out which subroutines eat the frame's budget. It
+ * follows 2NNN calls and 00EE returns to keep its own call stack, and charges
+ * every instruction slot the cpu runs to whatever is running. For each
+ * subroutine it counts
+ *
+ *   calls         times it was called, and who from
+ *   instructions  instructions run in it, not counting the ones it called
+ *   self          cycles spent in it, not counting the subroutines it called
+ *   total         cycles spent in it and everything it called
+ *
+ * A cycle is one instruction slot of a frame, so the speed is the budget.
+ * Slots spent waiting, on DXYN for the 60hz interrupt with display_wait or
+ * on FX0A for a key, cost a cycle without being an instruction. Subroutines
+ * go by their address, the code outside of any subroutine by the address the
+ * program started at.
+ *
+ * The profile writes out as a text report, or as folded stacks: a line for
+ * every chain of calls with the cycles spent at the end of it, which is what
+ * flamegraph.pl and inferno turn into a flame graph */
+
+/* Deepest the call stack is followed, recursing past this with the stack
+ * wrapping forgets the outermost calls */
+pub const MAX_CALLS: usize = stack::MAX_DEPTH;
+
+/* What one subroutine cost */
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq, Default)]
+pub struct Routine {
+    pub calls : u64, /* Times it was called */
+    pub instructions : u64, /* Instructions run in it */
+    pub exclusive : u64, /* Cycles spent in it */
+    pub inclusive : u64, /* Cycles spent in it and the subroutines it called */
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Profiler {
+    pub start : u16, /* Where the program started, stands in for the code outside of subroutines */
+    pub frames : u64, /* Frames run */
+    pub cycles : u64, /* Instruction slots run */
+    pub routines : BTreeMap<u16, Routine>, /* Cost of each subroutine, by address */
+    pub edges : BTreeMap<(u16, u16), u64>, /* Calls from one subroutine to another */
+    stacks : BTreeMap<Vec<u16>, u64>, /* Cycles spent at the end of each chain of calls */
+    calls : Vec<u16>, /* Subroutines running, outermost first, always starts with START */
+}
+
+/* Profiler for a program starting @ START */
+pub fn make_profiler(start : u16) -> Profiler {
+    let p = Profiler {
+        start: start,
+        frames: 0,
+        cycles: 0,
+        routines: BTreeMap::new(),
+        edges: BTreeMap::new(),
+        stacks: BTreeMap::new(),
+        calls: vec![start],
+    };
+    return p;
+}
+
+/* Name of the subroutine @ ADDRESS */
+fn name(address : u16) -> String {
+    return format!("{:#05x}", address);
+}
+
+impl Profiler {
+    /* The program started over, so nothing is running. What was counted stays */
+    pub fn restart(&mut self) {
+        self.calls = vec![self.start];
+    }
+
+    /* Count the instruction OPCODE, or a slot spent waiting on it if WAITED */
+    pub fn step(&mut self, opcode : u16, waited : bool) {
+        self.cycles += 1;
+
+        /* Everything on the stack is running, but recursive subroutines only count once */
+        for (i, address) in self.calls.iter().enumerate() {
+            if !self.calls[..i].contains(address) {
+                self.routines.entry(*address).or_default().inclusive += 1;
+            }
+        }
+        match self.stacks.get_mut(self.calls.as_slice()) {
+            Some(cycles) => *cycles += 1,
+            None => { self.stacks.insert(self.calls.clone(), 1); },
+        }
+        let top : u16 = self.calls[self.calls.len() - 1];
+        let routine : &mut Routine = self.routines.entry(top).or_default();
+        routine.exclusive += 1;
+        if !waited {
+            routine.instructions += 1;
+        }
+
+        if opcode & 0xF000 == 0x2000 {
+            let callee : u16 = opcode & 0x0FFF;
+            *self.edges.entry((top, callee)).or_insert(0) += 1;
+            self.routines.entry(callee).or_default().calls += 1;
+            if self.calls.len() > MAX_CALLS {
+                self.calls.remove(1);
+            }
+            self.calls.push(callee);
+        } else if opcode == 0x00EE && self.calls.len() > 1 {
+            self.calls.pop();
+        }
+    }
+
+    /* The profile as text, the costliest subroutines first */
+    pub fn report(&self) -> String {
+        let mut out : String = String::new();
+        let cycles : f64 = self.cycles.max(1) as f64;
+        let per_frame : f64 = self.frames.max(1) as f64;
+        let _ = writeln!(out, "{} cycles over {} frames, {:.1} a frame", self.cycles, self.frames,
+                         self.cycles as f64 / per_frame);
+        let _ = writeln!(out);
+        let _ = writeln!(out, "{:<10} {:>8} {:>12} {:>18} {:>18} {:>12}",
+                         "subroutine", "calls", "instructions", "self", "total", "total/frame");
+
+        let mut routines : Vec<(&u16, &Routine)> = self.routines.iter().collect();
+        routines.sort_by(|a, b| b.1.inclusive.cmp(&a.1.inclusive).then(b.1.exclusive.cmp(&a.1.exclusive)).then(a.0.cmp(b.0)));
+        for (address, r) in routines {
+            let _ = writeln!(out, "{:<10} {:>8} {:>12} {:>10} {:>6.1}% {:>10} {:>6.1}% {:>12.1}",
+                             name(*address), r.calls, r.instructions,
+                             r.exclusive, 100.0 * r.exclusive as f64 / cycles,
+                             r.inclusive, 100.0 * r.inclusive as f64 / cycles,
+                             r.inclusive as f64 / per_frame);
+        }
+
+        /* Call graph, the busiest calls first */
+        let _ = writeln!(out);
+        let _ = writeln!(out, "{:<10}    {:<10} {:>8}", "caller", "callee", "calls");
+        let mut edges : Vec<(&(u16, u16), &u64)> = self.edges.iter().collect();
+        edges.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        for ((caller, callee), calls) in edges {
+            let _ = writeln!(out, "{:<10} -> {:<10} {:>8}", name(*caller), name(*callee), calls);
+        }
+        return out;
+    }
+
+    /* The profile as folded stacks, "outer;inner cycles" a line */
+    pub fn folded(&self) -> String {
+        let mut out : String = String::new();
+        for (calls, cycles) in self.stacks.iter() {
+            let names : Vec<String> = calls.iter().map(|address| name(*address)).collect();
+            let _ = writeln!(out, "{} {}", names.join(";"), cycles);
+        }
+        return out;
+    }
+}
+
+#[cfg(test)]
+mod test;
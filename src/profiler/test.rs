@@ -0,0 +1,76 @@
+use super::*;
+
+#[test]
+fn test_profiler_program() {
+    let mut mem : memory::Mem = memory::make_memory();
+    mem.store_game(vec![
+        0x22, 0x06, /* 0x200 call 0x206 */
+        0x22, 0x06, /* 0x202 call 0x206 */
+        0x12, 0x04, /* 0x204 jump to itself */
+        0x22, 0x0C, /* 0x206 call 0x20c */
+        0x00, 0xEE, /* 0x208 return */
+        0x00, 0x00,
+        0x60, 0x01, /* 0x20c v0 = 1 */
+        0x00, 0xEE, /* 0x20e return */
+    ]);
+    let mut c : cpu::CPU = cpu::make_cpu(mem);
+    c.profiler = Some(make_profiler(c.pc));
+    assert!(c.run_frame(20, 0xf0));
+
+    let p : &Profiler = c.profiler.as_ref().unwrap();
+    assert_eq!((p.frames, p.cycles), (1, 20));
+    assert_eq!(p.routines[&0x200], Routine { calls: 0, instructions: 12, exclusive: 12, inclusive: 20 });
+    assert_eq!(p.routines[&0x206], Routine { calls: 2, instructions: 4, exclusive: 4, inclusive: 8 });
+    assert_eq!(p.routines[&0x20C], Routine { calls: 2, instructions: 4, exclusive: 4, inclusive: 4 });
+    assert_eq!(p.edges.iter().collect::<Vec<_>>(), vec![(&(0x200, 0x206), &2), (&(0x206, 0x20C), &2)]);
+    assert_eq!(p.folded(), "0x200 12\n0x200;0x206 4\n0x200;0x206;0x20c 4\n");
+
+    /* Costliest first, then the calls */
+    let report : String = p.report();
+    let lines : Vec<&str> = report.lines().collect();
+    assert_eq!(lines[0], "20 cycles over 1 frames, 20.0 a frame");
+    assert!(lines[3].starts_with("0x200 "));
+    assert!(lines[4].starts_with("0x206 "));
+    assert!(lines[4].ends_with("4   20.0%          8   40.0%          8.0"));
+    assert!(lines[5].starts_with("0x20c "));
+    assert_eq!(lines[8], "0x200      -> 0x206             2");
+
+    /* Waiting on a key costs cycles, but doesn't run anything */
+    let mut mem : memory::Mem = memory::make_memory();
+    mem.store_game(vec![0xF0, 0x0A]);
+    let mut c : cpu::CPU = cpu::make_cpu(mem);
+    c.profiler = Some(make_profiler(c.pc));
+    assert!(c.run_frame(5, 0xf0));
+    assert_eq!(c.profiler.unwrap().routines[&0x200], Routine { calls: 0, instructions: 0, exclusive: 5, inclusive: 5 });
+
+    /* So does drawing again before the next 60hz interrupt, with display_wait */
+    let mut mem : memory::Mem = memory::make_memory();
+    mem.store_game(vec![0xD0, 0x15, 0x12, 0x00]);
+    let mut c : cpu::CPU = cpu::make_cpu(mem);
+    c.quirks.display_wait = true;
+    c.profiler = Some(make_profiler(c.pc));
+    assert!(c.run_frame(5, 0xf0));
+    assert_eq!(c.profiler.unwrap().routines[&0x200], Routine { calls: 0, instructions: 2, exclusive: 5, inclusive: 5 });
+}
+
+#[test]
+fn test_profiler_recursion() {
+    let mut p : Profiler = make_profiler(0x200);
+    p.step(0x2300, false);
+    p.step(0x2300, false); /* Calls itself, from its first instruction */
+    p.step(0x00EE, false);
+    assert_eq!(p.routines[&0x300], Routine { calls: 2, instructions: 2, exclusive: 2, inclusive: 2 });
+
+    /* Returning with nothing to return to stays put */
+    p.step(0x00EE, false);
+    p.step(0x00EE, false);
+    assert_eq!(p.routines[&0x200], Routine { calls: 0, instructions: 2, exclusive: 2, inclusive: 5 });
+
+    /* Starting over forgets the calls, not the counts */
+    p.step(0x2300, false);
+    p.restart();
+    p.step(0x6001, false);
+    p.step(0xD015, true);
+    assert_eq!(p.routines[&0x200], Routine { calls: 0, instructions: 4, exclusive: 5, inclusive: 8 });
+    assert_eq!(p.folded(), "0x200 5\n0x200;0x300 2\n0x200;0x300;0x300 1\n");
+}
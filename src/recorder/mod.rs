@@ -0,0 +1,160 @@
+use super::*;
+use std::io::{ self, Write };
+use std::path::Path;
+
+/* Records every frame of the chip8 screen, either into an animated gif or
+ * into a y4m video (raw frames, any video tool can convert them). Frames
+ * are 1/60th of a second apart, capture once per frame the scheduler runs */
+
+/* Where the frames are going */
+pub enum Output<W: Write> {
+    Gif(gif::Encoder<W>), /* Indexed by filter intensity => 256 colour palette */
+    Y4m(W), /* YCbCr 4:4:4, header then "FRAME\n" + planes per frame */
+}
+
+pub struct Recorder<W: Write> {
+    output : Output<W>, /* Encoder for the file */
+    scale : u32, /* Every chip8 pixel is SCALE x SCALE pixels */
+    palette : display::Palette, /* Colours at the time the recording started */
+    pub frames : u64, /* Number of frames recorded so far, gifs leave some out */
+}
+
+/* Width and height of a recording at SCALE */
+fn frame_size(scale : u32) -> (u32, u32) {
+    return (64 * scale, 32 * scale);
+}
+
+/* Start a gif recording into OUT */
+pub fn make_gif_recorder<W: Write>(out : W, palette : display::Palette,
+                                   scale : u32) -> io::Result<Recorder<W>> {
+    let scale : u32 = if scale == 0 { 1 } else { scale };
+    let (width, height) = frame_size(scale);
+
+    /* Palette index == filter intensity, so fading pixels keep their colour */
+    let mut colours : Vec<u8> = Vec::with_capacity(256 * 3);
+    for level in 0..=0xff {
+        colours.extend_from_slice(&palette.mix(level));
+    }
+
+    let mut encoder = match gif::Encoder::new(out, width as u16, height as u16, &colours) {
+        Ok(encoder) => encoder,
+        Err(err) => return Err(io::Error::other(err)),
+    };
+    if let Err(err) = encoder.set_repeat(gif::Repeat::Infinite) {
+        return Err(io::Error::other(err));
+    }
+
+    let r = Recorder { output: Output::Gif(encoder), scale: scale, palette: palette, frames: 0 };
+    return Ok(r);
+}
+
+/* Start a y4m recording into OUT */
+pub fn make_y4m_recorder<W: Write>(mut out : W, palette : display::Palette,
+                                   scale : u32) -> io::Result<Recorder<W>> {
+    let scale : u32 = if scale == 0 { 1 } else { scale };
+    let (width, height) = frame_size(scale);
+    writeln!(out, "YUV4MPEG2 W{} H{} F60:1 Ip A1:1 C444", width, height)?;
+
+    let r = Recorder { output: Output::Y4m(out), scale: scale, palette: palette, frames: 0 };
+    return Ok(r);
+}
+
+/* Start recording into the file @ PATH. .y4m files get y4m, everything else is a gif */
+pub fn start(path : &Path, palette : display::Palette,
+             scale : u32) -> io::Result<Recorder<io::BufWriter<fs::File>>> {
+    let out = io::BufWriter::new(fs::File::create(path)?);
+    let is_y4m = match path.extension() {
+        Some(ext) => ext.eq_ignore_ascii_case("y4m"),
+        None => false,
+    };
+
+    if is_y4m {
+        return make_y4m_recorder(out, palette, scale);
+    }
+    return make_gif_recorder(out, palette, scale);
+}
+
+/* Gif delays are in 1/100ths of a second, so 60hz can't be hit exactly.
+ * Frames would take 2, 1, 2, 2, 1, 2... to keep the total time right to
+ * within a 1/100th, but viewers show a delay of 1 as 10. So a frame that
+ * would get 1 is left out, None, and the frame after it shows for 3 */
+pub fn gif_delay(frame : u64) -> Option<u16> {
+    let time = |frame : u64| (frame * 100 + 30) / 60;
+    let delay : u64 = time(frame + 1) - time(frame);
+    if delay < 2 {
+        return None;
+    }
+    if frame > 0 && time(frame) - time(frame - 1) < 2 {
+        return Some((time(frame + 1) - time(frame - 1)) as u16);
+    }
+    return Some(delay as u16);
+}
+
+/* Convert RGB to YCbCr (bt.601, studio range) */
+fn rgb_to_ycbcr(rgb : [u8; 3]) -> [u8; 3] {
+    let r : i32 = rgb[0] as i32;
+    let g : i32 = rgb[1] as i32;
+    let b : i32 = rgb[2] as i32;
+    let y : i32 = ((66 * r + 129 * g + 25 * b + 128) >> 8) + 16;
+    let cb : i32 = ((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128;
+    let cr : i32 = ((112 * r - 94 * g - 18 * b + 128) >> 8) + 128;
+    return [y as u8, cb as u8, cr as u8];
+}
+
+impl<W: Write> Recorder<W> {
+    /* Record the screen of CORE, as drawn by WORLD, as the next frame */
+    pub fn capture<B : Bus>(&mut self, world : &cpu::World, core : &cpu::CPU<B>) -> io::Result<()> {
+        let (width, height) = frame_size(self.scale);
+
+        /* Intensity of every pixel in the frame, scaled up */
+        let mut levels : Vec<u8> = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                levels.push(world.filter.pixel(&core.display, (x / self.scale) as usize,
+                                               (y / self.scale) as usize));
+            }
+        }
+
+        match &mut self.output {
+            Output::Gif(encoder) => {
+                if let Some(delay) = gif_delay(self.frames) {
+                    let frame = gif::Frame {
+                        width: width as u16,
+                        height: height as u16,
+                        delay: delay,
+                        buffer: std::borrow::Cow::Owned(levels),
+                        ..gif::Frame::default()
+                    };
+                    if let Err(err) = encoder.write_frame(&frame) {
+                        return Err(io::Error::other(err));
+                    }
+                }
+            },
+            Output::Y4m(out) => {
+                let mut planes : [Vec<u8>; 3] = [vec![], vec![], vec![]];
+                for level in levels {
+                    let ycbcr : [u8; 3] = rgb_to_ycbcr(self.palette.mix(level));
+                    for i in 0..3 { planes[i].push(ycbcr[i]); }
+                }
+                out.write_all(b"FRAME\n")?;
+                for plane in planes.iter() { out.write_all(plane)?; }
+            },
+        }
+        self.frames += 1;
+        return Ok(());
+    }
+
+    /* Stop recording, and finish off the file. Returns the writer */
+    pub fn finish(self) -> io::Result<W> {
+        match self.output {
+            Output::Gif(encoder) => return encoder.into_inner(),
+            Output::Y4m(mut out) => {
+                out.flush()?;
+                return Ok(out);
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
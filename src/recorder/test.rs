@@ -0,0 +1,65 @@
+use super::*;
+
+#[test]
+fn test_recorder_gif_delay() {
+    /* Every 3 frames make 5/100ths of a second, one of them left out */
+    let delays : Vec<Option<u16>> = (0..6).map(gif_delay).collect();
+    assert_eq!(delays, vec![Some(2), None, Some(3), Some(2), None, Some(3)]);
+
+    /* Never below 2, and 60 frames => 1 second */
+    assert!((0..600).filter_map(gif_delay).all(|d| d >= 2));
+    assert_eq!((0..60).filter_map(gif_delay).sum::<u16>(), 100);
+}
+
+#[test]
+fn test_recorder_y4m() {
+    let mem: memory::Mem = memory::make_memory();
+    let mut c : cpu::CPU = cpu::make_cpu(mem);
+    let world : cpu::World = cpu::World::new();
+
+    let mut r = make_y4m_recorder(vec![], display::make_palette(), 1).unwrap();
+    r.capture(&world, &c).unwrap();
+    c.display.screen[0][0] = true;
+    r.capture(&world, &c).unwrap();
+    assert_eq!(r.frames, 2);
+
+    let out : Vec<u8> = r.finish().unwrap();
+    let header : &[u8] = b"YUV4MPEG2 W64 H32 F60:1 Ip A1:1 C444\n";
+    let frame_len : usize = 6 + 64 * 32 * 3;
+    assert!(out.starts_with(header));
+    assert_eq!(out.len(), header.len() + 2 * frame_len);
+
+    /* Black then white for the first pixel's luma */
+    assert_eq!(out[header.len() + 6], 16);
+    assert_eq!(out[header.len() + frame_len + 6], 235);
+}
+
+#[test]
+fn test_recorder_gif() {
+    let mem: memory::Mem = memory::make_memory();
+    let mut c : cpu::CPU = cpu::make_cpu(mem);
+    let world : cpu::World = cpu::World::new();
+
+    let mut r = make_gif_recorder(vec![], display::make_palette(), 2).unwrap();
+    c.display.screen[0][1] = true;
+    for _ in 0..3 {
+        r.capture(&world, &c).unwrap();
+    }
+    assert_eq!(r.frames, 3);
+    let out : Vec<u8> = r.finish().unwrap();
+
+    /* Decode it back */
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::Indexed);
+    let mut decoder = options.read_info(&out[..]).unwrap();
+    let mut frames : usize = 0;
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        assert_eq!(frame.width, 128);
+        assert_eq!(frame.height, 64);
+        assert_eq!(frame.buffer[0], 0x00);
+        assert_eq!(frame.buffer[2], 0xff);
+        assert!(frame.delay >= 2);
+        frames += 1;
+    }
+    assert_eq!(frames, 2);
+}
@@ -0,0 +1,180 @@
+use super::*;
+use std::fmt;
+use std::io::{self, Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::time::{ Duration, Instant, SystemTime };
+
+/* Helpers for rom files. Roms can come as the raw program (.ch8, .sc8,
+ * .xo8, whatever it's named), zipped up, or inside an Octo cartridge gif.
+ * Which one it is gets worked out from the bytes, not the file name */
+
+/* Room for the program in MEMORY_SIZE bytes of memory, from 0x200 to the end */
+pub const fn program_space(memory_size : usize) -> usize {
+    return memory_size - memory::_PROG_START;
+}
+
+/* Extensions of rom files, for picking the rom out of a zip */
+pub const ROM_EXTENSIONS: [&str; 4] = ["ch8", "c8", "sc8", "xo8"];
+
+/* How the rom was packaged */
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Raw,
+    Zip,
+    Cartridge, /* Octo cartridge gif */
+}
+
+/* A loaded rom */
+pub struct Rom {
+    pub data : Vec<u8>, /* The program */
+    pub format : Format, /* What it came packaged as */
+    pub settings : config::Settings, /* Options that came with it, cartridges have some */
+}
+
+/* Things that can go wrong loading a rom */
+#[derive(Debug)]
+pub enum RomError {
+    Io(io::Error), /* Couldn't read the file */
+    Empty, /* Nothing to run */
+    TooBig(usize, usize), /* Size, room there is */
+    Zip(String), /* Bad zip, or no rom in it */
+    Cartridge(cartridge::CartridgeError),
+}
+
+impl fmt::Display for RomError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RomError::Io(err) => write!(f, "couldn't read rom: {}", err),
+            RomError::Empty => write!(f, "rom is empty"),
+            RomError::TooBig(size, max) =>
+                write!(f, "rom is {} bytes, but there's only room for {}", size, max),
+            RomError::Zip(what) => write!(f, "bad zip: {}", what),
+            RomError::Cartridge(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+/* How often the watcher looks at the file */
+pub const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/* Watches a rom file for changes, by polling its modified time */
+pub struct Watcher {
+    pub path : PathBuf, /* File being watched */
+    modified : Option<SystemTime>, /* Modified time the last time we looked */
+    checked : Option<Instant>, /* When we last looked */
+}
+
+/* SHA-1 of DATA as lowercase hex. Roms get identified by this, since file
+ * names change but the bytes don't */
+pub fn sha1_hex(data : &[u8]) -> String {
+    return sha1_smol::Sha1::from(data).digest().to_string();
+}
+
+/* Work out how DATA is packaged */
+pub fn detect(data : &[u8]) -> Format {
+    if data.starts_with(b"PK\x03\x04") {
+        return Format::Zip;
+    }
+    if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        return Format::Cartridge;
+    }
+    return Format::Raw;
+}
+
+/* Pull the rom out of the zip in DATA. That's the file with a rom
+ * extension, or the only file if there's just one */
+fn unzip(data : &[u8]) -> Result<Vec<u8>, RomError> {
+    let zip_err = |err : zip::result::ZipError| RomError::Zip(err.to_string());
+    let mut archive = zip::ZipArchive::new(Cursor::new(data)).map_err(zip_err)?;
+
+    let files : Vec<String> = archive.file_names()
+        .filter(|name| !name.ends_with('/'))
+        .map(|name| name.to_string())
+        .collect();
+    let is_rom = |name : &String| -> bool {
+        let ext : String = Path::new(name).extension().map_or(String::new(), |e| e.to_string_lossy().to_lowercase());
+        return ROM_EXTENSIONS.contains(&ext.as_str());
+    };
+    let roms : Vec<&String> = files.iter().filter(|name| is_rom(name)).collect();
+    let name : &String = match (roms.as_slice(), files.as_slice()) {
+        ([rom], _) => rom,
+        ([], [only]) => only,
+        ([], _) => return Err(RomError::Zip(String::from("no rom in it"))),
+        _ => return Err(RomError::Zip(format!("more than one rom in it: {}", files.join(", ")))),
+    };
+
+    let mut out : Vec<u8> = vec![];
+    let mut file = archive.by_name(name).map_err(zip_err)?;
+    file.read_to_end(&mut out).map_err(RomError::Io)?;
+    return Ok(out);
+}
+
+/* Load a rom out of DATA, whatever it's packaged in. It has to fit in MAX_SIZE bytes */
+pub fn load_bytes(data : &[u8], max_size : usize) -> Result<Rom, RomError> {
+    let format : Format = detect(data);
+    let mut settings : config::Settings = config::make_settings();
+    let mut max_size : usize = max_size;
+    let program : Vec<u8> = match format {
+        Format::Raw => data.to_vec(),
+        Format::Zip => unzip(data)?,
+        Format::Cartridge => {
+            let payload : cartridge::Payload = cartridge::decode(data).map_err(RomError::Cartridge)?;
+            settings = payload.options.settings();
+            max_size = max_size.min(payload.options.max_size.unwrap_or(max_size));
+            cartridge::assemble(&payload.program).map_err(RomError::Cartridge)?
+        },
+    };
+
+    if program.is_empty() {
+        return Err(RomError::Empty);
+    }
+    if program.len() > max_size {
+        return Err(RomError::TooBig(program.len(), max_size));
+    }
+    return Ok(Rom { data: program, format: format, settings: settings });
+}
+
+/* Load the rom @ PATH. It has to fit in MAX_SIZE bytes */
+pub fn load(path : &Path, max_size : usize) -> Result<Rom, RomError> {
+    let data : Vec<u8> = fs::read(path).map_err(RomError::Io)?;
+    return load_bytes(&data, max_size);
+}
+
+/* Start watching the file @ PATH */
+pub fn make_watcher(path : PathBuf) -> Watcher {
+    let mut w = Watcher {
+        path: path,
+        modified: None,
+        checked: None,
+    };
+    w.modified = w.modified_time();
+    return w;
+}
+
+impl Watcher {
+    /* Modified time of the file, None if it can't be read right now */
+    fn modified_time(&self) -> Option<SystemTime> {
+        return fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+    }
+
+    /* True if the file changed since the last time this said so. Only looks
+     * at the file every WATCH_INTERVAL, so it is fine to call every frame */
+    pub fn changed(&mut self) -> bool {
+        if self.checked.is_some_and(|c| c.elapsed() < WATCH_INTERVAL) {
+            return false;
+        }
+        self.checked = Some(Instant::now());
+
+        /* Files being rewritten can go missing for a moment, wait for them to come back */
+        let modified : Option<SystemTime> = self.modified_time();
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        return true;
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,100 @@
+use super::*;
+
+const PROGRAM_SPACE: usize = program_space(bus::CHIP8_SIZE);
+
+#[test]
+fn test_rom_sha1() {
+    assert_eq!(sha1_hex(b""), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+    assert_eq!(sha1_hex(b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+}
+
+#[test]
+fn test_rom_watcher() {
+    let path : PathBuf = std::env::temp_dir().join(format!("r_chip8_watch_{}.ch8", std::process::id()));
+    fs::write(&path, [0x12, 0x00]).unwrap();
+    let mut w : Watcher = make_watcher(path.clone());
+    assert!(!w.changed());
+
+    /* Move the modified time along instead of sleeping for the file system to notice */
+    let later : SystemTime = SystemTime::now() + Duration::from_secs(10);
+    fs::File::options().write(true).open(&path).unwrap().set_modified(later).unwrap();
+    assert!(!w.changed()); /* Too soon to look again */
+    w.checked = None;
+    assert!(w.changed());
+    w.checked = None;
+    assert!(!w.changed());
+
+    fs::remove_file(&path).unwrap();
+    w.checked = None;
+    assert!(!w.changed());
+}
+
+#[test]
+fn test_rom_detect() {
+    assert_eq!(detect(&[0x12, 0x00]), Format::Raw);
+    assert_eq!(detect(b""), Format::Raw);
+    assert_eq!(detect(b"PK\x03\x04rest"), Format::Zip);
+    assert_eq!(detect(b"GIF89a..."), Format::Cartridge);
+    assert_eq!(detect(b"GIF87a..."), Format::Cartridge);
+}
+
+/* A zip with FILES in it */
+fn make_zip(files : &[(&str, &[u8])]) -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(Cursor::new(vec![]));
+    for (name, data) in files.iter() {
+        zip.start_file(*name, zip::write::FileOptions::default()).unwrap();
+        io::Write::write_all(&mut zip, data).unwrap();
+    }
+    return zip.finish().unwrap().into_inner();
+}
+
+#[test]
+fn test_rom_load_bytes() {
+    let r : Rom = load_bytes(&[0x12, 0x00], PROGRAM_SPACE).unwrap();
+    assert_eq!(r.data, vec![0x12, 0x00]);
+    assert_eq!(r.format, Format::Raw);
+
+    assert!(matches!(load_bytes(&[], PROGRAM_SPACE), Err(RomError::Empty)));
+    assert!(load_bytes(&[0; PROGRAM_SPACE], PROGRAM_SPACE).is_ok());
+    assert!(matches!(load_bytes(&[0; PROGRAM_SPACE + 1], PROGRAM_SPACE),
+                     Err(RomError::TooBig(3585, 3584))));
+}
+
+#[test]
+fn test_rom_load_zip() {
+    /* The rom is picked out by its extension */
+    let zip : Vec<u8> = make_zip(&[("readme.txt", b"hi"), ("pong.CH8", &[0x12, 0x00])]);
+    let r : Rom = load_bytes(&zip, PROGRAM_SPACE).unwrap();
+    assert_eq!(r.data, vec![0x12, 0x00]);
+    assert_eq!(r.format, Format::Zip);
+
+    /* Or it's the only thing in there */
+    let zip : Vec<u8> = make_zip(&[("pong", &[0x12, 0x02])]);
+    assert_eq!(load_bytes(&zip, PROGRAM_SPACE).unwrap().data, vec![0x12, 0x02]);
+
+    let zip : Vec<u8> = make_zip(&[("a.txt", b"a"), ("b.txt", b"b")]);
+    assert!(matches!(load_bytes(&zip, PROGRAM_SPACE), Err(RomError::Zip(_))));
+    let zip : Vec<u8> = make_zip(&[("a.ch8", b"a"), ("b.sc8", b"b")]);
+    assert!(matches!(load_bytes(&zip, PROGRAM_SPACE), Err(RomError::Zip(_))));
+    let zip : Vec<u8> = make_zip(&[("big.ch8", &[0; PROGRAM_SPACE + 1])]);
+    assert!(matches!(load_bytes(&zip, PROGRAM_SPACE), Err(RomError::TooBig(_, _))));
+    assert!(matches!(load_bytes(b"PK\x03\x04junk", PROGRAM_SPACE), Err(RomError::Zip(_))));
+}
+
+#[test]
+fn test_rom_load_cartridge() {
+    let mut settings : config::Settings = config::make_settings();
+    settings.speed = Some(20);
+    let gif : Vec<u8> = cartridge::encode(&[0x12, 0x00], cartridge::make_options(&settings)).unwrap();
+    let r : Rom = load_bytes(&gif, PROGRAM_SPACE).unwrap();
+    assert_eq!(r.data, vec![0x12, 0x00]);
+    assert_eq!(r.format, Format::Cartridge);
+    assert_eq!(r.settings.speed, Some(20));
+    assert_eq!(r.settings.quirks, Some(cpu::make_quirks()));
+
+    /* The cartridge can say there's less room than we have */
+    let mut options : cartridge::Options = cartridge::make_options(&settings);
+    options.max_size = Some(1);
+    let gif : Vec<u8> = cartridge::encode(&[0x12, 0x00], options).unwrap();
+    assert!(matches!(load_bytes(&gif, PROGRAM_SPACE), Err(RomError::TooBig(2, 1))));
+}
@@ -0,0 +1,134 @@
+use super::*;
+use std::time::{ Duration, Instant };
+
+/* Frame scheduler. Runs the cpu in 60hz frames, a frames worth of
+ * instructions at a time, and keeps the frames lined up with the clock
+ * on the wall. Timers and the vblank quirk all hang off of the frames.
+ * Can also be paused, stepped a frame at a time, sped up or slowed down,
+ * or let loose to fast forward */
+
+/* Frames per second, same rate as the timers */
+pub const FRAME_RATE: u64 = 60;
+
+/* Instructions per frame. 12 * 60 => ~700 instructions / second */
+pub const DEFAULT_IPF: u16 = 12;
+
+/* Slowest and fastest the speed can be set to */
+pub const MIN_IPF: u16 = 1;
+pub const MAX_IPF: u16 = 10000;
+
+/* How long one frame is on the wall clock */
+pub const FRAME_TIME: Duration = Duration::from_micros(1_000_000 / FRAME_RATE);
+
+/* If we fall further behind than this, drop frames instead of rushing to catch up */
+pub const MAX_CATCH_UP: u64 = 4;
+
+pub struct Scheduler {
+    started : Instant, /* When the first frame started */
+    pub frames : u64, /* Number of frames run so far */
+    pub ipf : u16, /* Instructions to execute each frame */
+    pub paused : bool, /* Only run frames that were stepped */
+    pub fast_forward : bool, /* Run as fast as we can */
+    steps : u64, /* Frames stepped while paused, that haven't run yet */
+    offset : i64, /* Frames to add to the wall clock, for time spent paused or fast forwarding */
+    resync : bool, /* Line the wall clock back up with the frames before the next frame */
+}
+
+pub fn make_scheduler(ipf : u16) -> Scheduler {
+    let s = Scheduler {
+        started: Instant::now(),
+        frames: 0,
+        ipf: ipf,
+        paused: false,
+        fast_forward: false,
+        steps: 0,
+        offset: 0,
+        resync: false,
+    };
+    return s;
+}
+
+impl Scheduler {
+    /* Number of frames that need to run to catch up, ELAPSED time after we started.
+     * When fast forwarding there is no limit, run frames until FRAME_TIME is up */
+    pub fn frames_due_at(&mut self, elapsed : Duration) -> u64 {
+        let wall : i64 = (elapsed.as_micros() * FRAME_RATE as u128 / 1_000_000) as i64;
+
+        /* The wall clock doesn't count while paused or fast forwarding */
+        if self.paused {
+            self.resync = true;
+            let steps : u64 = self.steps;
+            self.steps = 0;
+            return steps;
+        }
+        if self.fast_forward {
+            self.resync = true;
+            return u64::MAX;
+        }
+        if self.resync {
+            self.offset = self.frames as i64 - wall;
+            self.resync = false;
+        }
+
+        let should_have : u64 = (wall + self.offset).max(0) as u64;
+        let due : u64 = should_have.saturating_sub(self.frames);
+
+        /* Too far behind (window dragged, laptop asleep...), skip ahead */
+        if due > MAX_CATCH_UP {
+            self.frames = should_have - MAX_CATCH_UP;
+            return MAX_CATCH_UP;
+        }
+        return due;
+    }
+
+    /* Number of frames that need to run right now */
+    pub fn frames_due(&mut self) -> u64 {
+        let elapsed : Duration = self.started.elapsed();
+        return self.frames_due_at(elapsed);
+    }
+
+    /* Pause, or carry on from where it was paused */
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.steps = 0;
+    }
+
+    /* Run just one more frame, pausing if it wasn't already */
+    pub fn step(&mut self) {
+        if !self.paused {
+            self.paused = true;
+            self.steps = 0;
+        }
+        self.steps += 1;
+    }
+
+    /* Half as many instructions per frame */
+    pub fn slower(&mut self) {
+        self.ipf = (self.ipf / 2).max(MIN_IPF);
+    }
+
+    /* Twice as many instructions per frame */
+    pub fn faster(&mut self) {
+        self.ipf = self.ipf.saturating_mul(2).min(MAX_IPF);
+    }
+
+    /* Speed and state, for showing in the title bar */
+    pub fn status(&self) -> String {
+        let mut out : String = format!("{} ipf ({} / s)", self.ipf, self.ipf as u64 * FRAME_RATE);
+        if self.paused {
+            out.push_str(", paused");
+        } else if self.fast_forward {
+            out.push_str(", fast forward");
+        }
+        return out;
+    }
+
+    /* Run the next frame on CORE. Returns false if the program exited */
+    pub fn run_frame<B : bus::Bus>(&mut self, core : &mut cpu::CPU<B>, input_key : u8) -> bool {
+        self.frames += 1;
+        return core.run_frame(self.ipf, input_key);
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,101 @@
+use super::*;
+
+#[test]
+fn test_scheduler_frames_due() {
+    let mem: memory::Mem = memory::make_memory();
+    let mut c : cpu::CPU = cpu::make_cpu(mem);
+    let mut s : Scheduler = make_scheduler(DEFAULT_IPF);
+
+    assert_eq!(s.frames_due_at(Duration::from_millis(0)), 0);
+    assert_eq!(s.frames_due_at(Duration::from_millis(20)), 1);
+    s.run_frame(&mut c, 0xf0);
+    assert_eq!(s.frames_due_at(Duration::from_millis(20)), 0);
+    assert_eq!(s.frames_due_at(Duration::from_millis(50)), 2);
+    s.run_frame(&mut c, 0xf0);
+    s.run_frame(&mut c, 0xf0);
+    assert_eq!(s.frames, 3);
+}
+
+#[test]
+fn test_scheduler_catch_up() {
+    let mut s : Scheduler = make_scheduler(DEFAULT_IPF);
+
+    /* A whole second behind only runs a few frames, and drops the rest */
+    assert_eq!(s.frames_due_at(Duration::from_secs(1)), MAX_CATCH_UP);
+    assert_eq!(s.frames, FRAME_RATE - MAX_CATCH_UP);
+}
+
+#[test]
+fn test_scheduler_ipf() {
+    let mem: memory::Mem = memory::make_memory();
+    let mut c : cpu::CPU = cpu::make_cpu(mem);
+    c.mem.write16((c.pc).into(), [0x12, 0x02]).unwrap(); // Jump to the add
+    c.mem.write16((c.pc + 2).into(), [0x70, 0x01]).unwrap(); // Add 1 to reg 0
+    c.mem.write16((c.pc + 4).into(), [0x12, 0x02]).unwrap(); // Jump back to the add
+    let mut s : Scheduler = make_scheduler(10);
+
+    assert!(s.run_frame(&mut c, 0xf0));
+    assert_eq!(c.regs[0], 5); /* Half of the instructions are the jump back */
+}
+
+#[test]
+fn test_scheduler_pause_and_step() {
+    let mem: memory::Mem = memory::make_memory();
+    let mut c : cpu::CPU = cpu::make_cpu(mem);
+    let mut s : Scheduler = make_scheduler(DEFAULT_IPF);
+
+    s.toggle_pause();
+    assert_eq!(s.frames_due_at(Duration::from_millis(500)), 0);
+    s.step();
+    s.step();
+    assert_eq!(s.frames_due_at(Duration::from_millis(500)), 2);
+    s.run_frame(&mut c, 0xf0);
+    s.run_frame(&mut c, 0xf0);
+    assert_eq!(s.frames_due_at(Duration::from_millis(600)), 0);
+
+    /* Carries on from where it paused, without catching up on the time paused */
+    s.toggle_pause();
+    assert_eq!(s.frames_due_at(Duration::from_millis(1000)), 0);
+    assert_eq!(s.frames_due_at(Duration::from_millis(1020)), 1);
+
+    /* Stepping while running pauses */
+    s.step();
+    assert!(s.paused);
+    assert_eq!(s.frames_due_at(Duration::from_millis(2000)), 1);
+}
+
+#[test]
+fn test_scheduler_fast_forward() {
+    let mem: memory::Mem = memory::make_memory();
+    let mut c : cpu::CPU = cpu::make_cpu(mem);
+    let mut s : Scheduler = make_scheduler(DEFAULT_IPF);
+
+    s.fast_forward = true;
+    assert_eq!(s.frames_due_at(Duration::from_millis(10)), u64::MAX);
+    for _ in 0..100 {
+        s.run_frame(&mut c, 0xf0);
+    }
+    assert!(s.status().ends_with("fast forward"));
+
+    /* Back to normal speed from where fast forward got to */
+    s.fast_forward = false;
+    assert_eq!(s.frames_due_at(Duration::from_millis(100)), 0);
+    assert_eq!(s.frames_due_at(Duration::from_millis(120)), 1);
+    assert_eq!(s.frames, 100);
+}
+
+#[test]
+fn test_scheduler_speed() {
+    let mut s : Scheduler = make_scheduler(DEFAULT_IPF);
+    s.faster();
+    assert_eq!(s.ipf, 24);
+    assert_eq!(s.status(), "24 ipf (1440 / s)");
+    for _ in 0..10 {
+        s.slower();
+    }
+    assert_eq!(s.ipf, MIN_IPF);
+    for _ in 0..20 {
+        s.faster();
+    }
+    assert_eq!(s.ipf, MAX_IPF);
+}
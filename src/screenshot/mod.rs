@@ -0,0 +1,68 @@
+use super::*;
+use std::io::{ self, Write };
+use std::path::{ Path, PathBuf };
+
+/* Screenshots of the chip8 screen as png files. Works off of the CPU and
+ * World only, so the window and headless runs both go through here */
+
+/* Encode the screen of CORE as a png into OUT. Every chip8 pixel becomes a
+ * SCALE x SCALE block, coloured with the filter and palette from WORLD */
+pub fn encode_png<W: Write, B : Bus>(out : W, world : &cpu::World, core : &cpu::CPU<B>,
+                            scale : u32) -> Result<(), png::EncodingError> {
+    let scale : u32 = if scale == 0 { 1 } else { scale };
+    let width : u32 = core.display.screen[0].len() as u32 * scale;
+    let height : u32 = core.display.screen.len() as u32 * scale;
+
+    /* Build up the image, row by row */
+    let mut data : Vec<u8> = Vec::with_capacity((width * height * 3) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let rgb : [u8; 3] = world.pixel_rgb(core, (x / scale) as usize,
+                                                (y / scale) as usize);
+            data.extend_from_slice(&rgb);
+        }
+    }
+
+    let mut encoder = png::Encoder::new(out, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)?;
+    return Ok(());
+}
+
+/* Current local time, for naming files */
+pub fn timestamp() -> String {
+    return chrono::Local::now().format("%Y%m%d-%H%M%S%.3f").to_string();
+}
+
+/* Name for a capture of ROM_PATH taken at TIMESTAMP => {rom}-{timestamp}.{ext} */
+pub fn file_name(rom_path : &str, timestamp : &str, ext : &str) -> String {
+    let rom : String = match Path::new(rom_path).file_stem() {
+        Some(stem) => stem.to_string_lossy().to_string(),
+        None => String::from("chip8"),
+    };
+    return format!("{}-{}.{}", rom, timestamp, ext);
+}
+
+/* Write a screenshot of CORE to the file @ PATH */
+pub fn write_png<B : Bus>(path : &Path, world : &cpu::World, core : &cpu::CPU<B>, scale : u32) -> io::Result<()> {
+    let file = fs::File::create(path)?;
+    let writer = io::BufWriter::new(file);
+    if let Err(err) = encode_png(writer, world, core, scale) {
+        return Err(io::Error::other(err));
+    }
+    return Ok(());
+}
+
+/* Save a screenshot of CORE into DIR, named after ROM_PATH and the current time.
+ * Returns the path of the new file */
+pub fn save_png<B : Bus>(dir : &Path, rom_path : &str, world : &cpu::World, core : &cpu::CPU<B>,
+                scale : u32) -> io::Result<PathBuf> {
+    let path : PathBuf = dir.join(file_name(rom_path, &timestamp(), "png"));
+    write_png(&path, world, core, scale)?;
+    return Ok(path);
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,31 @@
+use super::*;
+
+#[test]
+fn test_screenshot_file_name() {
+    assert_eq!(file_name("roms/IBM_LOGO", "20230101-120000.000", "png"),
+               "IBM_LOGO-20230101-120000.000.png");
+    assert_eq!(file_name("roms/games/PONG.ch8", "1", "gif"), "PONG-1.gif");
+}
+
+#[test]
+fn test_screenshot_encode_png() {
+    let mem: memory::Mem = memory::make_memory();
+    let mut c : cpu::CPU = cpu::make_cpu(mem);
+    let mut world : cpu::World = cpu::World::new();
+    world.palette = display::Palette { on: [0x11, 0x22, 0x33], off: [0x44, 0x55, 0x66] };
+    c.display.screen[0][1] = true;
+
+    let mut out : Vec<u8> = vec![];
+    encode_png(&mut out, &world, &c, 2).unwrap();
+
+    /* Decode it back and check the pixels made it through */
+    let decoder = png::Decoder::new(&out[..]);
+    let mut reader = decoder.read_info().unwrap();
+    let mut buf : Vec<u8> = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).unwrap();
+    assert_eq!(info.width, 128);
+    assert_eq!(info.height, 64);
+    assert_eq!(&buf[0..3], &[0x44, 0x55, 0x66]); /* (0, 0) is off */
+    assert_eq!(&buf[6..9], &[0x11, 0x22, 0x33]); /* (2, 0) => chip8 (1, 0) is on */
+    assert_eq!(&buf[(128 + 3) * 3..(128 + 4) * 3], &[0x11, 0x22, 0x33]); /* (3, 1) */
+}
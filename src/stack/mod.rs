@@ -0,0 +1,165 @@
+use super::*;
+use cpu::Fault;
+use std::ops::Range;
+
+/* The call stack for 2NNN and 00EE. The real interpreters only had room for
+ * so many return addresses, 12 on the COSMAC VIP and 16 on SCHIP, and did
+ * different things when a program went past that. Which one happens here:
+ *
+ *   error   calling too deep, or returning with nothing to return to,
+ *           stops the program (the default)
+ *   wrap    the stack pointer wraps around, overwriting the oldest return
+ *           address, or returning to whatever is in the top slot
+ *   memory  like the VIP, the return addresses live in memory from 0xEA0
+ *           where the program can see and change them, 2 bytes each, big
+ *           endian. The pointer wraps like it does for wrap
+ */
+
+/* Deepest the stack can be set to */
+pub const MAX_DEPTH: usize = 64;
+
+/* How deep the interpreters' stacks went */
+pub const VIP_DEPTH: usize = 12;
+pub const SCHIP_DEPTH: usize = 16;
+
+/* Where the memory backed stack starts */
+pub const MEMORY_ADDRESS: usize = 0xEA0;
+
+/* Names of the overflow behaviours, for the command line and files */
+pub const OVERFLOW_NAMES: [&str; 3] = ["error", "wrap", "memory"];
+
+/* What happens going past either end of the stack */
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Overflow {
+    Error,
+    Wrap,
+    Memory,
+}
+
+/* A fixed depth call stack */
+#[derive(Debug)]
+#[derive(Clone, PartialEq)]
+pub struct Stack {
+    pub depth : usize, /* Return addresses it can hold */
+    pub overflow : Overflow, /* What happens going past either end */
+    slots : [u16; MAX_DEPTH], /* Return addresses, unless they live in memory */
+    sp : usize, /* Slot the next call goes in */
+    count : usize, /* Return addresses on it, wrapping only keeps the newest DEPTH */
+}
+
+/* Stack with DEPTH slots, that does OVERFLOW going past either end */
+pub fn make_stack(depth : usize, overflow : Overflow) -> Stack {
+    let s = Stack {
+        depth: depth.clamp(1, MAX_DEPTH),
+        overflow: overflow,
+        slots: [0; MAX_DEPTH],
+        sp: 0,
+        count: 0,
+    };
+    return s;
+}
+
+/* Overflow behaviour called NAME, None if there isn't one */
+pub fn parse_overflow(name : &str) -> Option<Overflow> {
+    match name {
+        "error" => return Some(Overflow::Error),
+        "wrap" => return Some(Overflow::Wrap),
+        "memory" => return Some(Overflow::Memory),
+        _ => return None,
+    }
+}
+
+impl Overflow {
+    /* Name of the behaviour, what parse_overflow takes */
+    pub fn name(&self) -> &'static str {
+        match self {
+            Overflow::Error => return "error",
+            Overflow::Wrap => return "wrap",
+            Overflow::Memory => return "memory",
+        }
+    }
+}
+
+impl Stack {
+    /* The stack pointer, which slot the next call goes in */
+    pub fn pointer(&self) -> usize {
+        return self.sp;
+    }
+
+    /* Return addresses on the stack, oldest first. The memory backed stack
+     * peeks them out of MEM, the program may have changed them */
+    pub fn entries<B : Bus>(&self, mem : &B) -> Vec<u16> {
+        let mut out : Vec<u16> = Vec::with_capacity(self.count);
+        for i in 0..self.count {
+            let slot : usize = (self.sp + self.depth - self.count + i) % self.depth;
+            let address : u16 = match self.overflow {
+                Overflow::Memory => {
+                    let at : usize = MEMORY_ADDRESS + 2 * slot;
+                    u16::from_be_bytes([mem.peek(at).unwrap_or(0), mem.peek(at + 1).unwrap_or(0)])
+                },
+                _ => self.slots[slot],
+            };
+            out.push(address);
+        }
+        return out;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.count;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.count == 0;
+    }
+
+    /* Bytes of memory the stack lives in, None unless it's memory backed */
+    pub fn memory(&self) -> Option<Range<usize>> {
+        if self.overflow != Overflow::Memory {
+            return None;
+        }
+        return Some(MEMORY_ADDRESS..MEMORY_ADDRESS + 2 * self.depth);
+    }
+
+    /* Empty the stack. The memory backed stack's bytes are left alone, like the VIP would */
+    pub fn clear(&mut self) {
+        self.slots = [0; MAX_DEPTH];
+        self.sp = 0;
+        self.count = 0;
+    }
+
+    /* Push the return address ADDRESS, for a call */
+    pub fn push<B : Bus>(&mut self, mem : &mut B, address : u16) -> Result<(), Fault> {
+        if self.overflow == Overflow::Error && self.sp == self.depth {
+            return Err(Fault::StackOverflow(self.depth));
+        }
+        if self.overflow == Overflow::Memory {
+            mem.write16(MEMORY_ADDRESS + 2 * self.sp, address.to_be_bytes())?;
+        }
+        self.slots[self.sp] = address;
+        match self.overflow {
+            Overflow::Error => self.sp += 1,
+            _ => self.sp = (self.sp + 1) % self.depth,
+        }
+        self.count = (self.count + 1).min(self.depth);
+        return Ok(());
+    }
+
+    /* Pop the address to return to */
+    pub fn pop<B : Bus>(&mut self, mem : &mut B) -> Result<u16, Fault> {
+        if self.overflow == Overflow::Error && self.sp == 0 {
+            return Err(Fault::StackUnderflow);
+        }
+        let sp : usize = (self.sp + self.depth - 1) % self.depth;
+        let address : u16 = match self.overflow {
+            Overflow::Memory => u16::from_be_bytes(mem.read16(MEMORY_ADDRESS + 2 * sp)?),
+            _ => self.slots[sp],
+        };
+        self.sp = sp;
+        self.count = self.count.saturating_sub(1);
+        return Ok(address);
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,77 @@
+use super::*;
+
+#[test]
+fn test_stack_names() {
+    for name in OVERFLOW_NAMES {
+        assert_eq!(parse_overflow(name).unwrap().name(), name);
+    }
+    assert_eq!(parse_overflow("sideways"), None);
+    assert_eq!(make_stack(0, Overflow::Error).depth, 1);
+    assert_eq!(make_stack(1000, Overflow::Error).depth, MAX_DEPTH);
+}
+
+#[test]
+fn test_stack_error() {
+    let mut m : memory::Mem = memory::make_memory();
+    let mut s : Stack = make_stack(VIP_DEPTH, Overflow::Error);
+    assert_eq!(s.pop(&mut m), Err(Fault::StackUnderflow));
+    for i in 0..VIP_DEPTH {
+        s.push(&mut m, 0x200 + 2 * i as u16).unwrap();
+    }
+    assert_eq!(s.pointer(), VIP_DEPTH);
+    assert_eq!(s.push(&mut m, 0x300), Err(Fault::StackOverflow(VIP_DEPTH)));
+    assert_eq!(s.entries(&m).last(), Some(&0x216));
+    assert_eq!(s.pop(&mut m), Ok(0x216));
+    assert_eq!(s.len(), VIP_DEPTH - 1);
+
+    /* Nothing goes in memory */
+    assert!(m.data[MEMORY_ADDRESS..MEMORY_ADDRESS + 2 * VIP_DEPTH].iter().all(|b| *b == 0));
+}
+
+#[test]
+fn test_stack_wrap() {
+    let mut m : memory::Mem = memory::make_memory();
+    let mut s : Stack = make_stack(2, Overflow::Wrap);
+
+    /* The third call overwrites the first */
+    for address in [0x202, 0x204, 0x206] {
+        s.push(&mut m, address).unwrap();
+    }
+    assert_eq!(s.pointer(), 1);
+    assert_eq!(s.entries(&m), vec![0x204, 0x206]);
+    assert_eq!(s.len(), 2);
+    assert_eq!(s.pop(&mut m), Ok(0x206));
+    assert_eq!(s.pop(&mut m), Ok(0x204));
+    assert_eq!(s.pop(&mut m), Ok(0x206));
+    assert_eq!(s.pointer(), 0);
+    assert!(s.is_empty());
+
+    /* Full to the top, the pointer wraps to 0 but the stack isn't empty */
+    s.push(&mut m, 0x208).unwrap();
+    s.push(&mut m, 0x20a).unwrap();
+    assert_eq!(s.pointer(), 0);
+    assert_eq!(s.entries(&m), vec![0x208, 0x20a]);
+}
+
+#[test]
+fn test_stack_memory() {
+    let mut m : memory::Mem = memory::make_memory();
+    let mut s : Stack = make_stack(VIP_DEPTH, Overflow::Memory);
+    s.push(&mut m, 0x234).unwrap();
+    assert_eq!(m.data[MEMORY_ADDRESS..MEMORY_ADDRESS + 2], [0x02, 0x34]);
+
+    assert_eq!(s.memory(), Some(MEMORY_ADDRESS..MEMORY_ADDRESS + 2 * VIP_DEPTH));
+    assert_eq!(make_stack(VIP_DEPTH, Overflow::Wrap).memory(), None);
+
+    /* The program can change where it returns to, and the entries show it */
+    m.data[MEMORY_ADDRESS + 1] = 0x40;
+    assert_eq!(s.entries(&m), vec![0x240]);
+    assert_eq!(s.pop(&mut m), Ok(0x240));
+
+    /* Returning with nothing there wraps to the top slot */
+    m.data[MEMORY_ADDRESS + 2 * (VIP_DEPTH - 1)..MEMORY_ADDRESS + 2 * VIP_DEPTH].copy_from_slice(&[0x03, 0x00]);
+    assert_eq!(s.pop(&mut m), Ok(0x300));
+    assert_eq!(s.pointer(), VIP_DEPTH - 1);
+    s.clear();
+    assert!(s.is_empty());
+}
@@ -0,0 +1,278 @@
+use super::*;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/* Tool assisted editing of movies. A timeline is a movie plus save points,
+ * copies of the core taken at the start of a frame. Editing a frame throws
+ * away the save points after it, so re-running only has to go back as far
+ * as the nearest save point before the edit.
+ *
+ * Edits can be scripted, one per line, frames count from 0:
+ *
+ *   insert <frame> <keys> [count]   insert COUNT frames holding KEYS before FRAME
+ *   delete <frame> [count]          delete COUNT frames starting at FRAME
+ *   set <frame> <keys>              hold KEYS on FRAME
+ *   save <frame>                    make a save point at FRAME
+ *   branch <frame>                  cut the movie off at FRAME, to try something else from there
+ *   expect <frame> pc <addr>        check the core at the start of FRAME
+ *   expect <frame> v<X> <value>
+ *   expect <frame> screen <sha1>
+ *
+ * KEYS are the chip8 keys held down as hex digits, e.g. 5a, or - for none.
+ * Addresses and values are hex too. Anything after a # is a comment */
+
+/* Biggest frame or count a script can give, about 46 hours at 60 frames a second */
+pub const MAX_FRAMES: u64 = 10_000_000;
+
+pub struct Timeline<B : Bus + Clone = memory::Mem> {
+    pub movie : movie::Movie, /* Inputs being edited */
+    saves : BTreeMap<u64, cpu::CPU<B>>, /* Core at the start of a frame, by frame */
+}
+
+/* Something to check about the core */
+#[derive(Debug)]
+#[derive(Clone, PartialEq)]
+pub enum Check {
+    Pc(u16), /* Program counter */
+    Reg(usize, u8), /* Register, value */
+    Screen(String), /* sha1 of the screen, see screen_sha1 */
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Check::Pc(pc) => write!(f, "pc {:#05x}", pc),
+            Check::Reg(x, v) => write!(f, "v{:x} {:#04x}", x, v),
+            Check::Screen(hash) => write!(f, "screen {}", hash),
+        }
+    }
+}
+
+/* One line of a script */
+#[derive(Debug)]
+#[derive(Clone, PartialEq)]
+pub enum Command {
+    Insert(u64, input::Keypad, u64),
+    Delete(u64, u64),
+    Set(u64, input::Keypad),
+    Save(u64),
+    Branch(u64),
+    Expect(u64, Check),
+}
+
+/* Bad script line, line number and what was wrong */
+#[derive(Debug)]
+pub struct ScriptError(pub usize, pub String);
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "bad tas script, line {}: {}", self.0, self.1);
+    }
+}
+
+/* Start a timeline for MOVIE. CORE should have the rom loaded and nothing run yet */
+pub fn make_timeline<B : Bus + Clone>(movie : movie::Movie, core : &cpu::CPU<B>) -> Timeline<B> {
+    let mut start : cpu::CPU<B> = core.clone();
+    movie.prepare(&mut start);
+
+    let mut t = Timeline {
+        movie: movie,
+        saves: BTreeMap::new(),
+    };
+    t.saves.insert(0, start);
+    return t;
+}
+
+/* sha1 of the screen, one byte per pixel row by row. Two runs that end up
+ * with the same picture have the same hash */
+pub fn screen_sha1(display : &display::Display) -> String {
+    let pixels : Vec<u8> = display.screen.iter().flatten().map(|p| *p as u8).collect();
+    return rom::sha1_hex(&pixels);
+}
+
+/* Parse the keys held in KEYS, like 5a, or - for none */
+pub fn parse_keys(keys : &str) -> Option<input::Keypad> {
+    let mut keypad : input::Keypad = input::Keypad { state: 0 };
+    if keys == "-" {
+        return Some(keypad);
+    }
+    for c in keys.chars() {
+        let key : u32 = c.to_digit(16)?;
+        keypad.state |= 1 << key;
+    }
+    return Some(keypad);
+}
+
+/* Read a script of edits out of TEXT */
+pub fn parse_script(text : &str) -> Result<Vec<Command>, ScriptError> {
+    let mut commands : Vec<Command> = vec![];
+    for (i, line) in text.lines().enumerate() {
+        let line : &str = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let bad = |what : &str| ScriptError(i + 1, what.to_string());
+        let words : Vec<&str> = line.split_whitespace().collect();
+        let frame = |n : usize| -> Result<u64, ScriptError> {
+            return words.get(n).and_then(|w| w.parse().ok()).filter(|f| *f <= MAX_FRAMES).ok_or(bad("bad frame"));
+        };
+        let keys = |n : usize| -> Result<input::Keypad, ScriptError> {
+            return words.get(n).and_then(|w| parse_keys(w)).ok_or(bad("bad keys"));
+        };
+        let count = |n : usize| -> Result<u64, ScriptError> {
+            match words.get(n) {
+                Some(w) => return w.parse().ok().filter(|c| *c <= MAX_FRAMES).ok_or(bad("bad count")),
+                None => return Ok(1),
+            }
+        };
+
+        let command : Command = match words[0] {
+            "insert" => Command::Insert(frame(1)?, keys(2)?, count(3)?),
+            "delete" => Command::Delete(frame(1)?, count(2)?),
+            "set" => Command::Set(frame(1)?, keys(2)?),
+            "save" => Command::Save(frame(1)?),
+            "branch" => Command::Branch(frame(1)?),
+            "expect" => {
+                let what : &str = words.get(2).copied().unwrap_or("");
+                let value : &str = words.get(3).copied().ok_or(bad("expect needs a value"))?;
+                let number = || -> Result<u16, ScriptError> {
+                    let digits : &str = value.strip_prefix("0x").unwrap_or(value);
+                    return u16::from_str_radix(digits, 16).map_err(|_| bad("value isn't hex"));
+                };
+                let check : Check = match what {
+                    "pc" => Check::Pc(number()?),
+                    "screen" => Check::Screen(value.to_lowercase()),
+                    _ => {
+                        let reg : Option<usize> = what.strip_prefix('v')
+                            .and_then(|x| usize::from_str_radix(x, 16).ok())
+                            .filter(|x| *x < 16);
+                        match (reg, number()?) {
+                            (Some(x), v) if v <= 0xff => Check::Reg(x, v as u8),
+                            _ => return Err(bad("can only expect pc, v0-vf or screen")),
+                        }
+                    },
+                };
+                Command::Expect(frame(1)?, check)
+            },
+            _ => return Err(bad("unknown command")),
+        };
+        commands.push(command);
+    }
+    return Ok(commands);
+}
+
+/* Run COMMANDS on TIMELINE in order. Returns a message for every expect
+ * that didn't hold, nothing means the run went as expected */
+pub fn run_script<B : Bus + Clone>(timeline : &mut Timeline<B>, commands : &[Command]) -> Vec<String> {
+    let mut failures : Vec<String> = vec![];
+    for command in commands.iter() {
+        match command {
+            Command::Insert(frame, keypad, count) => timeline.insert(*frame, *keypad, *count),
+            Command::Delete(frame, count) => timeline.delete(*frame, *count),
+            Command::Set(frame, keypad) => timeline.set(*frame, *keypad),
+            Command::Save(frame) => timeline.save(*frame),
+            Command::Branch(frame) => *timeline = timeline.branch(*frame),
+            Command::Expect(frame, check) => {
+                if let Err(found) = timeline.check(*frame, check) {
+                    failures.push(format!("frame {}: expected {}, found {}", frame, check, found));
+                }
+            },
+        }
+    }
+    return failures;
+}
+
+impl<B : Bus + Clone> Timeline<B> {
+    /* Insert COUNT frames holding KEYPAD before FRAME */
+    pub fn insert(&mut self, frame : u64, keypad : input::Keypad, count : u64) {
+        self.pad_to(frame);
+        let at : usize = frame as usize;
+        let new_frames = std::iter::repeat_n(keypad.state, count as usize);
+        self.movie.frames.splice(at..at, new_frames);
+        self.edited(frame);
+    }
+
+    /* Delete COUNT frames starting at FRAME */
+    pub fn delete(&mut self, frame : u64, count : u64) {
+        let len : usize = self.movie.frames.len();
+        let start : usize = (frame as usize).min(len);
+        let end : usize = frame.saturating_add(count).min(len as u64) as usize;
+        self.movie.frames.drain(start..end);
+        self.edited(frame);
+    }
+
+    /* Hold KEYPAD on FRAME, the movie grows if it isn't that long yet */
+    pub fn set(&mut self, frame : u64, keypad : input::Keypad) {
+        self.pad_to(frame.saturating_add(1));
+        self.movie.frames[frame as usize] = keypad.state;
+        self.edited(frame);
+    }
+
+    /* Make a save point at the start of FRAME */
+    pub fn save(&mut self, frame : u64) {
+        let core : cpu::CPU<B> = self.state_at(frame);
+        self.saves.insert(frame, core);
+    }
+
+    /* Copy of the timeline cut off at FRAME, keeping the save points up to there */
+    pub fn branch(&self, frame : u64) -> Timeline<B> {
+        let mut movie : movie::Movie = self.movie.clone();
+        movie.frames.truncate(frame as usize);
+        let mut t = Timeline {
+            movie: movie,
+            saves: BTreeMap::new(),
+        };
+        for (f, core) in self.saves.range(..=frame) {
+            t.saves.insert(*f, core.clone());
+        }
+        return t;
+    }
+
+    /* The core at the start of FRAME, run from the nearest save point.
+     * Frames past the end of the movie have nothing held */
+    pub fn state_at(&self, frame : u64) -> cpu::CPU<B> {
+        let (start, save) = self.saves.range(..=frame).next_back().unwrap();
+        let mut core : cpu::CPU<B> = save.clone();
+        for f in *start..frame {
+            let keypad : input::Keypad = self.movie.keypad(f).unwrap_or(input::Keypad { state: 0 });
+            if !core.run_frame(self.movie.ipf, keypad.input_key()) {
+                break;
+            }
+        }
+        return core;
+    }
+
+    /* Re-run up to FRAME and see if CHECK holds. Err has what was found instead */
+    pub fn check(&self, frame : u64, check : &Check) -> Result<(), String> {
+        let core : cpu::CPU<B> = self.state_at(frame);
+        match check {
+            Check::Pc(pc) if core.pc == *pc => return Ok(()),
+            Check::Pc(_) => return Err(format!("pc {:#05x}", core.pc)),
+            Check::Reg(x, v) if core.regs[*x] == *v => return Ok(()),
+            Check::Reg(x, _) => return Err(format!("v{:x} {:#04x}", x, core.regs[*x])),
+            Check::Screen(hash) => {
+                let found : String = screen_sha1(&core.display);
+                if found == *hash {
+                    return Ok(());
+                }
+                return Err(format!("screen {}", found));
+            },
+        }
+    }
+
+    /* Grow the movie to LEN frames with nothing held */
+    fn pad_to(&mut self, len : u64) {
+        if (self.movie.frames.len() as u64) < len {
+            self.movie.frames.resize(len as usize, 0);
+        }
+    }
+
+    /* FRAME changed, save points after it are out of date. A save point at
+     * FRAME itself is from before FRAME's input, so it stays */
+    fn edited(&mut self, frame : u64) {
+        self.saves.retain(|f, _| *f <= frame);
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,101 @@
+use super::*;
+
+fn make_test_timeline(frames : Vec<u16>) -> Timeline {
+    let c : cpu::CPU = movie::test::make_test_cpu();
+    let mut m : movie::Movie = movie::make_movie("abc", &c, 10);
+    m.seed = 42;
+    m.frames = frames;
+    return make_timeline(m, &c);
+}
+
+#[test]
+fn test_tas_parse_script() {
+    let script : &str = "# press 5 for a bit\n\
+                         insert 10 5a 3\n\
+                         delete 2   # gone\n\
+                         set 4 -\n\
+                         save 8\n\
+                         branch 20\n\
+                         expect 30 pc 0x204\n\
+                         expect 30 vf 1\n\
+                         expect 30 screen ABC\n";
+    let commands : Vec<Command> = parse_script(script).unwrap();
+    assert_eq!(commands, vec![
+        Command::Insert(10, input::Keypad { state: 0x0420 }, 3),
+        Command::Delete(2, 1),
+        Command::Set(4, input::Keypad { state: 0 }),
+        Command::Save(8),
+        Command::Branch(20),
+        Command::Expect(30, Check::Pc(0x204)),
+        Command::Expect(30, Check::Reg(0xf, 1)),
+        Command::Expect(30, Check::Screen(String::from("abc"))),
+    ]);
+
+    assert_eq!(parse_script("jump 3").unwrap_err().0, 1);
+    assert_eq!(parse_script("\nset x 5").unwrap_err().0, 2);
+    assert_eq!(parse_script("set 1 5g").unwrap_err().0, 1);
+    assert_eq!(parse_script("expect 1 vg 1").unwrap_err().0, 1);
+    assert_eq!(parse_script("expect 1 v1 100").unwrap_err().0, 1);
+
+    /* Frames and counts too big to ever fit in memory */
+    assert_eq!(parse_script("delete 5 18446744073709551615").unwrap_err().1, "bad count");
+    assert_eq!(parse_script("set 18446744073709551615 -").unwrap_err().1, "bad frame");
+    assert_eq!(parse_script("insert 0 1 1000000000000").unwrap_err().1, "bad count");
+    assert!(parse_script(&format!("insert {} 1 {}", MAX_FRAMES, MAX_FRAMES)).is_ok());
+}
+
+#[test]
+fn test_tas_edits() {
+    let mut t : Timeline = make_test_timeline(vec![1, 2, 3]);
+    t.insert(1, input::Keypad { state: 9 }, 2);
+    assert_eq!(t.movie.frames, vec![1, 9, 9, 2, 3]);
+    t.delete(3, 5);
+    assert_eq!(t.movie.frames, vec![1, 9, 9]);
+    t.set(5, input::Keypad { state: 7 });
+    assert_eq!(t.movie.frames, vec![1, 9, 9, 0, 0, 7]);
+    t.insert(8, input::Keypad { state: 4 }, 1);
+    assert_eq!(t.movie.frames, vec![1, 9, 9, 0, 0, 7, 0, 0, 4]);
+    t.delete(5, u64::MAX);
+    assert_eq!(t.movie.frames, vec![1, 9, 9, 0, 0]);
+}
+
+#[test]
+fn test_tas_save_points() {
+    let frames : Vec<u16> = (0..40).map(|f| if f % 5 == 0 { 1 << (f % 16) } else { 0 }).collect();
+    let mut t : Timeline = make_test_timeline(frames.clone());
+    t.save(10);
+    t.save(20);
+    t.save(30);
+    assert_eq!(t.saves.keys().copied().collect::<Vec<u64>>(), vec![0, 10, 20, 30]);
+
+    /* Running from a save point ends up the same as running from the start */
+    let from_save : cpu::CPU = t.state_at(35);
+    let from_start : cpu::CPU = make_test_timeline(frames).state_at(35);
+    assert_eq!(from_save.regs, from_start.regs);
+    assert_eq!(from_save.pc, from_start.pc);
+    assert_eq!(from_save.display.screen, from_start.display.screen);
+
+    /* Editing frame 20 keeps the save point at 20, but not the one after */
+    t.set(20, input::Keypad { state: 0x0004 });
+    assert_eq!(t.saves.keys().copied().collect::<Vec<u64>>(), vec![0, 10, 20]);
+
+    let b : Timeline = t.branch(15);
+    assert_eq!(b.movie.frames.len(), 15);
+    assert_eq!(b.saves.keys().copied().collect::<Vec<u64>>(), vec![0, 10]);
+    assert_eq!(t.movie.frames.len(), 40);
+}
+
+#[test]
+fn test_tas_run_script() {
+    let mut t : Timeline = make_test_timeline(vec![]);
+
+    /* Hold key 3 on frame 1, the program stores it in v1 and draws */
+    let script : &str = "set 1 3\nsave 1\nexpect 2 v1 3\nexpect 2 v1 9\n";
+    let failures : Vec<String> = run_script(&mut t, &parse_script(script).unwrap());
+    assert_eq!(failures.len(), 1);
+    assert!(failures[0].starts_with("frame 2: expected v1 0x09, found v1 0x03"));
+
+    let screen : String = screen_sha1(&t.state_at(2).display);
+    let script : String = format!("expect 2 screen {}\nbranch 1\nexpect 2 v1 0\n", screen);
+    assert!(run_script(&mut t, &parse_script(&script).unwrap()).is_empty());
+}
@@ -0,0 +1,165 @@
+use super::*;
+use std::io::{ self, Write };
+use std::thread;
+use std::time::{ Duration, Instant };
+
+use crossterm::{
+    cursor::{ Hide, MoveTo, Show },
+    event::{ self, Event, KeyCode, KeyEventKind, KeyModifiers },
+    execute, queue,
+    style::Print,
+    terminal::{ self, EnterAlternateScreen, LeaveAlternateScreen },
+};
+
+/* Terminal frontend. Renders Display::screen with unicode characters and
+ * reads the keypad from the terminal in raw mode, so the emulator can be
+ * used over ssh on machines without a display server. */
+
+/* Which characters to draw the screen with */
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum Glyphs {
+    HalfBlock, /* 1x2 pixels per character => 64x16 characters */
+    Braille, /* 2x4 pixels per character => 32x8 characters */
+}
+
+/* Frames are 1/60th of a second */
+const FRAME_TIME: Duration = Duration::from_micros(16_667);
+
+/* Terminals don't tell us when a key is let go, so a key counts as held
+ * for this many frames after the last time we saw it. Key repeat will
+ * keep it held for as long as it is pushed down */
+const KEY_HOLD_FRAMES: u8 = 10;
+
+/* Name of the key that types C, same names as the keymap uses */
+pub fn key_name(c : char) -> Option<String> {
+    let name : String = match c {
+        '0'..='9' => format!("Key{}", c),
+        'a'..='z' | 'A'..='Z' => c.to_ascii_uppercase().to_string(),
+        ' ' => String::from("Space"),
+        '\'' => String::from("Apostrophe"),
+        ',' => String::from("Comma"),
+        '.' => String::from("Period"),
+        ';' => String::from("Semicolon"),
+        '/' => String::from("Slash"),
+        '-' => String::from("Minus"),
+        '=' => String::from("Equals"),
+        '[' => String::from("LBracket"),
+        ']' => String::from("RBracket"),
+        _ => return None,
+    };
+    return Some(name);
+}
+
+/* Map a key typed in the terminal to the CHIP-8 keypad with KEYMAP */
+pub fn map_key(keymap : &keymap::Keymap, c : char) -> Option<u8> {
+    return keymap.chip8_key(&key_name(c)?);
+}
+
+/* Draw SCREEN with half blocks, top pixel in the upper half of the character */
+pub fn render_half_blocks(screen : &[[bool; 64]; 32]) -> String {
+    let mut out : String = String::new();
+    for row in (0..screen.len()).step_by(2) {
+        for (top, bottom) in screen[row].iter().zip(screen[row + 1].iter()) {
+            let c : char = match (*top, *bottom) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (false, false) => ' ',
+            };
+            out.push(c);
+        }
+        out.push_str("\r\n");
+    }
+    return out;
+}
+
+/* Draw SCREEN with braille, each character is a 2 wide and 4 tall block of dots */
+pub fn render_braille(screen : &[[bool; 64]; 32]) -> String {
+    /* Bit for the dot at [row][col] inside of a braille character */
+    const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+    let mut out : String = String::new();
+    for row in (0..screen.len()).step_by(4) {
+        for col in (0..screen[row].len()).step_by(2) {
+            let mut bits : u32 = 0;
+            for dy in 0..4 {
+                for dx in 0..2 {
+                    if screen[row + dy][col + dx] { bits |= DOTS[dy][dx]; }
+                }
+            }
+            out.push(char::from_u32(0x2800 + bits).unwrap());
+        }
+        out.push_str("\r\n");
+    }
+    return out;
+}
+
+/* Run CORE in the terminal at IPF instructions a frame, until escape or ctrl-c is
+ * pushed. Rings the bell when the sound timer starts if AUDIO */
+pub fn run<B : Bus>(core : &mut cpu::CPU<B>, keymap : &keymap::Keymap, glyphs : Glyphs, ipf : u16,
+           audio : bool) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(stdout, EnterAlternateScreen, Hide)?;
+
+    let result = run_loop(core, keymap, glyphs, ipf, audio, &mut stdout);
+
+    /* Always give the terminal back, even if the loop failed */
+    execute!(stdout, Show, LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    return result;
+}
+
+/* Frame loop: read keys, execute a frames worth of instructions, draw */
+fn run_loop<B : Bus>(core : &mut cpu::CPU<B>, keymap : &keymap::Keymap, glyphs : Glyphs, ipf : u16,
+            audio : bool, stdout : &mut io::Stdout) -> io::Result<()> {
+    let mut held_key : u8 = 0xf0;
+    let mut held_frames : u8 = 0;
+    loop {
+        let frame_start : Instant = Instant::now();
+
+        /* Drain all the input that came in since last frame */
+        while event::poll(Duration::from_millis(0))? {
+            if let Event::Key(key) = event::read()? {
+                let ctrl_c = key.code == KeyCode::Char('c') &&
+                    key.modifiers.contains(KeyModifiers::CONTROL);
+                if key.code == KeyCode::Esc || ctrl_c {
+                    return Ok(());
+                }
+                if let KeyCode::Char(c) = key.code {
+                    if let Some(k) = map_key(keymap, c) {
+                        if key.kind == KeyEventKind::Release {
+                            if k == held_key { held_frames = 0; }
+                        } else {
+                            held_key = k;
+                            held_frames = KEY_HOLD_FRAMES;
+                        }
+                    }
+                }
+            }
+        }
+        if held_frames == 0 { held_key = 0xf0; }
+
+        let was_beeping : bool = core.sound_timer.is_running();
+        core.run_frame(ipf, held_key);
+        let bell : &str = if audio && !was_beeping && core.sound_timer.is_running() { "\x07" } else { "" };
+        held_frames = held_frames.saturating_sub(1);
+
+        let frame : String = match glyphs {
+            Glyphs::HalfBlock => render_half_blocks(&core.display.screen),
+            Glyphs::Braille => render_braille(&core.display.screen),
+        };
+        queue!(stdout, MoveTo(0, 0), Print(frame), Print("Esc to quit"), Print(bell))?;
+        stdout.flush()?;
+
+        /* Sleep off the rest of the frame */
+        let elapsed : Duration = frame_start.elapsed();
+        if elapsed < FRAME_TIME {
+            thread::sleep(FRAME_TIME - elapsed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
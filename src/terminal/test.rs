@@ -0,0 +1,45 @@
+use super::*;
+
+#[test]
+fn test_terminal_map_key() {
+    let qwerty : keymap::Keymap = keymap::layout("qwerty").unwrap();
+    assert_eq!(map_key(&qwerty, '1'), Some(0x01));
+    assert_eq!(map_key(&qwerty, '4'), Some(0x0c));
+    assert_eq!(map_key(&qwerty, 'x'), Some(0x00));
+    assert_eq!(map_key(&qwerty, 'V'), Some(0x0f));
+    assert_eq!(map_key(&qwerty, 'p'), None);
+
+    let dvorak : keymap::Keymap = keymap::layout("dvorak").unwrap();
+    assert_eq!(map_key(&dvorak, '\''), Some(0x04));
+    assert_eq!(map_key(&dvorak, ';'), Some(0x0a));
+}
+
+#[test]
+fn test_terminal_half_blocks() {
+    let mut d : display::Display = display::make_display();
+    d.screen[0][0] = true;
+    d.screen[1][0] = true;
+    d.screen[0][1] = true;
+    d.screen[3][2] = true;
+
+    let out : String = render_half_blocks(&d.screen);
+    let lines : Vec<&str> = out.split("\r\n").collect();
+    assert_eq!(lines.len(), 17); /* 16 rows + whatever is after the last newline */
+    assert_eq!(lines[0].chars().count(), 64);
+    assert!(lines[0].starts_with("█▀ "));
+    assert!(lines[1].starts_with("  ▄ "));
+}
+
+#[test]
+fn test_terminal_braille() {
+    let mut d : display::Display = display::make_display();
+    d.screen[0][0] = true; /* dot 1 */
+    d.screen[3][1] = true; /* dot 8 */
+
+    let out : String = render_braille(&d.screen);
+    let lines : Vec<&str> = out.split("\r\n").collect();
+    assert_eq!(lines.len(), 9);
+    assert_eq!(lines[0].chars().count(), 32);
+    assert_eq!(lines[0].chars().next(), Some('\u{2881}'));
+    assert_eq!(lines[1].chars().next(), Some('\u{2800}'));
+}